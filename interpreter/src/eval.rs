@@ -1,29 +1,68 @@
+use std::path::Path;
+
+use tracing::{span, Level};
+
 use eval_error::EvalError;
 use objects::{EnvReference, Object};
 
 use crate::parser::{
-    lexer::lexedtokens::LexedTokens, parse_errors::ParseError, ParsedProgram, Parser,
+    ast::Statement, lexer::lexedtokens::LexedTokens, parse_errors::StatementError, ParsedProgram,
+    Parser,
 };
 
-mod eval_error;
+pub mod builtins;
+pub mod constant_folding;
+pub mod context;
+pub mod eval_error;
 mod expression_evaluator;
 pub mod function_evaluator;
+mod import_evaluator;
 pub mod objects;
 mod statement_evaluator;
 
 pub enum EvaledProgram {
-    ParseError(Vec<ParseError>),
+    ParseError(Vec<StatementError>),
     EvalError(EvalError),
     Valid(Object),
 }
 
 pub fn eval(input: &str, env: &mut EnvReference) -> EvaledProgram {
     let lexed_tokens = LexedTokens::from(input);
-    let program = Parser::parse_tokens(lexed_tokens);
+    eval_program(Parser::parse_tokens(lexed_tokens), env)
+}
+
+/// Evaluates the input like `eval`, but only keeps the value when the last
+/// statement is an expression statement, as that is the value a REPL shows.
+/// Otherwise a valid program evaluates to `Object::Void`.
+pub fn eval_printable(input: &str, env: &mut EnvReference) -> EvaledProgram {
+    let program = Parser::parse_tokens(LexedTokens::from(input));
+    let ends_with_expression = match &program {
+        ParsedProgram::ValidProgram(statements) => {
+            matches!(statements.last(), Some(Statement::Expression(_)))
+        }
+        ParsedProgram::InvalidProgram(_) => false,
+    };
+
+    match eval_program(program, env) {
+        EvaledProgram::Valid(_) if !ends_with_expression => EvaledProgram::Valid(Object::Void),
+        evaled => evaled,
+    }
+}
+
+/// Evaluates the input with a parser that is reused between calls, which
+/// avoids allocating new source buffers when running many programs.
+pub fn eval_with_parser(parser: &mut Parser, input: &str, env: &mut EnvReference) -> EvaledProgram {
+    parser.reset(input);
+    eval_program(parser.parse(), env)
+}
 
+fn eval_program(program: ParsedProgram, env: &mut EnvReference) -> EvaledProgram {
     match program {
         ParsedProgram::InvalidProgram(parse_errors) => EvaledProgram::ParseError(parse_errors),
         ParsedProgram::ValidProgram(valid_program) => {
+            let eval_span = span!(Level::DEBUG, "Evaluate", statements = valid_program.len());
+            let _enter = eval_span.enter();
+
             let evaled = statement_evaluator::eval_statements(&valid_program, env);
 
             match evaled {
@@ -33,3 +72,146 @@ pub fn eval(input: &str, env: &mut EnvReference) -> EvaledProgram {
         }
     }
 }
+
+/// Evaluates the input in the given environment and hands the environment
+/// back, so bindings made by the input can be used by the next evaluation.
+pub fn eval_with_env(input: &str, mut env: EnvReference) -> (EvaledProgram, EnvReference) {
+    let evaled = eval(input, &mut env);
+    (evaled, env)
+}
+
+pub fn eval_file(path: &Path, env: &mut EnvReference) -> EvaledProgram {
+    let (path, source) = match import_evaluator::read_source(path) {
+        Ok(found_source) => found_source,
+        Err(import_error) => return EvaledProgram::EvalError(import_error),
+    };
+
+    env.borrow().context().borrow_mut().push_import(path);
+    let evaled = eval(&source, env);
+    env.borrow().context().borrow_mut().pop_import();
+
+    evaled
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use tracing::{
+        field::{Field, Visit},
+        span::{Attributes, Id, Record},
+        Subscriber,
+    };
+    use tracing_subscriber::{
+        layer::{Context, SubscriberExt},
+        registry::{LookupSpan, Registry},
+        Layer,
+    };
+
+    use crate::{
+        eval::{
+            self,
+            objects::{Environment, Object},
+            EvaledProgram,
+        },
+        parser::Parser,
+    };
+
+    type RecordedFields = Arc<Mutex<Vec<String>>>;
+
+    struct CapturingLayer {
+        recorded: RecordedFields,
+    }
+
+    struct FieldVisitor<'a> {
+        span_name: &'a str,
+        recorded: &'a RecordedFields,
+    }
+
+    impl Visit for FieldVisitor<'_> {
+        fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+            self.recorded
+                .lock()
+                .expect("Should lock recorded fields")
+                .push(format!("{} {}={value:?}", self.span_name, field.name()));
+        }
+    }
+
+    impl<S: Subscriber + for<'a> LookupSpan<'a>> Layer<S> for CapturingLayer {
+        fn on_new_span(&self, attrs: &Attributes<'_>, _id: &Id, _ctx: Context<'_, S>) {
+            attrs.record(&mut FieldVisitor {
+                span_name: attrs.metadata().name(),
+                recorded: &self.recorded,
+            });
+        }
+
+        fn on_record(&self, id: &Id, values: &Record<'_>, ctx: Context<'_, S>) {
+            if let Some(span) = ctx.span(id) {
+                values.record(&mut FieldVisitor {
+                    span_name: span.name(),
+                    recorded: &self.recorded,
+                });
+            }
+        }
+    }
+
+    #[test]
+    fn parse_and_eval_spans_test() {
+        let recorded: RecordedFields = Arc::new(Mutex::new(Vec::new()));
+        let subscriber = Registry::default().with(CapturingLayer {
+            recorded: Arc::clone(&recorded),
+        });
+
+        tracing::subscriber::with_default(subscriber, || {
+            eval::eval("let a: 5. a + 1", &mut Environment::new_env_reference());
+        });
+
+        let recorded = recorded.lock().expect("Should lock recorded fields");
+        assert!(recorded.contains(&String::from("Parse statements=2")));
+        assert!(recorded.contains(&String::from("Evaluate statements=2")));
+    }
+
+    #[test]
+    fn eval_with_env_keeps_bindings_test() {
+        let (evaled, env) = eval::eval_with_env("let x: 5.", Environment::new_env_reference());
+        assert!(matches!(evaled, EvaledProgram::Valid(_)));
+
+        let (evaled, _) = eval::eval_with_env("x + 1", env);
+        assert!(matches!(evaled, EvaledProgram::Valid(Object::Integer(6))));
+    }
+
+    #[test]
+    fn eval_with_reused_parser_test() {
+        let mut parser = Parser::new();
+
+        for number in 0..100 {
+            let input = format!("let x: {number}. x * 2");
+            let evaled =
+                eval::eval_with_parser(&mut parser, &input, &mut Environment::new_env_reference());
+
+            match evaled {
+                EvaledProgram::Valid(Object::Integer(integer)) => assert_eq!(number * 2, integer),
+                _ => panic!("Expected integer for '{input}'"),
+            }
+        }
+    }
+
+    #[test]
+    fn eval_printable_test() {
+        let env = &mut Environment::new_env_reference();
+
+        let input_expected = [
+            ("5 + 5", Object::Integer(10)),
+            ("let x: 5.", Object::Void),
+            ("x", Object::Integer(5)),
+            ("return x.", Object::Void),
+            ("let y: 1. y + x", Object::Integer(6)),
+        ];
+        for (input, expected) in input_expected {
+            match eval::eval_printable(input, env) {
+                EvaledProgram::Valid(object) => assert_eq!(expected, object, "Wrong for '{input}'"),
+                _ => panic!("Expected '{input}' to evaluate"),
+            }
+        }
+    }
+}
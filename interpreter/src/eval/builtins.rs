@@ -0,0 +1,1861 @@
+use std::{
+    cmp::Ordering,
+    fmt::{Debug, Display},
+    iter, ptr,
+    rc::Rc,
+    slice,
+};
+
+use crate::parser::ast::Operator;
+
+use super::{
+    eval_error::EvalError,
+    function_evaluator,
+    objects::{EnvReference, HashKey, Object},
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Builtin {
+    Len,
+    First,
+    Last,
+    Push,
+    Rest,
+    Puts,
+    Type,
+    Bool,
+    Int,
+    Str,
+    Map,
+    Reduce,
+    Filter,
+    Assert,
+    Range,
+    Split,
+    Join,
+    Contains,
+    Trim,
+    TrimStart,
+    TrimEnd,
+    Upper,
+    Lower,
+    Slice,
+    Keys,
+    Values,
+    Arity,
+    Abs,
+    Min,
+    Max,
+    Error,
+    Get,
+    Zip,
+    Repeat,
+    Chars,
+    Ord,
+    Chr,
+    Sort,
+    Format,
+    Lines,
+    ReadLine,
+    Now,
+    Native(Rc<NativeBuiltin>),
+}
+
+pub type NativeFunction = Box<dyn Fn(Vec<Object>) -> Result<Object, EvalError>>;
+
+/// A built-in provided by the program embedding the interpreter, rather than
+/// by the language itself.
+pub struct NativeBuiltin {
+    name: String,
+    function: NativeFunction,
+}
+
+impl NativeBuiltin {
+    pub fn new(name: &str, function: NativeFunction) -> NativeBuiltin {
+        NativeBuiltin {
+            name: String::from(name),
+            function,
+        }
+    }
+}
+
+/// Functions can not be compared, so a native built-in is only equal to
+/// itself.
+impl PartialEq for NativeBuiltin {
+    fn eq(&self, other: &Self) -> bool {
+        ptr::eq(self, other)
+    }
+}
+
+impl Debug for NativeBuiltin {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "NativeBuiltin({})", self.name)
+    }
+}
+
+impl Builtin {
+    pub fn lookup(identifier: &str) -> Option<Builtin> {
+        match identifier {
+            "len" => Some(Builtin::Len),
+            "first" => Some(Builtin::First),
+            "last" => Some(Builtin::Last),
+            "push" => Some(Builtin::Push),
+            "rest" => Some(Builtin::Rest),
+            "puts" => Some(Builtin::Puts),
+            "type" => Some(Builtin::Type),
+            "bool" => Some(Builtin::Bool),
+            "int" => Some(Builtin::Int),
+            "str" => Some(Builtin::Str),
+            "map" => Some(Builtin::Map),
+            "reduce" => Some(Builtin::Reduce),
+            "filter" => Some(Builtin::Filter),
+            "assert" => Some(Builtin::Assert),
+            "range" => Some(Builtin::Range),
+            "split" => Some(Builtin::Split),
+            "join" => Some(Builtin::Join),
+            "contains" => Some(Builtin::Contains),
+            "trim" => Some(Builtin::Trim),
+            "trimStart" => Some(Builtin::TrimStart),
+            "trimEnd" => Some(Builtin::TrimEnd),
+            "upper" => Some(Builtin::Upper),
+            "lower" => Some(Builtin::Lower),
+            "slice" => Some(Builtin::Slice),
+            "keys" => Some(Builtin::Keys),
+            "values" => Some(Builtin::Values),
+            "arity" => Some(Builtin::Arity),
+            "abs" => Some(Builtin::Abs),
+            "min" => Some(Builtin::Min),
+            "max" => Some(Builtin::Max),
+            "error" => Some(Builtin::Error),
+            "get" => Some(Builtin::Get),
+            "zip" => Some(Builtin::Zip),
+            "repeat" => Some(Builtin::Repeat),
+            "chars" => Some(Builtin::Chars),
+            "ord" => Some(Builtin::Ord),
+            "chr" => Some(Builtin::Chr),
+            "sort" => Some(Builtin::Sort),
+            "format" => Some(Builtin::Format),
+            "lines" => Some(Builtin::Lines),
+            "readLine" => Some(Builtin::ReadLine),
+            "now" => Some(Builtin::Now),
+            _ => None,
+        }
+    }
+
+    pub fn call(&self, args: &[Object], env: &EnvReference) -> Result<Object, EvalError> {
+        match self {
+            Builtin::Len => self.len(args),
+            Builtin::First => self.first(args),
+            Builtin::Last => self.last(args),
+            Builtin::Push => self.push(args),
+            Builtin::Rest => self.rest(args),
+            Builtin::Puts => self.puts(args, env),
+            Builtin::Type => self.type_of(args),
+            Builtin::Bool => self.bool(args),
+            Builtin::Int => self.int(args),
+            Builtin::Str => self.str(args),
+            Builtin::Map => self.map(args, env),
+            Builtin::Reduce => self.reduce(args, env),
+            Builtin::Filter => self.filter(args, env),
+            Builtin::Assert => self.assert(args),
+            Builtin::Range => self.range(args),
+            Builtin::Split => self.split(args),
+            Builtin::Join => self.join(args),
+            Builtin::Contains => self.contains(args),
+            Builtin::Trim => self.transform_string(args, |string| string.trim().to_string()),
+            Builtin::TrimStart => {
+                self.transform_string(args, |string| string.trim_start().to_string())
+            }
+            Builtin::TrimEnd => self.transform_string(args, |string| string.trim_end().to_string()),
+            Builtin::Upper => self.transform_string(args, str::to_uppercase),
+            Builtin::Lower => self.transform_string(args, str::to_lowercase),
+            Builtin::Slice => self.slice(args),
+            Builtin::Keys => self.keys(args),
+            Builtin::Values => self.values(args),
+            Builtin::Arity => self.arity(args),
+            Builtin::Abs => self.abs(args),
+            Builtin::Min => self.extremum(args, Ordering::Less),
+            Builtin::Max => self.extremum(args, Ordering::Greater),
+            Builtin::Error => self.error(args),
+            Builtin::Get => self.get(args),
+            Builtin::Zip => self.zip(args),
+            Builtin::Repeat => self.repeat(args),
+            Builtin::Chars => self.chars(args),
+            Builtin::Ord => self.ord(args),
+            Builtin::Chr => self.chr(args),
+            Builtin::Sort => self.sort(args, env),
+            Builtin::Format => self.format(args),
+            Builtin::Lines => self.lines(args),
+            Builtin::ReadLine => self.read_line(args, env),
+            Builtin::Now => self.now(args, env),
+            Builtin::Native(native) => (native.function)(args.to_vec()),
+        }
+    }
+
+    fn len(&self, args: &[Object]) -> Result<Object, EvalError> {
+        match self.single_argument(args)? {
+            Object::Array(elements) => Ok(Object::Integer(elements.len() as i32)),
+            Object::Str(string) => Ok(Object::Integer(string.chars().count() as i32)),
+            unexpected_object => Err(self.wrong_type(unexpected_object)),
+        }
+    }
+
+    fn first(&self, args: &[Object]) -> Result<Object, EvalError> {
+        match self.single_argument(args)? {
+            Object::Array(elements) => Ok(elements.first().cloned().unwrap_or(Object::Null)),
+            unexpected_object => Err(self.wrong_type(unexpected_object)),
+        }
+    }
+
+    fn last(&self, args: &[Object]) -> Result<Object, EvalError> {
+        match self.single_argument(args)? {
+            Object::Array(elements) => Ok(elements.last().cloned().unwrap_or(Object::Null)),
+            unexpected_object => Err(self.wrong_type(unexpected_object)),
+        }
+    }
+
+    fn push(&self, args: &[Object]) -> Result<Object, EvalError> {
+        self.expect_argument_count(args, 2)?;
+
+        match &args[0] {
+            Object::Array(elements) => {
+                let mut pushed_elements = elements.clone();
+                pushed_elements.push(args[1].clone());
+                Ok(Object::Array(pushed_elements))
+            }
+            unexpected_object => Err(self.wrong_type(unexpected_object)),
+        }
+    }
+
+    fn rest(&self, args: &[Object]) -> Result<Object, EvalError> {
+        match self.single_argument(args)? {
+            Object::Array(elements) => match elements.split_first() {
+                Some((_, rest)) => Ok(Object::Array(rest.to_vec())),
+                None => Ok(Object::Null),
+            },
+            unexpected_object => Err(self.wrong_type(unexpected_object)),
+        }
+    }
+
+    fn puts(&self, args: &[Object], env: &EnvReference) -> Result<Object, EvalError> {
+        for arg in args {
+            env.borrow()
+                .context()
+                .borrow()
+                .write_output(&arg.to_print_string())?;
+        }
+
+        Ok(Object::Void)
+    }
+
+    fn type_of(&self, args: &[Object]) -> Result<Object, EvalError> {
+        let object = self.single_argument(args)?;
+        Ok(Object::Str(object.type_name().to_string()))
+    }
+
+    fn bool(&self, args: &[Object]) -> Result<Object, EvalError> {
+        let object = self.single_argument(args)?;
+        Ok(Object::Boolean(object.is_truthy()))
+    }
+
+    fn int(&self, args: &[Object]) -> Result<Object, EvalError> {
+        let conversion_error = |object: &Object| EvalError::ConversionError {
+            from: object.clone(),
+            to: String::from("integer"),
+        };
+
+        match self.single_argument(args)? {
+            Object::Integer(integer) => Ok(Object::Integer(*integer)),
+            Object::Str(string) => match string.parse::<i32>() {
+                Ok(integer) => Ok(Object::Integer(integer)),
+                Err(_) => Err(conversion_error(&args[0])),
+            },
+            Object::Float(float) => {
+                let truncated = float.trunc();
+                if truncated.is_finite()
+                    && truncated >= i32::MIN as f64
+                    && truncated <= i32::MAX as f64
+                {
+                    Ok(Object::Integer(truncated as i32))
+                } else {
+                    Err(conversion_error(&args[0]))
+                }
+            }
+            unexpected_object => Err(self.wrong_type(unexpected_object)),
+        }
+    }
+
+    fn str(&self, args: &[Object]) -> Result<Object, EvalError> {
+        let object = self.single_argument(args)?;
+        Ok(Object::Str(object.to_print_string()))
+    }
+
+    fn map(&self, args: &[Object], env: &EnvReference) -> Result<Object, EvalError> {
+        self.expect_argument_count(args, 2)?;
+        let function = self.expect_function(&args[0])?;
+
+        match &args[1] {
+            Object::Array(elements) => Ok(Object::Array(
+                elements
+                    .iter()
+                    .map(|element| {
+                        function_evaluator::apply_function(function, slice::from_ref(element), env)
+                    })
+                    .collect::<Result<Vec<Object>, EvalError>>()?,
+            )),
+            unexpected_object => Err(self.wrong_type(unexpected_object)),
+        }
+    }
+
+    fn expect_function<'a>(&self, object: &'a Object) -> Result<&'a Object, EvalError> {
+        match object {
+            Object::Function(_) | Object::Builtin(_) => Ok(object),
+            unexpected_object => Err(self.wrong_type(unexpected_object)),
+        }
+    }
+
+    fn reduce(&self, args: &[Object], env: &EnvReference) -> Result<Object, EvalError> {
+        self.expect_argument_count(args, 3)?;
+        let reducer = match self.expect_function(&args[0])? {
+            Object::Function(function) if function.parameters.len() != 2 => {
+                return Err(self.wrong_type(&args[0]))
+            }
+            reducer => reducer,
+        };
+
+        match &args[2] {
+            Object::Array(elements) => elements.iter().try_fold(args[1].clone(), |acc, element| {
+                function_evaluator::apply_function(reducer, &[acc, element.clone()], env)
+            }),
+            unexpected_object => Err(self.wrong_type(unexpected_object)),
+        }
+    }
+
+    fn filter(&self, args: &[Object], env: &EnvReference) -> Result<Object, EvalError> {
+        self.expect_argument_count(args, 2)?;
+        let predicate = self.expect_function(&args[0])?;
+
+        let elements = match &args[1] {
+            Object::Array(elements) => elements,
+            unexpected_object => return Err(self.wrong_type(unexpected_object)),
+        };
+
+        let mut kept_elements = Vec::new();
+        for element in elements {
+            match function_evaluator::apply_function(predicate, slice::from_ref(element), env)? {
+                Object::Boolean(true) => kept_elements.push(element.clone()),
+                Object::Boolean(false) => {}
+                unexpected_condition => {
+                    return Err(EvalError::NonBooleanConditional(unexpected_condition))
+                }
+            }
+        }
+
+        Ok(Object::Array(kept_elements))
+    }
+
+    fn assert(&self, args: &[Object]) -> Result<Object, EvalError> {
+        let (condition, message) = match args {
+            [condition] => (condition, None),
+            [condition, Object::Str(message)] => (condition, Some(message.clone())),
+            [_, unexpected_object] => return Err(self.wrong_type(unexpected_object)),
+            _ => {
+                return Err(EvalError::WrongArgumentCount(
+                    self.to_string(),
+                    1..=2,
+                    args.len(),
+                ))
+            }
+        };
+
+        match condition {
+            Object::Boolean(true) => Ok(Object::Null),
+            _ => Err(EvalError::AssertionFailed(message)),
+        }
+    }
+
+    /// A step that moves away from the end of the range would never reach it,
+    /// so it is rejected instead of producing an empty array.
+    fn range(&self, args: &[Object]) -> Result<Object, EvalError> {
+        if let Some(unexpected_object) = args.iter().find(|arg| !matches!(arg, Object::Integer(_)))
+        {
+            return Err(self.wrong_type(unexpected_object));
+        }
+
+        let (start, end, step) = match args {
+            [Object::Integer(start), Object::Integer(end)] => (*start, *end, 1),
+            [Object::Integer(start), Object::Integer(end), Object::Integer(step)] => {
+                (*start, *end, *step)
+            }
+            _ => {
+                return Err(EvalError::WrongArgumentCount(
+                    self.to_string(),
+                    2..=3,
+                    args.len(),
+                ))
+            }
+        };
+
+        let moves_away = (step > 0 && start > end) || (step < 0 && start < end);
+        if step == 0 || moves_away {
+            return Err(EvalError::InvalidRange(start, end, step));
+        }
+
+        let elements = iter::successors(Some(start), |current| current.checked_add(step))
+            .take_while(|current| match step > 0 {
+                true => current < &end,
+                false => current > &end,
+            })
+            .map(Object::Integer)
+            .collect();
+
+        Ok(Object::Array(elements))
+    }
+
+    /// An empty separator splits the string into its characters.
+    fn split(&self, args: &[Object]) -> Result<Object, EvalError> {
+        self.expect_argument_count(args, 2)?;
+
+        let (string, separator) = match (&args[0], &args[1]) {
+            (Object::Str(string), Object::Str(separator)) => (string, separator),
+            (Object::Str(_), unexpected_object) | (unexpected_object, _) => {
+                return Err(self.wrong_type(unexpected_object))
+            }
+        };
+
+        let parts: Vec<Object> = match separator.is_empty() {
+            true => string
+                .chars()
+                .map(|char| Object::Str(char.to_string()))
+                .collect(),
+            false => string
+                .split(separator.as_str())
+                .map(|part| Object::Str(part.to_string()))
+                .collect(),
+        };
+
+        Ok(Object::Array(parts))
+    }
+
+    fn join(&self, args: &[Object]) -> Result<Object, EvalError> {
+        self.expect_argument_count(args, 2)?;
+
+        let (elements, separator) = match (&args[0], &args[1]) {
+            (Object::Array(elements), Object::Str(separator)) => (elements, separator),
+            (Object::Array(_), unexpected_object) | (unexpected_object, _) => {
+                return Err(self.wrong_type(unexpected_object))
+            }
+        };
+
+        let strings = elements
+            .iter()
+            .map(|element| match element {
+                Object::Str(string) => Ok(string.as_str()),
+                unexpected_object => Err(self.wrong_type(unexpected_object)),
+            })
+            .collect::<Result<Vec<&str>, EvalError>>()?;
+
+        Ok(Object::Str(strings.join(separator)))
+    }
+
+    fn contains(&self, args: &[Object]) -> Result<Object, EvalError> {
+        self.expect_argument_count(args, 2)?;
+
+        match (&args[0], &args[1]) {
+            (Object::Array(elements), element) => Ok(Object::Boolean(elements.contains(element))),
+            (Object::Str(string), Object::Str(substring)) => {
+                Ok(Object::Boolean(string.contains(substring.as_str())))
+            }
+            (Object::Str(_), unexpected_object) | (unexpected_object, _) => {
+                Err(self.wrong_type(unexpected_object))
+            }
+        }
+    }
+
+    fn transform_string<F>(&self, args: &[Object], transform: F) -> Result<Object, EvalError>
+    where
+        F: Fn(&str) -> String,
+    {
+        match self.single_argument(args)? {
+            Object::Str(string) => Ok(Object::Str(transform(string))),
+            unexpected_object => Err(self.wrong_type(unexpected_object)),
+        }
+    }
+
+    /// Indices are clamped to the bounds of the collection, and a range where
+    /// the start is not before the end gives an empty result.
+    fn slice(&self, args: &[Object]) -> Result<Object, EvalError> {
+        self.expect_argument_count(args, 3)?;
+
+        let (start, end) = match (&args[1], &args[2]) {
+            (Object::Integer(start), Object::Integer(end)) => (*start, *end),
+            (Object::Integer(_), unexpected_object) | (unexpected_object, _) => {
+                return Err(self.wrong_type(unexpected_object))
+            }
+        };
+        let clamp = |index: i32, len: usize| index.clamp(0, len as i32) as usize;
+
+        match &args[0] {
+            Object::Array(elements) => {
+                let (start, end) = (clamp(start, elements.len()), clamp(end, elements.len()));
+                Ok(Object::Array(
+                    elements.get(start..end).unwrap_or_default().to_vec(),
+                ))
+            }
+            Object::Str(string) => {
+                let len = string.chars().count();
+                let (start, end) = (clamp(start, len), clamp(end, len));
+                Ok(Object::Str(
+                    string
+                        .chars()
+                        .skip(start)
+                        .take(end.saturating_sub(start))
+                        .collect(),
+                ))
+            }
+            unexpected_object => Err(self.wrong_type(unexpected_object)),
+        }
+    }
+
+    fn keys(&self, args: &[Object]) -> Result<Object, EvalError> {
+        match self.single_argument(args)? {
+            Object::Hash(pairs) => Ok(Object::Array(
+                pairs.keys().cloned().map(Object::from).collect(),
+            )),
+            unexpected_object => Err(self.wrong_type(unexpected_object)),
+        }
+    }
+
+    fn values(&self, args: &[Object]) -> Result<Object, EvalError> {
+        match self.single_argument(args)? {
+            Object::Hash(pairs) => Ok(Object::Array(pairs.values().cloned().collect())),
+            unexpected_object => Err(self.wrong_type(unexpected_object)),
+        }
+    }
+
+    /// Built-ins have no declared parameters, so only function literals have
+    /// an arity.
+    fn arity(&self, args: &[Object]) -> Result<Object, EvalError> {
+        match self.single_argument(args)? {
+            Object::Function(function) => Ok(Object::Integer(function.parameters.len() as i32)),
+            unexpected_object => Err(self.wrong_type(unexpected_object)),
+        }
+    }
+
+    /// The absolute value of the smallest integer does not fit in an integer,
+    /// so it is reported as an overflow of negating it.
+    fn abs(&self, args: &[Object]) -> Result<Object, EvalError> {
+        match self.single_argument(args)? {
+            Object::Integer(integer) => match integer.checked_abs() {
+                Some(absolute) => Ok(Object::Integer(absolute)),
+                None => Err(EvalError::IntegerOverflow(0, Operator::Minus, *integer)),
+            },
+            Object::Float(float) => Ok(Object::Float(float.abs())),
+            unexpected_object => Err(self.wrong_type(unexpected_object)),
+        }
+    }
+
+    /// Finds the smallest or largest of the arguments, or of the elements of
+    /// a single array argument. Integers and floats can be mixed, and the
+    /// chosen number keeps its type. An empty array has no extremum.
+    fn extremum(&self, args: &[Object], wanted: Ordering) -> Result<Object, EvalError> {
+        let numbers = match args {
+            [Object::Array(elements)] => elements.as_slice(),
+            [] => {
+                return Err(EvalError::WrongArgumentCount(
+                    self.to_string(),
+                    1..=usize::MAX,
+                    args.len(),
+                ))
+            }
+            numbers => numbers,
+        };
+
+        let as_float = |object: &Object| match object {
+            Object::Integer(integer) => Ok(*integer as f64),
+            Object::Float(float) => Ok(*float),
+            unexpected_object => Err(self.wrong_type(unexpected_object)),
+        };
+
+        let mut extremum: Option<(&Object, f64)> = None;
+        for number in numbers {
+            let value = as_float(number)?;
+            extremum = match extremum {
+                Some((_, current)) if value.partial_cmp(&current) == Some(wanted) => {
+                    Some((number, value))
+                }
+                None => Some((number, value)),
+                unchanged => unchanged,
+            };
+        }
+
+        Ok(extremum.map_or(Object::Null, |(number, _)| number.clone()))
+    }
+
+    fn error(&self, args: &[Object]) -> Result<Object, EvalError> {
+        match self.single_argument(args)? {
+            Object::Str(message) => Err(EvalError::UserError(message.clone())),
+            unexpected_object => Err(self.wrong_type(unexpected_object)),
+        }
+    }
+
+    /// Unlike indexing, which gives null for a missing key, the caller chooses
+    /// the value to fall back on.
+    fn get(&self, args: &[Object]) -> Result<Object, EvalError> {
+        self.expect_argument_count(args, 3)?;
+
+        match &args[0] {
+            Object::Hash(pairs) => Ok(pairs
+                .get(&HashKey::try_from(args[1].clone())?)
+                .unwrap_or(&args[2])
+                .clone()),
+            unexpected_object => Err(self.wrong_type(unexpected_object)),
+        }
+    }
+
+    /// Pairs stop at the end of the shorter array, so the extra elements of
+    /// the longer array are left out.
+    fn zip(&self, args: &[Object]) -> Result<Object, EvalError> {
+        self.expect_argument_count(args, 2)?;
+
+        match (&args[0], &args[1]) {
+            (Object::Array(left), Object::Array(right)) => Ok(Object::Array(
+                iter::zip(left, right)
+                    .map(|(left, right)| Object::Array(vec![left.clone(), right.clone()]))
+                    .collect(),
+            )),
+            (Object::Array(_), unexpected_object) | (unexpected_object, _) => {
+                Err(self.wrong_type(unexpected_object))
+            }
+        }
+    }
+
+    fn repeat(&self, args: &[Object]) -> Result<Object, EvalError> {
+        self.expect_argument_count(args, 2)?;
+
+        let count = match &args[1] {
+            Object::Integer(count) => {
+                usize::try_from(*count).map_err(|_| EvalError::NegativeRepeatCount(*count))?
+            }
+            unexpected_object => return Err(self.wrong_type(unexpected_object)),
+        };
+
+        match &args[0] {
+            Object::Str(string) => Ok(Object::Str(string.repeat(count))),
+            Object::Array(elements) => Ok(Object::Array(
+                iter::repeat_n(elements, count).flatten().cloned().collect(),
+            )),
+            unexpected_object => Err(self.wrong_type(unexpected_object)),
+        }
+    }
+
+    /// Splits by unicode scalar value, so a character made of several bytes
+    /// stays whole.
+    fn chars(&self, args: &[Object]) -> Result<Object, EvalError> {
+        match self.single_argument(args)? {
+            Object::Str(string) => Ok(Object::Array(
+                string
+                    .chars()
+                    .map(|char| Object::Str(char.to_string()))
+                    .collect(),
+            )),
+            unexpected_object => Err(self.wrong_type(unexpected_object)),
+        }
+    }
+
+    fn ord(&self, args: &[Object]) -> Result<Object, EvalError> {
+        let object = self.single_argument(args)?;
+        let Object::Str(string) = object else {
+            return Err(self.wrong_type(object));
+        };
+
+        let mut chars = string.chars();
+        match (chars.next(), chars.next()) {
+            (Some(char), None) => Ok(Object::Integer(u32::from(char) as i32)),
+            _ => Err(self.wrong_type(object)),
+        }
+    }
+
+    /// Surrogates and numbers beyond the last unicode codepoint are not
+    /// characters, so they can not be converted.
+    fn chr(&self, args: &[Object]) -> Result<Object, EvalError> {
+        let codepoint = match self.single_argument(args)? {
+            Object::Integer(codepoint) => *codepoint,
+            unexpected_object => return Err(self.wrong_type(unexpected_object)),
+        };
+
+        match u32::try_from(codepoint).ok().and_then(char::from_u32) {
+            Some(char) => Ok(Object::Str(char.to_string())),
+            None => Err(EvalError::ConversionError {
+                from: Object::Integer(codepoint),
+                to: String::from("character"),
+            }),
+        }
+    }
+
+    /// Without a comparator the elements must all be integers, all floats or
+    /// all strings. A comparator is called with two elements, and gives a
+    /// negative integer when the first goes before the second, a positive
+    /// integer when it goes after, and zero when their order does not matter.
+    fn sort(&self, args: &[Object], env: &EnvReference) -> Result<Object, EvalError> {
+        let (elements, comparator) = match args {
+            [Object::Array(elements)] => (elements, None),
+            [Object::Array(elements), comparator] => {
+                (elements, Some(self.expect_function(comparator)?))
+            }
+            [unexpected_object] | [unexpected_object, _] => {
+                return Err(self.wrong_type(unexpected_object))
+            }
+            _ => {
+                return Err(EvalError::WrongArgumentCount(
+                    self.to_string(),
+                    1..=2,
+                    args.len(),
+                ))
+            }
+        };
+
+        if comparator.is_none() {
+            if let Some(unorderable) = elements.iter().find(|element| {
+                !matches!(
+                    element,
+                    Object::Integer(_) | Object::Float(_) | Object::Str(_)
+                ) || element.type_name() != elements[0].type_name()
+            }) {
+                return Err(self.wrong_type(unorderable));
+            }
+        }
+
+        let mut compare = |left: &Object, right: &Object| match comparator {
+            Some(comparator) => {
+                match function_evaluator::apply_function(
+                    comparator,
+                    &[left.clone(), right.clone()],
+                    env,
+                )? {
+                    Object::Integer(sign) => Ok(sign.cmp(&0)),
+                    unexpected_object => Err(self.wrong_type(&unexpected_object)),
+                }
+            }
+            None => left.partial_cmp(right).ok_or_else(|| self.wrong_type(left)),
+        };
+
+        Ok(Object::Array(merge_sort(elements.clone(), &mut compare)?))
+    }
+
+    /// Every `{}` in the format string is replaced by the next argument, as
+    /// `str` would convert it. `{{` and `}}` are written as a single brace.
+    fn format(&self, args: &[Object]) -> Result<Object, EvalError> {
+        let (format_string, arguments) = match args {
+            [Object::Str(format_string), arguments @ ..] => (format_string, arguments),
+            [unexpected_object, ..] => return Err(self.wrong_type(unexpected_object)),
+            [] => {
+                return Err(EvalError::WrongArgumentCount(
+                    self.to_string(),
+                    1..=usize::MAX,
+                    args.len(),
+                ))
+            }
+        };
+
+        let mut formatted = String::with_capacity(format_string.len());
+        let mut placeholders = 0;
+        let mut chars = format_string.chars().peekable();
+
+        while let Some(char) = chars.next() {
+            match (char, chars.peek()) {
+                ('{', Some('{')) | ('}', Some('}')) => {
+                    chars.next();
+                    formatted.push(char);
+                }
+                ('{', Some('}')) => {
+                    chars.next();
+                    if let Some(argument) = arguments.get(placeholders) {
+                        formatted.push_str(&argument.to_print_string());
+                    }
+                    placeholders += 1;
+                }
+                ('{' | '}', _) => {
+                    return Err(EvalError::InvalidFormatString(format_string.clone()))
+                }
+                (char, _) => formatted.push(char),
+            }
+        }
+
+        if placeholders != arguments.len() {
+            return Err(EvalError::FormatArgumentMismatch {
+                placeholders,
+                arguments: arguments.len(),
+            });
+        }
+
+        Ok(Object::Str(formatted))
+    }
+
+    /// Lines end at `\n` or `\r\n`, and a newline at the end of the string
+    /// ends the last line rather than starting an empty one.
+    fn lines(&self, args: &[Object]) -> Result<Object, EvalError> {
+        match self.single_argument(args)? {
+            Object::Str(string) => Ok(Object::Array(
+                string
+                    .lines()
+                    .map(|line| Object::Str(line.to_string()))
+                    .collect(),
+            )),
+            unexpected_object => Err(self.wrong_type(unexpected_object)),
+        }
+    }
+
+    /// Gives null once the input has ended.
+    fn read_line(&self, args: &[Object], env: &EnvReference) -> Result<Object, EvalError> {
+        self.expect_argument_count(args, 0)?;
+
+        let line = env.borrow().context().borrow().read_input_line()?;
+        Ok(line.map_or(Object::Null, Object::Str))
+    }
+
+    /// The whole seconds since the Unix epoch. The milliseconds since the
+    /// epoch are far beyond the range of an integer, while the seconds fit
+    /// until January 2038.
+    fn now(&self, args: &[Object], env: &EnvReference) -> Result<Object, EvalError> {
+        self.expect_argument_count(args, 0)?;
+
+        let seconds = env.borrow().context().borrow().now().as_secs();
+        i32::try_from(seconds)
+            .map(Object::Integer)
+            .map_err(|_| EvalError::TimeOutOfRange(seconds))
+    }
+
+    fn single_argument<'a>(&self, args: &'a [Object]) -> Result<&'a Object, EvalError> {
+        self.expect_argument_count(args, 1)?;
+        Ok(&args[0])
+    }
+
+    fn expect_argument_count(&self, args: &[Object], expected: usize) -> Result<(), EvalError> {
+        if args.len() != expected {
+            return Err(EvalError::WrongArgumentCount(
+                self.to_string(),
+                expected..=expected,
+                args.len(),
+            ));
+        }
+
+        Ok(())
+    }
+
+    fn wrong_type(&self, object: &Object) -> EvalError {
+        EvalError::WrongArgumentType(self.to_string(), object.clone())
+    }
+}
+
+/// A comparator written in Lasagna can fail, and does not have to describe a
+/// consistent order, so elements are sorted by a stable merge sort that stops
+/// at the first error instead of by the sorts of the standard library.
+fn merge_sort<F>(mut elements: Vec<Object>, compare: &mut F) -> Result<Vec<Object>, EvalError>
+where
+    F: FnMut(&Object, &Object) -> Result<Ordering, EvalError>,
+{
+    if elements.len() <= 1 {
+        return Ok(elements);
+    }
+
+    let right = merge_sort(elements.split_off(elements.len() / 2), compare)?;
+    let left = merge_sort(elements, compare)?;
+
+    let mut merged = Vec::with_capacity(left.len() + right.len());
+    let (mut left, mut right) = (left.into_iter().peekable(), right.into_iter().peekable());
+    while let (Some(left_element), Some(right_element)) = (left.peek(), right.peek()) {
+        let next = match compare(left_element, right_element)? {
+            Ordering::Greater => right.next(),
+            _ => left.next(),
+        };
+        merged.extend(next);
+    }
+    merged.extend(left.chain(right));
+
+    Ok(merged)
+}
+
+impl Display for Builtin {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Builtin::Len => write!(f, "len"),
+            Builtin::First => write!(f, "first"),
+            Builtin::Last => write!(f, "last"),
+            Builtin::Push => write!(f, "push"),
+            Builtin::Rest => write!(f, "rest"),
+            Builtin::Puts => write!(f, "puts"),
+            Builtin::Type => write!(f, "type"),
+            Builtin::Bool => write!(f, "bool"),
+            Builtin::Int => write!(f, "int"),
+            Builtin::Str => write!(f, "str"),
+            Builtin::Map => write!(f, "map"),
+            Builtin::Reduce => write!(f, "reduce"),
+            Builtin::Filter => write!(f, "filter"),
+            Builtin::Assert => write!(f, "assert"),
+            Builtin::Range => write!(f, "range"),
+            Builtin::Split => write!(f, "split"),
+            Builtin::Join => write!(f, "join"),
+            Builtin::Contains => write!(f, "contains"),
+            Builtin::Trim => write!(f, "trim"),
+            Builtin::TrimStart => write!(f, "trimStart"),
+            Builtin::TrimEnd => write!(f, "trimEnd"),
+            Builtin::Upper => write!(f, "upper"),
+            Builtin::Lower => write!(f, "lower"),
+            Builtin::Slice => write!(f, "slice"),
+            Builtin::Keys => write!(f, "keys"),
+            Builtin::Values => write!(f, "values"),
+            Builtin::Arity => write!(f, "arity"),
+            Builtin::Abs => write!(f, "abs"),
+            Builtin::Min => write!(f, "min"),
+            Builtin::Max => write!(f, "max"),
+            Builtin::Error => write!(f, "error"),
+            Builtin::Get => write!(f, "get"),
+            Builtin::Zip => write!(f, "zip"),
+            Builtin::Repeat => write!(f, "repeat"),
+            Builtin::Chars => write!(f, "chars"),
+            Builtin::Ord => write!(f, "ord"),
+            Builtin::Chr => write!(f, "chr"),
+            Builtin::Sort => write!(f, "sort"),
+            Builtin::Format => write!(f, "format"),
+            Builtin::Lines => write!(f, "lines"),
+            Builtin::ReadLine => write!(f, "readLine"),
+            Builtin::Now => write!(f, "now"),
+            Builtin::Native(native) => write!(f, "{}", native.name),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        cell::{Cell, RefCell},
+        io::Cursor,
+        rc::Rc,
+        time::Duration,
+    };
+
+    use crate::{
+        eval::{
+            self,
+            eval_error::EvalError,
+            objects::{Environment, Object},
+            EvaledProgram,
+        },
+        parser::test_util,
+    };
+
+    #[test]
+    fn len_builtin_test() {
+        let input_expected: Vec<(&str, i32)> = vec![
+            ("len([])", 0),
+            ("len([1, 2, 3])", 3),
+            ("let a: [1, 2]. len(a)", 2),
+            ("len(\"hello\")", 5),
+        ];
+
+        test_util::assert_list(input_expected, |expected: &i32, input: &&str| {
+            match test_util::expect_evaled_program(input) {
+                Object::Integer(integer) => assert_eq!(expected, &integer),
+                something_else => {
+                    panic!("Expected integer, got {something_else} for input '{input}'")
+                }
+            }
+        });
+    }
+
+    #[test]
+    fn first_and_last_builtin_test() {
+        let input_expected: Vec<(&str, &str)> = vec![
+            ("first([1, 2, 3])", "1"),
+            ("last([1, 2, 3])", "3"),
+            ("first([])", "null"),
+            ("last([])", "null"),
+        ];
+
+        test_util::assert_list(input_expected, |expected: &&str, input: &&str| {
+            let object = test_util::expect_evaled_program(input);
+            assert_eq!(expected, &object.to_string(), "Wrong result for '{input}'");
+        });
+
+        assert!(matches!(
+            test_util::expect_evaled_program("first([1, 2, 3])"),
+            Object::Integer(1)
+        ));
+        assert!(matches!(
+            test_util::expect_evaled_program("last([])"),
+            Object::Null
+        ));
+    }
+
+    #[test]
+    fn push_builtin_test() {
+        let object = test_util::expect_evaled_program("push([1, 2], 3)");
+        assert_eq!("[1, 2, 3]", object.to_string());
+
+        let input = "
+            let a: [1, 2].
+            let b: push(a, 3).
+            len(a)
+        ";
+        match test_util::expect_evaled_program(input) {
+            Object::Integer(length) => assert_eq!(2, length, "Original array should be untouched"),
+            something_else => panic!("Expected integer, got {something_else}"),
+        }
+
+        match eval::eval("push([1])", &mut Environment::new_env_reference()) {
+            EvaledProgram::EvalError(EvalError::WrongArgumentCount(_, expected, 1))
+                if expected == (2..=2) => {}
+            _ => panic!("Expected wrong argument count error"),
+        }
+    }
+
+    #[test]
+    fn rest_builtin_test() {
+        let input_expected: Vec<(&str, &str)> = vec![
+            ("rest([1, 2, 3])", "[2, 3]"),
+            ("rest([1])", "[]"),
+            ("rest([])", "null"),
+            ("let a: [1, 2, 3]. let b: rest(a). a", "[1, 2, 3]"),
+        ];
+
+        test_util::assert_list(input_expected, |expected: &&str, input: &&str| {
+            let object = test_util::expect_evaled_program(input);
+            assert_eq!(expected, &object.to_string(), "Wrong result for '{input}'");
+        });
+
+        assert!(matches!(
+            test_util::expect_evaled_program("rest([])"),
+            Object::Null
+        ));
+    }
+
+    #[test]
+    fn puts_builtin_test() {
+        let output = Rc::new(RefCell::new(Vec::<u8>::new()));
+        let mut env = Environment::new_with_output(output.clone());
+
+        match eval::eval("puts(\"hi\", [1, \"a\"])", &mut env) {
+            EvaledProgram::Valid(Object::Void) => {}
+            _ => panic!("puts should not return a value"),
+        }
+
+        let printed = String::from_utf8(output.borrow().clone()).expect("Should be utf8");
+        assert_eq!("hi\n[1, \"a\"]\n", printed);
+    }
+
+    #[test]
+    fn read_line_builtin_test() {
+        let input = Rc::new(RefCell::new(Cursor::new("first line\r\nsecond line\n")));
+        let mut env = Environment::new_with_input(input);
+
+        let input_expected: Vec<(&str, &str)> = vec![
+            ("readLine()", "\"first line\""),
+            ("readLine()", "\"second line\""),
+            ("readLine()", "null"),
+            ("readLine()", "null"),
+        ];
+
+        test_util::assert_list(
+            input_expected,
+            |expected: &&str, input: &&str| match eval::eval(input, &mut env) {
+                EvaledProgram::Valid(object) => {
+                    assert_eq!(expected, &object.to_string(), "Wrong result for '{input}'")
+                }
+                _ => panic!("'{input}' should evaluate"),
+            },
+        );
+
+        assert!(matches!(
+            eval::eval("readLine(1)", &mut env),
+            EvaledProgram::EvalError(EvalError::WrongArgumentCount(..))
+        ));
+    }
+
+    #[test]
+    fn now_builtin_test() {
+        let ticks = Rc::new(Cell::new(0));
+        let clock_ticks = Rc::clone(&ticks);
+        let mut env = Environment::new_with_clock(Rc::new(move || {
+            clock_ticks.set(clock_ticks.get() + 1);
+            Duration::from_millis(1_700_000_000_250 + 1_000 * clock_ticks.get())
+        }));
+
+        let input_expected: Vec<(&str, &str)> = vec![
+            ("now()", "1700000001"),
+            ("let start: now(). now() - start", "1"),
+        ];
+
+        test_util::assert_list(
+            input_expected,
+            |expected: &&str, input: &&str| match eval::eval(input, &mut env) {
+                EvaledProgram::Valid(object) => {
+                    assert_eq!(expected, &object.to_string(), "Wrong result for '{input}'")
+                }
+                _ => panic!("'{input}' should evaluate"),
+            },
+        );
+        assert_eq!(3, ticks.get());
+
+        assert!(matches!(
+            eval::eval("now(1)", &mut env),
+            EvaledProgram::EvalError(EvalError::WrongArgumentCount(..))
+        ));
+        match test_util::expect_evaled_program("now()") {
+            Object::Integer(seconds) => {
+                assert!(seconds > 1_700_000_000, "Expected a time after 2023")
+            }
+            something_else => panic!("Expected integer, got {something_else}"),
+        }
+
+        let past_2038 = i32::MAX as u64 + 1;
+        let mut env = Environment::new_with_clock(Rc::new(move || Duration::from_secs(past_2038)));
+        match eval::eval("now()", &mut env) {
+            EvaledProgram::EvalError(EvalError::TimeOutOfRange(seconds)) => {
+                assert_eq!(past_2038, seconds)
+            }
+            _ => panic!("Expected the time to be out of range"),
+        }
+    }
+
+    #[test]
+    fn type_builtin_test() {
+        let input_expected: Vec<(&str, &str)> = vec![
+            ("type(1)", "integer"),
+            ("type(1.5)", "float"),
+            ("type(true)", "boolean"),
+            ("type(\"a\")", "string"),
+            ("type([1, 2])", "array"),
+            ("type(fn(x): x~)", "function"),
+            ("type(len)", "function"),
+            ("type(first([]))", "null"),
+        ];
+
+        test_util::assert_list(input_expected, |expected: &&str, input: &&str| {
+            match test_util::expect_evaled_program(input) {
+                Object::Str(type_name) => assert_eq!(expected, &type_name),
+                something_else => panic!("Expected string, got {something_else} for '{input}'"),
+            }
+        });
+    }
+
+    #[test]
+    fn bool_builtin_test() {
+        let input_expected: Vec<(&str, bool)> = vec![
+            ("bool(1)", true),
+            ("bool(-1)", true),
+            ("bool(0)", false),
+            ("bool(true)", true),
+            ("bool(false)", false),
+            ("bool(\"a\")", true),
+            ("bool(\"\")", false),
+            ("bool([0])", true),
+            ("bool([])", false),
+            ("bool(first([]))", false),
+        ];
+
+        test_util::assert_list(input_expected, |expected: &bool, input: &&str| {
+            match test_util::expect_evaled_program(input) {
+                Object::Boolean(boolean) => assert_eq!(expected, &boolean, "Wrong for '{input}'"),
+                something_else => panic!("Expected boolean, got {something_else} for '{input}'"),
+            }
+        });
+    }
+
+    #[test]
+    fn int_builtin_test() {
+        let input_expected: Vec<(&str, i32)> = vec![
+            ("int(\"42\")", 42),
+            ("int(\"-7\")", -7),
+            ("int(3.9)", 3),
+            ("int(-3.9)", -3),
+            ("int(5)", 5),
+        ];
+
+        test_util::assert_list(input_expected, |expected: &i32, input: &&str| {
+            match test_util::expect_evaled_program(input) {
+                Object::Integer(integer) => assert_eq!(expected, &integer, "Wrong for '{input}'"),
+                something_else => panic!("Expected integer, got {something_else} for '{input}'"),
+            }
+        });
+
+        match eval::eval("int(\"forty\")", &mut Environment::new_env_reference()) {
+            EvaledProgram::EvalError(EvalError::ConversionError { from, to }) => {
+                assert_eq!("\"forty\"", from.to_string());
+                assert_eq!("integer", to);
+            }
+            _ => panic!("Expected conversion error for invalid integer string"),
+        }
+    }
+
+    #[test]
+    fn str_builtin_test() {
+        let input_expected: Vec<(&str, &str)> = vec![
+            ("str(42)", "42"),
+            ("str(-1)", "-1"),
+            ("str(3)", "3"),
+            ("str(3.0)", "3.0"),
+            ("str(-2.0)", "-2.0"),
+            ("str(2.5)", "2.5"),
+            ("str(true)", "true"),
+            ("str([1, 2])", "[1, 2]"),
+            ("str([\"a\"])", "[\"a\"]"),
+            ("str(\"a\")", "a"),
+        ];
+
+        test_util::assert_list(input_expected, |expected: &&str, input: &&str| {
+            match test_util::expect_evaled_program(input) {
+                Object::Str(string) => assert_eq!(expected, &string, "Wrong for '{input}'"),
+                something_else => panic!("Expected string, got {something_else} for '{input}'"),
+            }
+        });
+
+        assert!(matches!(
+            eval::eval("str(1, 2)", &mut Environment::new_env_reference()),
+            EvaledProgram::EvalError(EvalError::WrongArgumentCount(_, expected, 2))
+                if expected == (1..=1)
+        ));
+    }
+
+    #[test]
+    fn map_builtin_test() {
+        let input_expected: Vec<(&str, &str)> = vec![
+            ("map(fn(x): x * 2~, [1, 2, 3])", "[2, 4, 6]"),
+            ("map(fn(x): x~, [1, 2, 3])", "[1, 2, 3]"),
+            ("map(fn(x): x~, [])", "[]"),
+            ("let double: fn(x): x * 2~ map(double, [4])", "[8]"),
+            ("map(str, [1, true])", "[\"1\", \"true\"]"),
+            ("let y: 10. map(fn(x): x + y~, [1, 2])", "[11, 12]"),
+        ];
+
+        test_util::assert_list(input_expected, |expected: &&str, input: &&str| {
+            let object = test_util::expect_evaled_program(input);
+            assert_eq!(expected, &object.to_string(), "Wrong result for '{input}'");
+        });
+    }
+
+    #[test]
+    fn reduce_builtin_test() {
+        let input_expected: Vec<(&str, &str)> = vec![
+            ("reduce(fn(acc, x): acc + x~, 0, [1, 2, 3])", "6"),
+            ("reduce(fn(acc, x): acc + x~, 10, [])", "10"),
+            (
+                "reduce(fn(acc, x): push(acc, x * x)~, [], [1, 2, 3])",
+                "[1, 4, 9]",
+            ),
+        ];
+
+        test_util::assert_list(input_expected, |expected: &&str, input: &&str| {
+            let object = test_util::expect_evaled_program(input);
+            assert_eq!(expected, &object.to_string(), "Wrong result for '{input}'");
+        });
+
+        assert!(matches!(
+            eval::eval(
+                "reduce(fn(x): x~, 0, [1])",
+                &mut Environment::new_env_reference()
+            ),
+            EvaledProgram::EvalError(EvalError::WrongArgumentType(_, _))
+        ));
+    }
+
+    #[test]
+    fn filter_builtin_test() {
+        let input_expected: Vec<(&str, &str)> = vec![
+            ("filter(fn(x): x > 2~, [1, 2, 3, 4])", "[3, 4]"),
+            ("filter(fn(x): false~, [1, 2, 3])", "[]"),
+            ("filter(fn(x): true~, [1, 2, 3])", "[1, 2, 3]"),
+        ];
+
+        test_util::assert_list(input_expected, |expected: &&str, input: &&str| {
+            let object = test_util::expect_evaled_program(input);
+            assert_eq!(expected, &object.to_string(), "Wrong result for '{input}'");
+        });
+
+        assert!(matches!(
+            eval::eval(
+                "filter(fn(x): x~, [1])",
+                &mut Environment::new_env_reference()
+            ),
+            EvaledProgram::EvalError(EvalError::NonBooleanConditional(_))
+        ));
+    }
+
+    #[test]
+    fn assert_builtin_test() {
+        assert!(matches!(
+            test_util::expect_evaled_program("assert(1 < 2)"),
+            Object::Null
+        ));
+
+        match eval::eval(
+            "assert(1 > 2, \"one is not bigger\")",
+            &mut Environment::new_env_reference(),
+        ) {
+            EvaledProgram::EvalError(EvalError::AssertionFailed(Some(message))) => {
+                assert_eq!("one is not bigger", message)
+            }
+            _ => panic!("Expected assert with message to fail"),
+        }
+
+        assert!(matches!(
+            eval::eval("assert(1)", &mut Environment::new_env_reference()),
+            EvaledProgram::EvalError(EvalError::AssertionFailed(None))
+        ));
+    }
+
+    #[test]
+    fn range_builtin_test() {
+        let input_expected: Vec<(&str, &str)> = vec![
+            ("range(0, 5)", "[0, 1, 2, 3, 4]"),
+            ("range(0, 10, 2)", "[0, 2, 4, 6, 8]"),
+            ("range(5, 0, -2)", "[5, 3, 1]"),
+            ("range(3, 3)", "[]"),
+            ("map(fn(x): x * x~, range(1, 4))", "[1, 4, 9]"),
+        ];
+
+        test_util::assert_list(input_expected, |expected: &&str, input: &&str| {
+            let object = test_util::expect_evaled_program(input);
+            assert_eq!(expected, &object.to_string(), "Wrong result for '{input}'");
+        });
+
+        for input in ["range(0, 5, 0)", "range(0, 5, -1)", "range(5, 0)"] {
+            match eval::eval(input, &mut Environment::new_env_reference()) {
+                EvaledProgram::EvalError(EvalError::InvalidRange(..)) => {}
+                _ => panic!("Expected invalid range error for '{input}'"),
+            }
+        }
+
+        match eval::eval("range(1)", &mut Environment::new_env_reference()) {
+            EvaledProgram::EvalError(eval_error) => assert_eq!(
+                "range takes 2 to 3 arguments, but was called with 1\n",
+                eval_error.to_string()
+            ),
+            _ => panic!("Expected wrong argument count error"),
+        }
+    }
+
+    #[test]
+    fn split_builtin_test() {
+        let input_expected: Vec<(&str, &str)> = vec![
+            ("split(\"a,b,c\", \",\")", "[\"a\", \"b\", \"c\"]"),
+            ("split(\"abc\", \"\")", "[\"a\", \"b\", \"c\"]"),
+            ("split(\"abc\", \";\")", "[\"abc\"]"),
+        ];
+
+        test_util::assert_list(input_expected, |expected: &&str, input: &&str| {
+            let object = test_util::expect_evaled_program(input);
+            assert_eq!(expected, &object.to_string(), "Wrong result for '{input}'");
+        });
+    }
+
+    #[test]
+    fn join_builtin_test() {
+        let input_expected: Vec<(&str, &str)> = vec![
+            ("join([\"a\", \"b\", \"c\"], \"-\")", "\"a-b-c\""),
+            ("join([], \"-\")", "\"\""),
+            ("join(split(\"a,b\", \",\"), \";\")", "\"a;b\""),
+        ];
+
+        test_util::assert_list(input_expected, |expected: &&str, input: &&str| {
+            let object = test_util::expect_evaled_program(input);
+            assert_eq!(expected, &object.to_string(), "Wrong result for '{input}'");
+        });
+
+        assert!(matches!(
+            eval::eval(
+                "join([\"a\", 1], \"-\")",
+                &mut Environment::new_env_reference()
+            ),
+            EvaledProgram::EvalError(EvalError::WrongArgumentType(_, Object::Integer(1)))
+        ));
+    }
+
+    #[test]
+    fn contains_builtin_test() {
+        let input_expected: Vec<(&str, bool)> = vec![
+            ("contains([1, 2, 3], 2)", true),
+            ("contains([1, 2, 3], 4)", false),
+            ("contains([\"a\", [1]], [1])", true),
+            ("contains([1], \"1\")", false),
+            ("contains(\"hello\", \"ell\")", true),
+            ("contains(\"hello\", \"olle\")", false),
+        ];
+
+        test_util::assert_list(input_expected, |expected: &bool, input: &&str| {
+            match test_util::expect_evaled_program(input) {
+                Object::Boolean(boolean) => assert_eq!(expected, &boolean, "Input '{input}'"),
+                something_else => panic!("Expected boolean, got {something_else}"),
+            }
+        });
+    }
+
+    #[test]
+    fn trim_builtin_test() {
+        let input_expected: Vec<(&str, &str)> = vec![
+            ("trim(\"  hi  \")", "\"hi\""),
+            ("trim(\"hi\")", "\"hi\""),
+            ("trimStart(\"  hi  \")", "\"hi  \""),
+            ("trimEnd(\"  hi  \")", "\"  hi\""),
+            ("trimStart(\"hi\")", "\"hi\""),
+        ];
+
+        test_util::assert_list(input_expected, |expected: &&str, input: &&str| {
+            let object = test_util::expect_evaled_program(input);
+            assert_eq!(expected, &object.to_string(), "Wrong result for '{input}'");
+        });
+    }
+
+    #[test]
+    fn case_builtin_test() {
+        let input_expected: Vec<(&str, &str)> = vec![
+            ("upper(\"abc\")", "\"ABC\""),
+            ("lower(\"ABC\")", "\"abc\""),
+            ("upper(\"LaSagNa 1\")", "\"LASAGNA 1\""),
+            ("lower(\"LaSagNa 1\")", "\"lasagna 1\""),
+        ];
+
+        test_util::assert_list(input_expected, |expected: &&str, input: &&str| {
+            let object = test_util::expect_evaled_program(input);
+            assert_eq!(expected, &object.to_string(), "Wrong result for '{input}'");
+        });
+    }
+
+    #[test]
+    fn slice_builtin_test() {
+        let input_expected: Vec<(&str, &str)> = vec![
+            ("slice([1, 2, 3, 4], 1, 3)", "[2, 3]"),
+            ("slice([1, 2, 3, 4], 0, 4)", "[1, 2, 3, 4]"),
+            ("slice(\"hello\", 0, 2)", "\"he\""),
+            ("slice(\"hello\", 3, 5)", "\"lo\""),
+            ("slice([1, 2, 3], -5, 10)", "[1, 2, 3]"),
+            ("slice(\"hello\", 2, 99)", "\"llo\""),
+            ("slice([1, 2, 3], 2, 1)", "[]"),
+            ("slice(\"hello\", 4, 1)", "\"\""),
+        ];
+
+        test_util::assert_list(input_expected, |expected: &&str, input: &&str| {
+            let object = test_util::expect_evaled_program(input);
+            assert_eq!(expected, &object.to_string(), "Wrong result for '{input}'");
+        });
+    }
+
+    #[test]
+    fn keys_and_values_builtin_test() {
+        let input_expected: Vec<(&str, &str)> = vec![
+            ("keys({\"b\": 1, \"a\": 2, 3: true})", "[\"b\", \"a\", 3]"),
+            ("values({\"b\": 1, \"a\": 2, 3: true})", "[1, 2, true]"),
+            ("keys({})", "[]"),
+            ("values({})", "[]"),
+        ];
+
+        for _ in 0..3 {
+            test_util::assert_list(input_expected.clone(), |expected: &&str, input: &&str| {
+                let object = test_util::expect_evaled_program(input);
+                assert_eq!(expected, &object.to_string(), "Wrong result for '{input}'");
+            });
+        }
+    }
+
+    #[test]
+    fn arity_builtin_test() {
+        let input_expected: Vec<(&str, i32)> = vec![
+            ("arity(fn(): 1~)", 0),
+            ("arity(fn(x): x~)", 1),
+            ("arity(fn(x, y): x~)", 2),
+            ("fn add(x, y): x + y~ arity(add)", 2),
+        ];
+
+        test_util::assert_list(input_expected, |expected: &i32, input: &&str| {
+            match test_util::expect_evaled_program(input) {
+                Object::Integer(integer) => assert_eq!(expected, &integer, "Wrong for '{input}'"),
+                something_else => panic!("Expected integer, got {something_else} for '{input}'"),
+            }
+        });
+    }
+
+    #[test]
+    fn abs_builtin_test() {
+        let input_expected: Vec<(&str, &str)> = vec![
+            ("abs(-5)", "5"),
+            ("abs(5)", "5"),
+            ("abs(0)", "0"),
+            ("abs(-2.5)", "2.5"),
+            ("abs(2.5)", "2.5"),
+        ];
+
+        test_util::assert_list(input_expected, |expected: &&str, input: &&str| {
+            let object = test_util::expect_evaled_program(input);
+            assert_eq!(expected, &object.to_string(), "Wrong result for '{input}'");
+        });
+
+        assert!(matches!(
+            test_util::expect_evaled_program("abs(-5)"),
+            Object::Integer(5)
+        ));
+        assert!(matches!(
+            eval::eval(
+                "abs(-2147483647 - 1)",
+                &mut Environment::new_env_reference()
+            ),
+            EvaledProgram::EvalError(EvalError::IntegerOverflow(..))
+        ));
+    }
+
+    #[test]
+    fn min_and_max_builtin_test() {
+        let input_expected: Vec<(&str, &str)> = vec![
+            ("min(3, 7)", "3"),
+            ("max(3, 7)", "7"),
+            ("min(4)", "4"),
+            ("max(2, 9, -4, 5)", "9"),
+            ("min(2.5, 1.5)", "1.5"),
+            ("max(2.5, 1.5)", "2.5"),
+            ("min(2, 1.5)", "1.5"),
+            ("max(2, 1.5)", "2"),
+            ("min([5, 3, 8])", "3"),
+            ("max([5, 3.5, 8])", "8"),
+            ("min([])", "null"),
+        ];
+
+        test_util::assert_list(input_expected, |expected: &&str, input: &&str| {
+            let object = test_util::expect_evaled_program(input);
+            assert_eq!(expected, &object.to_string(), "Wrong result for '{input}'");
+        });
+
+        assert!(matches!(
+            test_util::expect_evaled_program("min(3, 7)"),
+            Object::Integer(3)
+        ));
+        assert!(matches!(
+            test_util::expect_evaled_program("max(3, 7.0)"),
+            Object::Float(_)
+        ));
+        assert!(matches!(
+            eval::eval("max()", &mut Environment::new_env_reference()),
+            EvaledProgram::EvalError(EvalError::WrongArgumentCount(..))
+        ));
+    }
+
+    #[test]
+    fn error_builtin_test() {
+        match eval::eval(
+            "let a: 1. error(\"boom\"). let a: 2.",
+            &mut Environment::new_env_reference(),
+        ) {
+            EvaledProgram::EvalError(EvalError::UserError(message)) => assert_eq!("boom", message),
+            _ => panic!("Expected error to halt evaluation with its message"),
+        }
+
+        assert!(matches!(
+            eval::eval(
+                "let f: fn(x): if x < 0: error(\"negative\")~ x~ f(-1)",
+                &mut Environment::new_env_reference()
+            ),
+            EvaledProgram::EvalError(EvalError::UserError(_))
+        ));
+    }
+
+    #[test]
+    fn get_builtin_test() {
+        let input_expected: Vec<(&str, &str)> = vec![
+            ("get({\"a\": 1}, \"a\", 0)", "1"),
+            ("get({\"a\": 1}, \"b\", 0)", "0"),
+            ("get({}, 1, \"none\")", "\"none\""),
+            ("get({true: [1]}, true, [])", "[1]"),
+        ];
+
+        test_util::assert_list(input_expected, |expected: &&str, input: &&str| {
+            let object = test_util::expect_evaled_program(input);
+            assert_eq!(expected, &object.to_string(), "Wrong result for '{input}'");
+        });
+
+        assert!(matches!(
+            eval::eval("get({}, [1], 0)", &mut Environment::new_env_reference()),
+            EvaledProgram::EvalError(EvalError::UnhashableKey(_))
+        ));
+        assert!(matches!(
+            eval::eval("get({}, 1)", &mut Environment::new_env_reference()),
+            EvaledProgram::EvalError(EvalError::WrongArgumentCount(..))
+        ));
+    }
+
+    #[test]
+    fn zip_builtin_test() {
+        let input_expected: Vec<(&str, &str)> = vec![
+            (
+                "zip([1, 2, 3], [\"a\", \"b\", \"c\"])",
+                "[[1, \"a\"], [2, \"b\"], [3, \"c\"]]",
+            ),
+            ("zip([1, 2, 3], [true])", "[[1, true]]"),
+            ("zip([1], [4, 5, 6])", "[[1, 4]]"),
+            ("zip([], [1])", "[]"),
+        ];
+
+        test_util::assert_list(input_expected, |expected: &&str, input: &&str| {
+            let object = test_util::expect_evaled_program(input);
+            assert_eq!(expected, &object.to_string(), "Wrong result for '{input}'");
+        });
+    }
+
+    #[test]
+    fn repeat_builtin_test() {
+        let input_expected: Vec<(&str, &str)> = vec![
+            ("repeat(\"ab\", 3)", "\"ababab\""),
+            ("repeat([0], 3)", "[0, 0, 0]"),
+            ("repeat([1, 2], 2)", "[1, 2, 1, 2]"),
+            ("repeat(\"ab\", 0)", "\"\""),
+            ("repeat([1], 0)", "[]"),
+        ];
+
+        test_util::assert_list(input_expected, |expected: &&str, input: &&str| {
+            let object = test_util::expect_evaled_program(input);
+            assert_eq!(expected, &object.to_string(), "Wrong result for '{input}'");
+        });
+
+        assert!(matches!(
+            eval::eval("repeat(\"ab\", -1)", &mut Environment::new_env_reference()),
+            EvaledProgram::EvalError(EvalError::NegativeRepeatCount(-1))
+        ));
+    }
+
+    #[test]
+    fn chars_builtin_test() {
+        let input_expected: Vec<(&str, &str)> = vec![
+            ("chars(\"abc\")", "[\"a\", \"b\", \"c\"]"),
+            ("chars(\"\")", "[]"),
+            ("chars(\"bløt\")", "[\"b\", \"l\", \"ø\", \"t\"]"),
+            ("join(chars(\"a🍝b\"), \"-\")", "\"a-🍝-b\""),
+            ("len(chars(\"🍝🍝\"))", "2"),
+        ];
+
+        test_util::assert_list(input_expected, |expected: &&str, input: &&str| {
+            let object = test_util::expect_evaled_program(input);
+            assert_eq!(expected, &object.to_string(), "Wrong result for '{input}'");
+        });
+    }
+
+    #[test]
+    fn ord_and_chr_builtin_test() {
+        let input_expected: Vec<(&str, &str)> = vec![
+            ("ord(\"A\")", "65"),
+            ("chr(65)", "\"A\""),
+            ("ord(\"ø\")", "248"),
+            ("chr(127837)", "\"🍝\""),
+            ("chr(ord(\"🍝\"))", "\"🍝\""),
+            ("ord(chr(955))", "955"),
+        ];
+
+        test_util::assert_list(input_expected, |expected: &&str, input: &&str| {
+            let object = test_util::expect_evaled_program(input);
+            assert_eq!(expected, &object.to_string(), "Wrong result for '{input}'");
+        });
+
+        for input in ["chr(-1)", "chr(55296)", "chr(1114112)"] {
+            match eval::eval(input, &mut Environment::new_env_reference()) {
+                EvaledProgram::EvalError(EvalError::ConversionError { to, .. }) => {
+                    assert_eq!("character", to)
+                }
+                _ => panic!("Expected conversion error for '{input}'"),
+            }
+        }
+    }
+
+    #[test]
+    fn sort_builtin_test() {
+        let input_expected: Vec<(&str, &str)> = vec![
+            ("sort([3, 1, 2])", "[1, 2, 3]"),
+            ("sort([])", "[]"),
+            ("sort([2.5, -1.0, 0.5])", "[-1.0, 0.5, 2.5]"),
+            (
+                "sort([\"pear\", \"apple\", \"fig\"])",
+                "[\"apple\", \"fig\", \"pear\"]",
+            ),
+            ("sort([3, 1, 2], fn(a, b): b - a~)", "[3, 2, 1]"),
+            (
+                "sort([\"ccc\", \"a\", \"bb\"], fn(a, b): len(a) - len(b)~)",
+                "[\"a\", \"bb\", \"ccc\"]",
+            ),
+            ("let a: [2, 1]. let b: sort(a). a", "[2, 1]"),
+            (
+                "sort([[2, \"a\"], [1, \"b\"], [2, \"c\"]], fn(a, b): a[0] - b[0]~)",
+                "[[1, \"b\"], [2, \"a\"], [2, \"c\"]]",
+            ),
+            ("len(sort(range(0, 50), fn(a, b): 1~))", "50"),
+        ];
+
+        test_util::assert_list(input_expected, |expected: &&str, input: &&str| {
+            let object = test_util::expect_evaled_program(input);
+            assert_eq!(expected, &object.to_string(), "Wrong result for '{input}'");
+        });
+
+        assert!(matches!(
+            eval::eval(
+                "sort([1, 2], fn(a, b): error(\"no\")~)",
+                &mut Environment::new_env_reference()
+            ),
+            EvaledProgram::EvalError(EvalError::UserError(_))
+        ));
+    }
+
+    #[test]
+    fn format_builtin_test() {
+        let input_expected: Vec<(&str, &str)> = vec![
+            ("format(\"{} + {} = {}\", 1, 2, 3)", "\"1 + 2 = 3\""),
+            ("format(\"no placeholders\")", "\"no placeholders\""),
+            (
+                "format(\"{}, {}!\", \"hello\", \"world\")",
+                "\"hello, world!\"",
+            ),
+            ("format(\"{}{}\", [1, 2.0], true)", "\"[1, 2.0]true\""),
+            ("format(\"{{}} {{{}}}\", 7)", "\"{} {7}\""),
+            ("format(\"{}🍝\", \"ø\")", "\"ø🍝\""),
+        ];
+
+        test_util::assert_list(input_expected, |expected: &&str, input: &&str| {
+            let object = test_util::expect_evaled_program(input);
+            assert_eq!(expected, &object.to_string(), "Wrong result for '{input}'");
+        });
+
+        for input in [
+            "format(\"{} {}\", 1)",
+            "format(\"{}\", 1, 2)",
+            "format(\"{{}}\", 1)",
+        ] {
+            assert!(
+                matches!(
+                    eval::eval(input, &mut Environment::new_env_reference()),
+                    EvaledProgram::EvalError(EvalError::FormatArgumentMismatch { .. })
+                ),
+                "Expected argument mismatch for '{input}'"
+            );
+        }
+
+        for input in ["format(\"{\")", "format(\"a } b\")", "format(\"{0}\", 1)"] {
+            assert!(
+                matches!(
+                    eval::eval(input, &mut Environment::new_env_reference()),
+                    EvaledProgram::EvalError(EvalError::InvalidFormatString(_))
+                ),
+                "Expected invalid format string for '{input}'"
+            );
+        }
+    }
+
+    #[test]
+    fn lines_builtin_test() {
+        let input_expected: Vec<(&str, &str)> = vec![
+            ("lines(\"a\nb\nc\")", "[\"a\", \"b\", \"c\"]"),
+            ("lines(\"a\r\nb\r\nc\")", "[\"a\", \"b\", \"c\"]"),
+            ("lines(\"a\nb\n\")", "[\"a\", \"b\"]"),
+            ("lines(\"a\r\n\")", "[\"a\"]"),
+            ("lines(\"a\n\nb\")", "[\"a\", \"\", \"b\"]"),
+            ("lines(\"one line\")", "[\"one line\"]"),
+            ("lines(\"\")", "[]"),
+        ];
+
+        test_util::assert_list(input_expected, |expected: &&str, input: &&str| {
+            let object = test_util::expect_evaled_program(input);
+            assert_eq!(expected, &object.to_string(), "Wrong result for '{input}'");
+        });
+    }
+
+    #[test]
+    fn native_builtin_test() {
+        let mut env = Environment::new_env_reference();
+        env.borrow().register_builtin(
+            "double",
+            Box::new(|args| match args.as_slice() {
+                [Object::Integer(integer)] => Ok(Object::Integer(integer * 2)),
+                _ => Err(EvalError::UserError(String::from(
+                    "double takes an integer",
+                ))),
+            }),
+        );
+        env.borrow()
+            .register_builtin("len", Box::new(|_| Ok(Object::Integer(-1))));
+
+        assert!(matches!(
+            eval::eval("double(true)", &mut env),
+            EvaledProgram::EvalError(EvalError::UserError(_))
+        ));
+
+        let input_expected: Vec<(&str, &str)> = vec![
+            ("double(21)", "42"),
+            ("map(double, [1, 2])", "[2, 4]"),
+            ("type(double)", "\"function\""),
+            ("len([1, 2])", "-1"),
+            ("let double: fn(x): x~. double(1)", "1"),
+        ];
+
+        test_util::assert_list(
+            input_expected,
+            |expected: &&str, input: &&str| match eval::eval(input, &mut Rc::clone(&env)) {
+                EvaledProgram::Valid(object) => {
+                    assert_eq!(expected, &object.to_string(), "Wrong result for '{input}'")
+                }
+                _ => panic!("'{input}' should evaluate"),
+            },
+        );
+    }
+
+    #[test]
+    fn builtin_wrong_argument_type_test() {
+        for input in [
+            "first(1)",
+            "last(true)",
+            "len(5)",
+            "push(1, 2)",
+            "rest(1)",
+            "map(1, [1])",
+            "map(len, 1)",
+            "range(0, true)",
+            "split(1, \",\")",
+            "split(\"a\", 1)",
+            "contains(\"a\", 1)",
+            "contains(1, 1)",
+            "trim(1)",
+            "trimEnd([\" a\"])",
+            "upper(1)",
+            "lower(false)",
+            "slice(1, 0, 1)",
+            "slice([1], \"0\", 1)",
+            "slice(\"a\", 0, true)",
+            "keys([1])",
+            "values(\"a\")",
+            "arity(1)",
+            "arity(len)",
+            "abs(\"1\")",
+            "min(1, true)",
+            "max([1, \"2\"])",
+            "max(\"a\")",
+            "error(1)",
+            "get([1], 0, 0)",
+            "zip([1], 1)",
+            "zip(\"ab\", [1])",
+            "repeat(1, 2)",
+            "repeat(\"a\", \"2\")",
+            "chars(1)",
+            "chars([\"a\"])",
+            "ord(\"ab\")",
+            "ord(\"\")",
+            "ord(65)",
+            "chr(\"A\")",
+            "sort(1)",
+            "sort([1, \"2\"])",
+            "sort([1, 2.0])",
+            "sort([true, false])",
+            "sort([[1], [2]])",
+            "sort([1], 1)",
+            "sort([1, 2], fn(a, b): true~)",
+            "format(1)",
+            "lines(1)",
+            "lines([\"a\"])",
+        ] {
+            match eval::eval(input, &mut Environment::new_env_reference()) {
+                EvaledProgram::EvalError(EvalError::WrongArgumentType(_, _)) => {}
+                _ => panic!("Expected wrong argument type error for '{input}'"),
+            }
+        }
+    }
+}
@@ -0,0 +1,314 @@
+use crate::parser::{
+    assign_statement::{AssignStatement, ParallelAssignStatement},
+    ast::{BlockStatement, PrefixOperator, Statement},
+    expressions::{
+        expression::Expression,
+        expression_statement::ExpressionStatement,
+        functions::{CallExpression, FunctionLiteral},
+        if_expression::IfExpression,
+    },
+    return_statement::ReturnStatement,
+    while_statement::WhileStatement,
+    ParsedProgram,
+};
+
+use super::{
+    context::{ArithmeticMode, DivisionMode, FloatMode},
+    expression_evaluator::eval_infix_expression,
+    objects::Object,
+};
+
+/// Replaces operations on literals with the literal they evaluate to, so
+/// `2 + 3` becomes `5` before the program is evaluated. Anything involving an
+/// identifier or a call is left as is, as is an operation that would fail or
+/// whose result depends on the arithmetic or division mode of the evaluation.
+pub fn fold_constants(program: ParsedProgram) -> ParsedProgram {
+    match program {
+        ParsedProgram::ValidProgram(statements) => {
+            ParsedProgram::ValidProgram(statements.into_iter().map(fold_statement).collect())
+        }
+        invalid_program => invalid_program,
+    }
+}
+
+fn fold_statement(statement: Statement) -> Statement {
+    match statement {
+        Statement::Assign(assign_statement) => Statement::Assign(fold_assign(assign_statement)),
+        Statement::ConstAssign(assign_statement) => {
+            Statement::ConstAssign(fold_assign(assign_statement))
+        }
+        Statement::ParallelAssign(ParallelAssignStatement {
+            identifiers,
+            assignments,
+        }) => Statement::ParallelAssign(ParallelAssignStatement {
+            identifiers,
+            assignments: fold_expressions(assignments),
+        }),
+        Statement::Return(ReturnStatement { return_value }) => Statement::Return(ReturnStatement {
+            return_value: return_value.map(fold_expression),
+        }),
+        Statement::Expression(ExpressionStatement { expression }) => {
+            Statement::Expression(ExpressionStatement {
+                expression: fold_expression(expression),
+            })
+        }
+        Statement::While(WhileStatement { condition, body }) => Statement::While(WhileStatement {
+            condition: fold_expression(condition),
+            body: fold_block(body),
+        }),
+        Statement::DoWhile(WhileStatement { condition, body }) => {
+            Statement::DoWhile(WhileStatement {
+                condition: fold_expression(condition),
+                body: fold_block(body),
+            })
+        }
+        statement @ (Statement::Import(_) | Statement::Break | Statement::Continue) => statement,
+    }
+}
+
+fn fold_assign(assign_statement: AssignStatement) -> AssignStatement {
+    AssignStatement {
+        indices: fold_expressions(assign_statement.indices),
+        assignment: fold_expression(assign_statement.assignment),
+        ..assign_statement
+    }
+}
+
+fn fold_block(block: BlockStatement) -> BlockStatement {
+    BlockStatement {
+        statements: block.statements.into_iter().map(fold_statement).collect(),
+    }
+}
+
+fn fold_expressions(expressions: Vec<Expression>) -> Vec<Expression> {
+    expressions.into_iter().map(fold_expression).collect()
+}
+
+fn fold_expression(expression: Expression) -> Expression {
+    match expression {
+        Expression::Prefix {
+            right,
+            operator,
+            span,
+        } => match (operator, fold_expression(*right)) {
+            (PrefixOperator::Bang, Expression::BooleanLiteral(boolean)) => {
+                Expression::BooleanLiteral(!boolean)
+            }
+            (PrefixOperator::Minus, Expression::IntegerLiteral(integer)) if integer != i32::MIN => {
+                Expression::IntegerLiteral(-integer)
+            }
+            (PrefixOperator::Minus, Expression::FloatLiteral(float)) => {
+                Expression::FloatLiteral(-float)
+            }
+            (operator, right) => Expression::Prefix {
+                right: Box::new(right),
+                operator,
+                span,
+            },
+        },
+        Expression::Infix {
+            left,
+            right,
+            operator,
+            span,
+        } => {
+            let left = fold_expression(*left);
+            let right = fold_expression(*right);
+
+            // The operation is only folded when it evaluates to the same literal
+            // in every mode, which a checked evaluation that succeeds guarantees
+            // for overflow and non-finite floats but not for rounding.
+            let folded = match (as_constant(&left), as_constant(&right)) {
+                (Some(left), Some(right)) => {
+                    let evaluate = |division_mode| {
+                        eval_infix_expression(
+                            &operator,
+                            left.clone(),
+                            right.clone(),
+                            span,
+                            (ArithmeticMode::Checked, division_mode, FloatMode::Checked),
+                        )
+                        .ok()
+                    };
+                    match (
+                        evaluate(DivisionMode::Truncate),
+                        evaluate(DivisionMode::Floor),
+                    ) {
+                        (Some(truncated), Some(floored)) if truncated == floored => {
+                            as_literal(truncated)
+                        }
+                        _ => None,
+                    }
+                }
+                _ => None,
+            };
+
+            folded.unwrap_or_else(|| Expression::Infix {
+                left: Box::new(left),
+                right: Box::new(right),
+                operator,
+                span,
+            })
+        }
+        Expression::If(IfExpression {
+            condition,
+            consequence,
+            alternative,
+        }) => Expression::If(IfExpression {
+            condition: Box::new(fold_expression(*condition)),
+            consequence: fold_block(consequence),
+            alternative: alternative.map(fold_block),
+        }),
+        Expression::Function(FunctionLiteral {
+            parameters,
+            defaults,
+            rest_parameter,
+            body,
+        }) => Expression::Function(FunctionLiteral {
+            parameters,
+            defaults: fold_expressions(defaults),
+            rest_parameter,
+            body: fold_block(body),
+        }),
+        Expression::Call(CallExpression {
+            function,
+            arguments,
+            span,
+        }) => Expression::Call(CallExpression {
+            function: Box::new(fold_expression(*function)),
+            arguments: fold_expressions(arguments),
+            span,
+        }),
+        Expression::ArrayLiteral(elements) => Expression::ArrayLiteral(fold_expressions(elements)),
+        Expression::TupleLiteral(elements) => Expression::TupleLiteral(fold_expressions(elements)),
+        Expression::Index { left, index, span } => Expression::Index {
+            left: Box::new(fold_expression(*left)),
+            index: Box::new(fold_expression(*index)),
+            span,
+        },
+        Expression::Block(block) => Expression::Block(fold_block(block)),
+        Expression::HashLiteral(pairs) => Expression::HashLiteral(
+            pairs
+                .into_iter()
+                .map(|(key, value)| (fold_expression(key), fold_expression(value)))
+                .collect(),
+        ),
+        literal @ (Expression::IdentifierLiteral(_)
+        | Expression::IntegerLiteral(_)
+        | Expression::FloatLiteral(_)
+        | Expression::BooleanLiteral(_)
+        | Expression::StringLiteral(_)) => literal,
+    }
+}
+
+fn as_constant(expression: &Expression) -> Option<Object> {
+    match expression {
+        Expression::IntegerLiteral(integer) => Some(Object::Integer(*integer)),
+        Expression::FloatLiteral(float) => Some(Object::Float(*float)),
+        Expression::BooleanLiteral(boolean) => Some(Object::Boolean(*boolean)),
+        Expression::StringLiteral(string) => Some(Object::Str(string.clone())),
+        _ => None,
+    }
+}
+
+fn as_literal(object: Object) -> Option<Expression> {
+    match object {
+        Object::Integer(integer) => Some(Expression::IntegerLiteral(integer)),
+        Object::Float(float) => Some(Expression::FloatLiteral(float)),
+        Object::Boolean(boolean) => Some(Expression::BooleanLiteral(boolean)),
+        Object::Str(string) => Some(Expression::StringLiteral(string)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        eval::{
+            self,
+            constant_folding::fold_constants,
+            objects::{Environment, Object},
+            EvaledProgram,
+        },
+        parser::{
+            ast::Statement,
+            expressions::{expression::Expression, expression_statement::ExpressionStatement},
+            test_util, ParsedProgram,
+        },
+    };
+
+    fn fold(input: &str) -> Vec<Statement> {
+        match fold_constants(test_util::parse_program(input)) {
+            ParsedProgram::ValidProgram(statements) => statements,
+            ParsedProgram::InvalidProgram(_) => panic!("'{input}' should parse"),
+        }
+    }
+
+    #[test]
+    fn fold_constant_expressions_test() {
+        let input_expected = vec![
+            ("2 + 3 * 4", Expression::IntegerLiteral(14)),
+            ("!true", Expression::BooleanLiteral(false)),
+            ("-(2 ^ 3)", Expression::IntegerLiteral(-8)),
+            ("1.5 * 2.0", Expression::FloatLiteral(3.0)),
+            ("1 < 2 == true", Expression::BooleanLiteral(true)),
+            ("\"a\" == \"b\"", Expression::BooleanLiteral(false)),
+        ];
+
+        test_util::assert_list(input_expected, |expected: &Expression, input: &&str| {
+            assert_eq!(
+                fold(input),
+                vec![Statement::Expression(ExpressionStatement {
+                    expression: expected.clone()
+                })],
+                "Wrong for '{input}'"
+            );
+        });
+    }
+
+    #[test]
+    fn fold_leaves_non_constants_test() {
+        let unfoldable = [
+            "a + 1",
+            "len(\"a\") + 1",
+            "1 / 0",
+            "2147483647 + 1",
+            "1 + true",
+        ];
+
+        for input in unfoldable {
+            assert_eq!(
+                test_util::expect_parsed_program(input),
+                fold(input),
+                "'{input}' should not be folded"
+            );
+        }
+
+        assert!(
+            matches!(
+                fold("-7 / 2").as_slice(),
+                [Statement::Expression(ExpressionStatement {
+                    expression: Expression::Infix { .. }
+                })]
+            ),
+            "Division that rounds differently by mode should not be folded"
+        );
+
+        assert_eq!(
+            test_util::expect_parsed_program("fn(a): a + 5~. a + 3"),
+            fold("fn(a): a + (2 + 3)~. a + (1 + 2)")
+        );
+    }
+
+    #[test]
+    fn folded_program_evaluates_the_same_test() {
+        let input = "let f: fn(x): x * (2 + 3)~. let a: [1 + 1, -2.5]. f(a[0]) + 10 % 3";
+
+        let folded = fold_constants(test_util::parse_program(input));
+        match eval::eval_program(folded, &mut Environment::new_env_reference()) {
+            EvaledProgram::Valid(object) => assert_eq!(Object::Integer(11), object),
+            _ => panic!("Folded program should evaluate"),
+        }
+        assert_eq!(Object::Integer(11), test_util::expect_evaled_program(input));
+    }
+}
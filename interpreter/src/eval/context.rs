@@ -0,0 +1,311 @@
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    fmt::Debug,
+    io::{stdin, stdout, BufRead, Write},
+    path::{Path, PathBuf},
+    rc::Rc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use crate::parser::ast::Operator;
+
+use super::{
+    builtins::{Builtin, NativeBuiltin, NativeFunction},
+    eval_error::EvalError,
+};
+
+pub type ContextReference = Rc<RefCell<EvalContext>>;
+
+/// Each call of a Lasagna function takes a number of native stack frames, which
+/// are large in an unoptimized build. The default is low enough to be reached
+/// before a 2 MB stack overflows in such a build, which is the stack size of a
+/// thread spawned by the standard library. Embedders running evaluation on a
+/// larger stack can raise it with `set_max_call_depth`.
+pub const DEFAULT_MAX_CALL_DEPTH: usize = 100;
+
+/// How integer operations that overflow are handled.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum ArithmeticMode {
+    /// Overflow is an error.
+    #[default]
+    Checked,
+    /// Overflow wraps around at the bounds of the integer.
+    Wrapping,
+    /// Overflow is clamped to the bounds of the integer.
+    Saturating,
+}
+
+/// How integer division and modulo round when the operands have different
+/// signs.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum DivisionMode {
+    /// The quotient is rounded toward zero, so `-7 / 2` is `-3` and `-7 % 2`
+    /// is `-1`. The remainder has the sign of the dividend.
+    #[default]
+    Truncate,
+    /// The quotient is rounded toward negative infinity, so `-7 / 2` is `-4`
+    /// and `-7 % 2` is `1`. The remainder has the sign of the divisor.
+    Floor,
+}
+
+/// How float operations whose result is not a finite number, like `0.0 / 0.0`
+/// or `1.0 / 0.0`, are handled.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum FloatMode {
+    /// The result is NaN or an infinity, as IEEE 754 specifies. NaN is not
+    /// equal to anything, not even itself.
+    #[default]
+    Ieee,
+    /// A result that is not a finite number is an error.
+    Checked,
+}
+
+/// State that belongs to a whole evaluation rather than a single scope. Every
+/// environment enclosed by another shares the context of its outermost scope.
+#[derive(Debug)]
+pub struct EvalContext {
+    import_stack: Vec<PathBuf>,
+    output: Option<OutputWriter>,
+    input: Option<InputReader>,
+    clock: Option<Clock>,
+    call_depth: usize,
+    max_call_depth: usize,
+    arithmetic_mode: ArithmeticMode,
+    division_mode: DivisionMode,
+    float_mode: FloatMode,
+    steps_taken: usize,
+    step_limit: Option<usize>,
+    allow_top_level_return: bool,
+    native_builtins: HashMap<String, Rc<NativeBuiltin>>,
+    operator_overloads: HashMap<(String, Operator), Rc<NativeBuiltin>>,
+}
+
+#[derive(Clone)]
+pub struct OutputWriter(Rc<RefCell<dyn Write>>);
+
+impl Debug for OutputWriter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "OutputWriter")
+    }
+}
+
+#[derive(Clone)]
+pub struct InputReader(Rc<RefCell<dyn BufRead>>);
+
+impl Debug for InputReader {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "InputReader")
+    }
+}
+
+/// Gives the time since the Unix epoch.
+#[derive(Clone)]
+pub struct Clock(Rc<dyn Fn() -> Duration>);
+
+impl Debug for Clock {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Clock")
+    }
+}
+
+impl EvalContext {
+    pub fn new() -> EvalContext {
+        EvalContext {
+            import_stack: Vec::new(),
+            output: None,
+            input: None,
+            clock: None,
+            call_depth: 0,
+            max_call_depth: DEFAULT_MAX_CALL_DEPTH,
+            arithmetic_mode: ArithmeticMode::default(),
+            division_mode: DivisionMode::default(),
+            float_mode: FloatMode::default(),
+            steps_taken: 0,
+            step_limit: None,
+            allow_top_level_return: true,
+            native_builtins: HashMap::new(),
+            operator_overloads: HashMap::new(),
+        }
+    }
+
+    pub fn new_context_reference() -> ContextReference {
+        Rc::new(RefCell::new(EvalContext::new()))
+    }
+
+    pub fn set_output(&mut self, output: Rc<RefCell<dyn Write>>) {
+        self.output = Some(OutputWriter(output));
+    }
+
+    pub fn write_output(&self, text: &str) -> Result<(), EvalError> {
+        let written = match &self.output {
+            Some(OutputWriter(output)) => writeln!(output.borrow_mut(), "{text}"),
+            None => writeln!(stdout(), "{text}"),
+        };
+
+        written.map_err(|error| EvalError::OutputError(error.to_string()))
+    }
+
+    pub fn set_input(&mut self, input: Rc<RefCell<dyn BufRead>>) {
+        self.input = Some(InputReader(input));
+    }
+
+    /// Reads the next line of input without its line ending, or `None` when
+    /// the input has ended.
+    pub fn read_input_line(&self) -> Result<Option<String>, EvalError> {
+        let mut line = String::new();
+        let read = match &self.input {
+            Some(InputReader(input)) => input.borrow_mut().read_line(&mut line),
+            None => stdin().lock().read_line(&mut line),
+        };
+
+        match read.map_err(|error| EvalError::InputError(error.to_string()))? {
+            0 => Ok(None),
+            _ => {
+                let line_end = line.trim_end_matches(['\n', '\r']).len();
+                line.truncate(line_end);
+                Ok(Some(line))
+            }
+        }
+    }
+
+    pub fn set_clock(&mut self, clock: Rc<dyn Fn() -> Duration>) {
+        self.clock = Some(Clock(clock));
+    }
+
+    /// The time since the Unix epoch, as given by the clock of the context or
+    /// by the system clock if it has none.
+    pub fn now(&self) -> Duration {
+        match &self.clock {
+            Some(Clock(clock)) => clock(),
+            None => SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default(),
+        }
+    }
+
+    pub fn current_import(&self) -> Option<PathBuf> {
+        self.import_stack.last().cloned()
+    }
+
+    pub fn is_importing(&self, path: &Path) -> bool {
+        self.import_stack.iter().any(|imported| imported == path)
+    }
+
+    pub fn push_import(&mut self, path: PathBuf) {
+        self.import_stack.push(path);
+    }
+
+    pub fn pop_import(&mut self) {
+        self.import_stack.pop();
+    }
+
+    pub fn set_max_call_depth(&mut self, max_call_depth: usize) {
+        self.max_call_depth = max_call_depth;
+    }
+
+    pub fn arithmetic_mode(&self) -> ArithmeticMode {
+        self.arithmetic_mode
+    }
+
+    pub fn set_arithmetic_mode(&mut self, arithmetic_mode: ArithmeticMode) {
+        self.arithmetic_mode = arithmetic_mode;
+    }
+
+    pub fn division_mode(&self) -> DivisionMode {
+        self.division_mode
+    }
+
+    pub fn set_division_mode(&mut self, division_mode: DivisionMode) {
+        self.division_mode = division_mode;
+    }
+
+    pub fn float_mode(&self) -> FloatMode {
+        self.float_mode
+    }
+
+    pub fn set_float_mode(&mut self, float_mode: FloatMode) {
+        self.float_mode = float_mode;
+    }
+
+    /// Limits how many statements and expressions may be evaluated before the
+    /// evaluation is stopped. Setting a limit starts counting from zero again.
+    pub fn set_step_limit(&mut self, step_limit: usize) {
+        self.step_limit = Some(step_limit);
+        self.steps_taken = 0;
+    }
+
+    pub fn take_step(&mut self) -> Result<(), EvalError> {
+        if let Some(step_limit) = self.step_limit {
+            if self.steps_taken >= step_limit {
+                return Err(EvalError::StepLimitExceeded(step_limit));
+            }
+        }
+
+        self.steps_taken += 1;
+        Ok(())
+    }
+
+    pub fn allows_top_level_return(&self) -> bool {
+        self.allow_top_level_return
+    }
+
+    /// Decides whether a `return` outside of any function ends the program
+    /// with its value, which it does by default, or is an error.
+    pub fn set_allow_top_level_return(&mut self, allow_top_level_return: bool) {
+        self.allow_top_level_return = allow_top_level_return;
+    }
+
+    pub fn register_builtin(&mut self, name: &str, function: NativeFunction) {
+        self.native_builtins.insert(
+            String::from(name),
+            Rc::new(NativeBuiltin::new(name, function)),
+        );
+    }
+
+    pub fn native_builtin(&self, name: &str) -> Option<Builtin> {
+        self.native_builtins
+            .get(name)
+            .map(|native| Builtin::Native(Rc::clone(native)))
+    }
+
+    /// The function is called with the left and right operand when the left
+    /// operand has the given type name.
+    pub fn register_operator(
+        &mut self,
+        type_name: &str,
+        operator: Operator,
+        function: NativeFunction,
+    ) {
+        let name = format!("{type_name} {operator}");
+        self.operator_overloads.insert(
+            (String::from(type_name), operator),
+            Rc::new(NativeBuiltin::new(&name, function)),
+        );
+    }
+
+    pub fn operator_overload(&self, type_name: &str, operator: &Operator) -> Option<Builtin> {
+        self.operator_overloads
+            .get(&(String::from(type_name), operator.clone()))
+            .map(|native| Builtin::Native(Rc::clone(native)))
+    }
+
+    pub fn enter_call(&mut self) -> Result<(), EvalError> {
+        if self.call_depth >= self.max_call_depth {
+            return Err(EvalError::RecursionLimitExceeded(self.max_call_depth));
+        }
+
+        self.call_depth += 1;
+        Ok(())
+    }
+
+    pub fn exit_call(&mut self) {
+        self.call_depth = self.call_depth.saturating_sub(1);
+    }
+}
+
+impl Default for EvalContext {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -1,10 +1,11 @@
-use std::fmt::Display;
+use std::{error::Error, fmt::Display, ops::RangeInclusive, path::PathBuf};
 
 use crate::{
     eval::objects::FunctionListable,
     parser::{
         ast::{Identifier, Operator},
         expressions::expression::Expression,
+        lexer::span::Span,
     },
 };
 
@@ -19,9 +20,97 @@ pub enum EvalError {
     BooleanInfixOperator(Operator),
     NonBooleanConditional(Object),
     IdentifierNotFound(Identifier),
-    VoidAssignment(Expression),
+    VoidAssignment(Box<Expression>),
     UnexpectedFunctionExpression(Object),
     ArgumentMismatch(Vec<Identifier>, Vec<Object>),
+    /// A built-in without an upper limit on its arguments takes up to
+    /// `usize::MAX` of them.
+    WrongArgumentCount(String, RangeInclusive<usize>, usize),
+    WrongArgumentType(String, Object),
+    ImportFailed {
+        path: PathBuf,
+        reason: String,
+    },
+    OutputError(String),
+    RecursionLimitExceeded(usize),
+    StepLimitExceeded(usize),
+    ConversionError {
+        from: Object,
+        to: String,
+    },
+    ReassignConstant(String),
+    IndexNotSupported(Object, Object),
+    UnhashableKey(Object),
+    IntegerOverflow(i32, Operator, i32),
+    NegativeExponent(i32),
+    DivisionByZero(Span),
+    FloatDomainError(f64, Operator, f64),
+    AssertionFailed(Option<String>),
+    InvalidRange(i32, i32, i32),
+    NegativeRepeatCount(i32),
+    BreakOutsideLoop,
+    ContinueOutsideLoop,
+    ReturnOutsideFunction,
+    UserError(String),
+    TypeMismatch {
+        expected: String,
+        got: Object,
+    },
+    FormatArgumentMismatch {
+        placeholders: usize,
+        arguments: usize,
+    },
+    InvalidFormatString(String),
+    InputError(String),
+    IndexOutOfBounds(i32, usize),
+    /// The seconds since the Unix epoch, when they no longer fit in an integer.
+    TimeOutOfRange(u64),
+}
+
+impl EvalError {
+    /// A code that identifies the kind of error, which stays the same when
+    /// the message changes. Evaluation errors have codes starting at `E1001`.
+    pub fn code(&self) -> &'static str {
+        match self {
+            EvalError::DivisionByZero(_) => "E1001",
+            EvalError::EmptyProgram => "E1002",
+            EvalError::IncorrectBangSuffix(_) => "E1003",
+            EvalError::IntegerInfixOperatorError(_) => "E1004",
+            EvalError::InfixRightLeft(..) => "E1005",
+            EvalError::BooleanInfixOperator(_) => "E1006",
+            EvalError::NonBooleanConditional(_) => "E1007",
+            EvalError::IdentifierNotFound(_) => "E1008",
+            EvalError::VoidAssignment(_) => "E1009",
+            EvalError::UnexpectedFunctionExpression(_) => "E1010",
+            EvalError::ArgumentMismatch(..) => "E1011",
+            EvalError::WrongArgumentCount(..) => "E1012",
+            EvalError::WrongArgumentType(..) => "E1013",
+            EvalError::ImportFailed { .. } => "E1014",
+            EvalError::OutputError(_) => "E1015",
+            EvalError::RecursionLimitExceeded(_) => "E1016",
+            EvalError::StepLimitExceeded(_) => "E1017",
+            EvalError::ConversionError { .. } => "E1018",
+            EvalError::ReassignConstant(_) => "E1019",
+            EvalError::IndexNotSupported(..) => "E1020",
+            EvalError::UnhashableKey(_) => "E1021",
+            EvalError::IntegerOverflow(..) => "E1022",
+            EvalError::NegativeExponent(_) => "E1023",
+            EvalError::FloatDomainError(..) => "E1024",
+            EvalError::AssertionFailed(_) => "E1025",
+            EvalError::InvalidRange(..) => "E1026",
+            EvalError::NegativeRepeatCount(_) => "E1027",
+            EvalError::BreakOutsideLoop => "E1028",
+            EvalError::ContinueOutsideLoop => "E1029",
+            EvalError::ReturnOutsideFunction => "E1030",
+            EvalError::UserError(_) => "E1031",
+            EvalError::TypeMismatch { .. } => "E1032",
+            EvalError::FormatArgumentMismatch { .. } => "E1033",
+            EvalError::InvalidFormatString(_) => "E1034",
+            EvalError::InputError(_) => "E1035",
+            EvalError::IndexOutOfBounds(..) => "E1036",
+            EvalError::TimeOutOfRange(_) => "E1037",
+        }
+    }
 }
 
 impl Display for EvalError {
@@ -58,16 +147,132 @@ impl Display for EvalError {
             EvalError::ArgumentMismatch(params, args) => {
                 writeln!(f, "Passed in arguments to no matche function parameters. Parameters: {} Arguments: {}", params.to_function_string(), args.to_function_string())
             }
+            EvalError::WrongArgumentCount(builtin, expected, found) => {
+                let plural = |count: &usize| match count {
+                    1 => "argument",
+                    _ => "arguments",
+                };
+                match (expected.start(), expected.end()) {
+                    (min, max) if min == max => {
+                        write!(f, "{builtin} takes {min} {}", plural(min))
+                    }
+                    (min, &usize::MAX) => {
+                        write!(f, "{builtin} takes at least {min} {}", plural(min))
+                    }
+                    (min, max) => write!(f, "{builtin} takes {min} to {max} arguments"),
+                }?;
+                writeln!(f, ", but was called with {found}")
+            }
+            EvalError::WrongArgumentType(builtin, object) => {
+                writeln!(f, "{builtin} does not support the argument {object}")
+            }
+            EvalError::ImportFailed { path, reason } => {
+                writeln!(f, "Failed to import {}: {reason}", path.display())
+            }
+            EvalError::OutputError(error) => writeln!(f, "Failed to write output: {error}"),
+            EvalError::InputError(error) => writeln!(f, "Failed to read input: {error}"),
+            EvalError::IndexOutOfBounds(index, length) => writeln!(
+                f,
+                "Can not assign to index {index} of an array of length {length}"
+            ),
+            EvalError::TimeOutOfRange(seconds) => writeln!(
+                f,
+                "The time {seconds} seconds after the Unix epoch does not fit in an integer"
+            ),
+            EvalError::ConversionError { from, to } => {
+                writeln!(f, "Could not convert {from} to {to}")
+            }
+            EvalError::ReassignConstant(identifier) => {
+                writeln!(f, "Can not reassign the constant {identifier}")
+            }
+            EvalError::UnhashableKey(key) => {
+                writeln!(f, "{key} can not be used as a hash key")
+            }
+            EvalError::IndexNotSupported(left, index) => {
+                writeln!(f, "Can not index into {left} with {index}")
+            }
+            EvalError::IntegerOverflow(left, operator, right) => {
+                writeln!(
+                    f,
+                    "Integer overflow when evaluating {left} {operator} {right}"
+                )
+            }
+            EvalError::NegativeExponent(exponent) => {
+                writeln!(
+                    f,
+                    "Integers can not be raised to the negative exponent {exponent}"
+                )
+            }
+            EvalError::DivisionByZero(span) => writeln!(f, "Division by zero at {span}"),
+            EvalError::FloatDomainError(left, operator, right) => writeln!(
+                f,
+                "{} {operator} {} does not give a finite number",
+                Object::Float(*left),
+                Object::Float(*right)
+            ),
+            EvalError::AssertionFailed(Some(message)) => {
+                writeln!(f, "Assertion failed: {message}")
+            }
+            EvalError::AssertionFailed(None) => writeln!(f, "Assertion failed"),
+            EvalError::InvalidRange(start, end, step) => {
+                writeln!(f, "Can not step from {start} to {end} by {step}")
+            }
+            EvalError::NegativeRepeatCount(count) => {
+                writeln!(f, "Can not repeat a value {count} times")
+            }
+            EvalError::BreakOutsideLoop => writeln!(f, "break can only be used inside a loop"),
+            EvalError::UserError(message) => writeln!(f, "Error: {message}"),
+            EvalError::TypeMismatch { expected, got } => writeln!(
+                f,
+                "Expected a value of type {expected}, but got {} of type {}",
+                got,
+                got.type_name()
+            ),
+            EvalError::FormatArgumentMismatch {
+                placeholders,
+                arguments,
+            } => writeln!(
+                f,
+                "Format string has {placeholders} placeholders, but was given {arguments} arguments"
+            ),
+            EvalError::InvalidFormatString(format_string) => writeln!(
+                f,
+                "Format string \"{format_string}\" has a brace that is not part of {{}}, {{{{ or }}}}"
+            ),
+            EvalError::ContinueOutsideLoop => {
+                writeln!(f, "continue can only be used inside a loop")
+            }
+            EvalError::ReturnOutsideFunction => {
+                writeln!(f, "return can only be used inside a function")
+            }
+            EvalError::RecursionLimitExceeded(limit) => {
+                writeln!(f, "Exceeded the maximum recursion depth of {limit}")
+            }
+            EvalError::StepLimitExceeded(limit) => {
+                writeln!(f, "Exceeded the limit of {limit} evaluation steps")
+            }
         }
     }
 }
 
+impl Error for EvalError {}
+
 #[cfg(test)]
 mod tests {
+    use std::{collections::HashSet, error::Error, path::PathBuf};
 
     use crate::{
-        eval::{self, objects::Environment, EvaledProgram},
-        parser::test_util,
+        eval::{
+            self,
+            objects::{Environment, Object},
+            EvaledProgram,
+        },
+        parser::{
+            ast::{Identifier, Operator},
+            expressions::expression::Expression,
+            lexer::span::Span,
+            test_util,
+        },
     };
 
     use super::EvalError;
@@ -97,6 +302,12 @@ mod tests {
                 ",
                 "Operator + is not supported for booleans",
             ),
+            ("str(1, 2)", "str takes 1 argument, but was called with 2"),
+            ("push([1])", "push takes 2 arguments, but was called with 1"),
+            (
+                "min()",
+                "min takes at least 1 argument, but was called with 0",
+            ),
         ];
 
         test_util::assert_list(input_expected, |expected: &&str, input: &&str| {
@@ -124,4 +335,87 @@ mod tests {
             _ => panic!("Code was supposed to return an error"),
         };
     }
+
+    #[test]
+    fn eval_error_can_be_boxed_test() {
+        fn run(input: &str) -> Result<(), Box<dyn Error>> {
+            match eval::eval(input, &mut Environment::new_env_reference()) {
+                EvaledProgram::EvalError(eval_error) => Err(eval_error)?,
+                _ => Ok(()),
+            }
+        }
+
+        let error = run("5 + true").expect_err("Should fail to evaluate");
+        assert!(error.downcast_ref::<EvalError>().is_some());
+        assert_eq!(
+            error.to_string().trim(),
+            "5 and true does not have a common operator"
+        );
+    }
+
+    #[test]
+    fn error_codes_test() {
+        match eval::eval("1 / 0", &mut Environment::new_env_reference()) {
+            EvaledProgram::EvalError(eval_error) => assert_eq!("E1001", eval_error.code()),
+            _ => panic!("Expected division by zero"),
+        }
+        assert_eq!(
+            "E1008",
+            EvalError::IdentifierNotFound(Identifier(String::from("a"))).code()
+        );
+
+        let eval_errors = [
+            EvalError::DivisionByZero(Span::default()),
+            EvalError::EmptyProgram,
+            EvalError::IncorrectBangSuffix(Object::Null),
+            EvalError::IntegerInfixOperatorError(Operator::Plus),
+            EvalError::InfixRightLeft(Object::Null, Object::Null),
+            EvalError::BooleanInfixOperator(Operator::Plus),
+            EvalError::NonBooleanConditional(Object::Null),
+            EvalError::IdentifierNotFound(Identifier(String::from("a"))),
+            EvalError::VoidAssignment(Box::new(Expression::IntegerLiteral(1))),
+            EvalError::UnexpectedFunctionExpression(Object::Null),
+            EvalError::ArgumentMismatch(Vec::new(), Vec::new()),
+            EvalError::WrongArgumentCount(String::new(), 1..=1, 2),
+            EvalError::WrongArgumentType(String::new(), Object::Null),
+            EvalError::ImportFailed {
+                path: PathBuf::new(),
+                reason: String::new(),
+            },
+            EvalError::OutputError(String::new()),
+            EvalError::RecursionLimitExceeded(1),
+            EvalError::StepLimitExceeded(1),
+            EvalError::ConversionError {
+                from: Object::Null,
+                to: String::new(),
+            },
+            EvalError::ReassignConstant(String::new()),
+            EvalError::IndexNotSupported(Object::Null, Object::Null),
+            EvalError::UnhashableKey(Object::Null),
+            EvalError::IntegerOverflow(1, Operator::Plus, 1),
+            EvalError::NegativeExponent(-1),
+            EvalError::FloatDomainError(0.0, Operator::DividedBy, 0.0),
+            EvalError::AssertionFailed(None),
+            EvalError::InvalidRange(0, 1, -1),
+            EvalError::NegativeRepeatCount(-1),
+            EvalError::BreakOutsideLoop,
+            EvalError::ContinueOutsideLoop,
+            EvalError::ReturnOutsideFunction,
+            EvalError::UserError(String::new()),
+            EvalError::TypeMismatch {
+                expected: String::new(),
+                got: Object::Null,
+            },
+            EvalError::FormatArgumentMismatch {
+                placeholders: 1,
+                arguments: 2,
+            },
+            EvalError::InvalidFormatString(String::new()),
+            EvalError::InputError(String::new()),
+            EvalError::IndexOutOfBounds(1, 1),
+            EvalError::TimeOutOfRange(0),
+        ];
+        let codes: HashSet<&str> = eval_errors.iter().map(EvalError::code).collect();
+        assert_eq!(eval_errors.len(), codes.len(), "Codes should be unique");
+    }
 }
@@ -1,50 +1,165 @@
+use std::cmp::Ordering;
+
+use indexmap::IndexMap;
 use tracing::{event, span, Level};
 
 use crate::parser::expressions::if_expression::IfExpression;
 use crate::parser::{
-    ast::{Identifier, Operator, PrefixOperator},
+    ast::{BlockStatement, Identifier, Operator, PrefixOperator},
     expressions::expression::Expression,
+    lexer::span::Span,
 };
 
 use super::{
+    builtins::Builtin,
+    context::{ArithmeticMode, DivisionMode, FloatMode},
     eval_error::EvalError,
-    objects::{EnvReference, Object},
+    objects::{EnvReference, HashKey, Object},
 };
 
 pub(crate) trait Evaluable {
     fn eval(&self, env: &mut EnvReference) -> Result<Object, EvalError>;
 }
 
+/// Every call of a Lasagna function passes through this function more than
+/// once, so the work of each kind of expression is done in a function of its
+/// own. This keeps the stack frame of the dispatch small, which decides how
+/// deep Lasagna programs can recurse before the native stack runs out.
 impl Evaluable for Expression {
     fn eval(&self, env: &mut EnvReference) -> Result<Object, EvalError> {
-        use Object::*;
+        env.borrow().context().borrow_mut().take_step()?;
 
         match self {
-            Expression::IntegerLiteral(number) => {
-                event!(Level::DEBUG, "Evaluated to number {number}");
-                Ok(Integer(*number))
-            }
+            Expression::IntegerLiteral(_)
+            | Expression::FloatLiteral(_)
+            | Expression::BooleanLiteral(_)
+            | Expression::StringLiteral(_) => Ok(eval_literal(self)),
             Expression::IdentifierLiteral(identifier) => {
                 eval_identifier_expression(identifier, env)
             }
-            Expression::BooleanLiteral(boolean) => {
-                event!(Level::DEBUG, "Evaluated to boolean {boolean}");
-                Ok(Boolean(*boolean))
-            }
-            Expression::Prefix { right, operator } => eval_prefix_expression(right, operator, env),
+            Expression::Prefix {
+                right, operator, ..
+            } => eval_prefix_expression(right, operator, env),
             Expression::Infix {
                 left,
                 right,
                 operator,
-            } => {
-                let left = left.eval(env)?;
-                let right = right.eval(env)?;
-                eval_infix_expression(operator, left, right)
-            }
+                span,
+            } => eval_infix_operands(left, right, operator, *span, env),
             Expression::If(if_expression) => if_expression.eval(env),
             Expression::Function(function_literal) => function_literal.eval(env),
             Expression::Call(call_expression) => call_expression.eval(env),
+            Expression::ArrayLiteral(elements) => {
+                Ok(Object::Array(eval_expressions(elements, env)?))
+            }
+            Expression::TupleLiteral(elements) => {
+                Ok(Object::Tuple(eval_expressions(elements, env)?))
+            }
+            Expression::Block(block) => block.eval(env),
+            Expression::HashLiteral(pairs) => eval_hash_literal(pairs, env),
+            Expression::Index { left, index, .. } => eval_index_operands(left, index, env),
+        }
+    }
+}
+
+fn eval_literal(literal: &Expression) -> Object {
+    match literal {
+        Expression::IntegerLiteral(number) => {
+            event!(Level::DEBUG, "Evaluated to number {number}");
+            Object::Integer(*number)
+        }
+        Expression::FloatLiteral(number) => {
+            event!(Level::DEBUG, "Evaluated to float {number}");
+            Object::Float(*number)
+        }
+        Expression::BooleanLiteral(boolean) => {
+            event!(Level::DEBUG, "Evaluated to boolean {boolean}");
+            Object::Boolean(*boolean)
+        }
+        Expression::StringLiteral(string) => Object::Str(string.clone()),
+        _ => unreachable!("Only literals are evaluated as literals"),
+    }
+}
+
+fn eval_infix_operands(
+    left: &Expression,
+    right: &Expression,
+    operator: &Operator,
+    span: Span,
+    env: &mut EnvReference,
+) -> Result<Object, EvalError> {
+    let left = left.eval(env)?;
+    if let Operator::NullCoalesce = operator {
+        return match left {
+            Object::Null => right.eval(env),
+            left => Ok(left),
+        };
+    }
+
+    let right = right.eval(env)?;
+    if let Some(overload) = find_operator_overload(operator, &left, &right, env) {
+        return overload.call(&[left, right], env);
+    }
+
+    let context = env.borrow().context();
+    let modes = (
+        context.borrow().arithmetic_mode(),
+        context.borrow().division_mode(),
+        context.borrow().float_mode(),
+    );
+    eval_infix_expression(operator, left, right, span, modes)
+}
+
+fn eval_index_operands(
+    left: &Expression,
+    index: &Expression,
+    env: &mut EnvReference,
+) -> Result<Object, EvalError> {
+    let left = left.eval(env)?;
+    let index = index.eval(env)?;
+    eval_index_expression(left, index)
+}
+
+fn eval_expressions(
+    expressions: &[Expression],
+    env: &mut EnvReference,
+) -> Result<Vec<Object>, EvalError> {
+    expressions
+        .iter()
+        .map(|expression| expression.eval(env))
+        .collect::<Result<Vec<Object>, EvalError>>()
+}
+
+/// Later pairs overwrite earlier pairs with the same key, while the key keeps
+/// its original position.
+fn eval_hash_literal(
+    pairs: &[(Expression, Expression)],
+    env: &mut EnvReference,
+) -> Result<Object, EvalError> {
+    let mut hash = IndexMap::new();
+    for (key, value) in pairs {
+        let key = HashKey::try_from(key.eval(env)?)?;
+        hash.insert(key, value.eval(env)?);
+    }
+
+    Ok(Object::Hash(Box::new(hash)))
+}
+
+/// Indexing outside the bounds of a collection, or with a key missing from a
+/// hash, evaluates to null.
+fn eval_index_expression(left: Object, index: Object) -> Result<Object, EvalError> {
+    match (left, index) {
+        (Object::Hash(pairs), key) => Ok(pairs
+            .get(&HashKey::try_from(key)?)
+            .cloned()
+            .unwrap_or(Object::Null)),
+        (Object::Array(elements) | Object::Tuple(elements), Object::Integer(index)) => {
+            Ok(usize::try_from(index)
+                .ok()
+                .and_then(|index| elements.get(index).cloned())
+                .unwrap_or(Object::Null))
         }
+        (left, index) => Err(EvalError::IndexNotSupported(left, index)),
     }
 }
 
@@ -52,8 +167,17 @@ fn eval_identifier_expression(
     identifier: &Identifier,
     env: &EnvReference,
 ) -> Result<Object, EvalError> {
-    match env.borrow().get_identifier(&identifier.0) {
-        Some(object) => Ok(object),
+    if let Some(object) = env.borrow().get_identifier(&identifier.0) {
+        return Ok(object);
+    }
+
+    let native_builtin = env
+        .borrow()
+        .context()
+        .borrow()
+        .native_builtin(&identifier.0);
+    match native_builtin.or_else(|| Builtin::lookup(&identifier.0)) {
+        Some(builtin) => Ok(Object::Builtin(builtin)),
         None => Err(EvalError::IdentifierNotFound(identifier.clone())),
     }
 }
@@ -64,36 +188,103 @@ impl Evaluable for IfExpression {
         let _enter = expression_statement_span.enter();
         event!(Level::DEBUG, "Evaluating if condition");
 
-        let evaluated_condition = match self.condition.eval(env)? {
-            Object::Boolean(boolean) => boolean,
-            unexpected_condition => {
-                return Err(EvalError::NonBooleanConditional(unexpected_condition))
-            }
-        };
+        // A return value from either branch is passed on as is, so a return
+        // inside the if ends the surrounding function rather than the block.
+        match self.eval_branch(env)? {
+            Some(branch) => branch.eval(env),
+            None => Ok(Object::Void),
+        }
+    }
+}
 
-        match evaluated_condition {
-            true => Ok(self.consequence.eval(env)?),
-            false => match &self.alternative {
-                Some(alternative) => Ok(alternative.eval(env)?),
-                None => Ok(Object::Void),
-            },
+impl IfExpression {
+    /// Evaluates the condition, and gives the branch it selects, if any.
+    pub(super) fn eval_branch(
+        &self,
+        env: &mut EnvReference,
+    ) -> Result<Option<&BlockStatement>, EvalError> {
+        match self.condition.eval(env)? {
+            Object::Boolean(true) => Ok(Some(&self.consequence)),
+            Object::Boolean(false) => Ok(self.alternative.as_ref()),
+            unexpected_condition => Err(EvalError::NonBooleanConditional(unexpected_condition)),
         }
     }
 }
 
-fn eval_infix_expression(
+/// Operators on the built-in numeric, boolean and string types always have
+/// their built-in meaning, while the meaning of an operator on any other type
+/// can be registered for the type of its left operand.
+fn find_operator_overload(
+    operator: &Operator,
+    left: &Object,
+    right: &Object,
+    env: &EnvReference,
+) -> Option<Builtin> {
+    let is_primitive = |object: &Object| {
+        matches!(
+            object,
+            Object::Integer(_) | Object::Float(_) | Object::Boolean(_) | Object::Str(_)
+        )
+    };
+    if is_primitive(left) || is_primitive(right) {
+        return None;
+    }
+
+    let context = env.borrow().context();
+    let overload = context
+        .borrow()
+        .operator_overload(left.type_name(), operator);
+    overload
+}
+
+pub(super) fn eval_infix_expression(
     operator: &crate::parser::ast::Operator,
     left: Object,
     right: Object,
+    span: Span,
+    (mode, division_mode, float_mode): (ArithmeticMode, DivisionMode, FloatMode),
 ) -> Result<Object, EvalError> {
     use Object::*;
+
+    // Equality is defined for every pair of objects by their `PartialEq`
+    // implementation, so objects of different types are simply unequal.
+    match operator {
+        Operator::Equals => return Ok(Boolean(left == right)),
+        Operator::NotEquals => return Ok(Boolean(left != right)),
+        _ => {}
+    }
+
+    let comparison: Option<fn(Ordering) -> bool> = match operator {
+        Operator::LessThan => Some(Ordering::is_lt),
+        Operator::GreaterThan => Some(Ordering::is_gt),
+        Operator::LessThanOrEqual => Some(Ordering::is_le),
+        Operator::GreaterThanOrEqual => Some(Ordering::is_ge),
+        _ => None,
+    };
+    if let Some(comparison) = comparison {
+        return match left.partial_cmp(&right) {
+            Some(ordering) => Ok(Boolean(comparison(ordering))),
+            // NaN is unordered, so as in IEEE 754 every comparison with it is false.
+            None if matches!((&left, &right), (Float(_), Float(_))) => Ok(Boolean(false)),
+            None => Err(EvalError::InfixRightLeft(left, right)),
+        };
+    }
+
     match (left, right) {
-        (Integer(left_integer), Integer(right_integer)) => {
-            eval_integer_infix_expression(left_integer, right_integer, operator)
-        }
-        (Boolean(left_boolean), Boolean(right_boolean)) => {
-            eval_boolean_infix_expression(left_boolean, right_boolean, operator)
-        }
+        (Integer(left_integer), Integer(right_integer)) => eval_integer_infix_expression(
+            left_integer,
+            right_integer,
+            operator,
+            span,
+            (mode, division_mode),
+        ),
+        (Float(left_float), Float(right_float)) => eval_float_infix_expression(
+            left_float,
+            right_float,
+            operator,
+            (division_mode, float_mode),
+        ),
+        (Boolean(_), Boolean(_)) => Err(EvalError::BooleanInfixOperator(operator.clone())),
         (unexpected_left, unexpected_right) => Err(EvalError::InfixRightLeft(
             unexpected_left.clone(),
             unexpected_right.clone(),
@@ -101,46 +292,145 @@ fn eval_infix_expression(
     }
 }
 
-fn eval_boolean_infix_expression(
-    left_boolean: bool,
-    right_boolean: bool,
-    operator: &Operator,
+fn eval_integer_infix_expression(
+    left_integer: i32,
+    right_integer: i32,
+    operator: &crate::parser::ast::Operator,
+    span: Span,
+    (mode, division_mode): (ArithmeticMode, DivisionMode),
 ) -> Result<Object, EvalError> {
     use Object::*;
 
     Ok(match operator {
-        Operator::Equals => Boolean(left_boolean == right_boolean),
-        Operator::NotEquals => Boolean(left_boolean != right_boolean),
-        unsupported_operator => {
-            return Err(EvalError::BooleanInfixOperator(
-                unsupported_operator.clone(),
-            ))
+        Operator::Minus | Operator::Plus | Operator::Multiply => Integer(eval_integer_arithmetic(
+            left_integer,
+            operator,
+            right_integer,
+            mode,
+        )?),
+        Operator::DividedBy | Operator::Modulo => Integer(eval_integer_division(
+            left_integer,
+            operator,
+            right_integer,
+            span,
+            (mode, division_mode),
+        )?),
+        Operator::Power => Integer(eval_integer_power(left_integer, right_integer, mode)?),
+        unexpected_operator => {
+            return Err(EvalError::IntegerInfixOperatorError(
+                unexpected_operator.clone(),
+            ));
         }
     })
 }
 
-fn eval_integer_infix_expression(
-    left_integer: i32,
-    right_integer: i32,
-    operator: &crate::parser::ast::Operator,
-) -> Result<Object, EvalError> {
-    use Object::*;
+type IntegerOperations = (
+    fn(i32, i32) -> Option<i32>,
+    fn(i32, i32) -> i32,
+    fn(i32, i32) -> i32,
+);
+
+fn eval_integer_arithmetic(
+    left: i32,
+    operator: &Operator,
+    right: i32,
+    mode: ArithmeticMode,
+) -> Result<i32, EvalError> {
+    let (checked, wrapping, saturating): IntegerOperations = match operator {
+        Operator::Plus => (i32::checked_add, i32::wrapping_add, i32::saturating_add),
+        Operator::Minus => (i32::checked_sub, i32::wrapping_sub, i32::saturating_sub),
+        Operator::Multiply => (i32::checked_mul, i32::wrapping_mul, i32::saturating_mul),
+        Operator::DividedBy => (i32::checked_div, i32::wrapping_div, i32::saturating_div),
+        // The only overflowing remainder is `i32::MIN % -1`, which is zero.
+        Operator::Modulo => (i32::checked_rem, i32::wrapping_rem, i32::wrapping_rem),
+        unexpected_operator => {
+            return Err(EvalError::IntegerInfixOperatorError(
+                unexpected_operator.clone(),
+            ))
+        }
+    };
+
+    match mode {
+        ArithmeticMode::Checked => checked(left, right)
+            .ok_or_else(|| EvalError::IntegerOverflow(left, operator.clone(), right)),
+        ArithmeticMode::Wrapping => Ok(wrapping(left, right)),
+        ArithmeticMode::Saturating => Ok(saturating(left, right)),
+    }
+}
+
+/// Evaluates both `/` and `%`. Dividing by zero is an error in every
+/// arithmetic mode. Flooring only changes the result when the division has a
+/// remainder and the operands have different signs.
+fn eval_integer_division(
+    dividend: i32,
+    operator: &Operator,
+    divisor: i32,
+    span: Span,
+    (mode, division_mode): (ArithmeticMode, DivisionMode),
+) -> Result<i32, EvalError> {
+    if divisor == 0 {
+        return Err(EvalError::DivisionByZero(span));
+    }
+
+    let truncated = eval_integer_arithmetic(dividend, operator, divisor, mode)?;
+    let floors = division_mode == DivisionMode::Floor
+        && dividend.wrapping_rem(divisor) != 0
+        && (dividend < 0) != (divisor < 0);
+    if !floors {
+        return Ok(truncated);
+    }
 
     Ok(match operator {
-        Operator::Minus => Integer(left_integer - right_integer),
-        Operator::Plus => Integer(left_integer + right_integer),
-        Operator::Multiply => Integer(left_integer * right_integer),
-        Operator::DividedBy => Integer(left_integer / right_integer),
-        Operator::LessThan => Boolean(left_integer < right_integer),
-        Operator::GreaterThan => Boolean(left_integer > right_integer),
-        Operator::Equals => Boolean(left_integer == right_integer),
-        Operator::NotEquals => Boolean(left_integer != right_integer),
+        Operator::Modulo => truncated + divisor,
+        _ => truncated - 1,
+    })
+}
+
+fn eval_integer_power(base: i32, exponent: i32, mode: ArithmeticMode) -> Result<i32, EvalError> {
+    let overflow = || EvalError::IntegerOverflow(base, Operator::Power, exponent);
+
+    let Ok(unsigned_exponent) = u32::try_from(exponent) else {
+        return Err(EvalError::NegativeExponent(exponent));
+    };
+
+    match mode {
+        ArithmeticMode::Checked => base.checked_pow(unsigned_exponent).ok_or_else(overflow),
+        ArithmeticMode::Wrapping => Ok(base.wrapping_pow(unsigned_exponent)),
+        ArithmeticMode::Saturating => Ok(base.saturating_pow(unsigned_exponent)),
+    }
+}
+
+fn eval_float_infix_expression(
+    left_float: f64,
+    right_float: f64,
+    operator: &Operator,
+    (division_mode, float_mode): (DivisionMode, FloatMode),
+) -> Result<Object, EvalError> {
+    let result = match operator {
+        Operator::Minus => left_float - right_float,
+        Operator::Plus => left_float + right_float,
+        Operator::Multiply => left_float * right_float,
+        Operator::DividedBy => left_float / right_float,
+        Operator::Power => left_float.powf(right_float),
+        Operator::Modulo => match division_mode {
+            DivisionMode::Truncate => left_float % right_float,
+            DivisionMode::Floor => left_float - right_float * (left_float / right_float).floor(),
+        },
         unexpected_operator => {
             return Err(EvalError::IntegerInfixOperatorError(
                 unexpected_operator.clone(),
             ));
         }
-    })
+    };
+
+    match float_mode {
+        FloatMode::Checked if !result.is_finite() => Err(EvalError::FloatDomainError(
+            left_float,
+            operator.clone(),
+            right_float,
+        )),
+        _ => Ok(Object::Float(result)),
+    }
 }
 
 fn eval_prefix_expression(
@@ -151,13 +441,27 @@ fn eval_prefix_expression(
     let right = right.eval(env)?;
     match operator {
         PrefixOperator::Bang => eval_bang_operator_expression(&right),
-        PrefixOperator::Minus => eval_minus_operator_expression(&right),
+        PrefixOperator::Minus => {
+            let mode = env.borrow().context().borrow().arithmetic_mode();
+            eval_minus_operator_expression(&right, mode)
+        }
     }
 }
 
-fn eval_minus_operator_expression(right: &Object) -> Result<Object, EvalError> {
+/// Negating an integer is subtracting it from zero, which overflows for the
+/// smallest integer and is handled by the arithmetic mode like other overflow.
+fn eval_minus_operator_expression(
+    right: &Object,
+    mode: ArithmeticMode,
+) -> Result<Object, EvalError> {
     match right {
-        Object::Integer(integer_value) => Ok(Object::Integer(-integer_value)),
+        Object::Integer(integer_value) => Ok(Object::Integer(eval_integer_arithmetic(
+            0,
+            &Operator::Minus,
+            *integer_value,
+            mode,
+        )?)),
+        Object::Float(float_value) => Ok(Object::Float(-float_value)),
         unexpected_object => Err(EvalError::IncorrectBangSuffix(unexpected_object.clone())),
     }
 }
@@ -171,7 +475,18 @@ fn eval_bang_operator_expression(right: &Object) -> Result<Object, EvalError> {
 
 #[cfg(test)]
 mod tests {
-    use crate::{eval::objects::Object, parser::test_util};
+    use std::rc::Rc;
+
+    use crate::{
+        eval::{
+            self,
+            context::{ArithmeticMode, DivisionMode, FloatMode},
+            eval_error::EvalError,
+            objects::{Environment, Object},
+            EvaledProgram,
+        },
+        parser::{ast::Operator, test_util},
+    };
 
     #[test]
     fn eval_integer_expression_test() {
@@ -191,6 +506,10 @@ mod tests {
             ("3 * 3 * 3 + 10", 37),
             ("3 * (3 * 3) + 10", 37),
             ("(5 + 10 * 2 + 15 / 3) * 2 + -10", 50),
+            ("2 ^ 10", 1024),
+            ("2 ^ 0", 1),
+            ("2 ^ 2 ^ 3", 256),
+            ("3 * 2 ^ 2", 12),
         ];
 
         let asserter = |expected: &i32, input: &&str| {
@@ -216,6 +535,13 @@ mod tests {
             ("1 > 2", false),
             ("1 < 1", false),
             ("1 > 1", false),
+            ("5 <= 5", true),
+            ("4 <= 5", true),
+            ("6 <= 5", false),
+            ("5 >= 5", true),
+            ("4 >= 5", false),
+            ("1.5 <= 1.5", true),
+            ("1.5 >= 2.5", false),
             ("1 == 1", true),
             ("1 != 1", false),
             ("1 == 2", false),
@@ -286,4 +612,393 @@ mod tests {
             }
         });
     }
+
+    #[test]
+    fn eval_string_expression_test() {
+        let object = test_util::expect_evaled_program("\"hi\"");
+
+        match &object {
+            Object::Str(string) => assert_eq!("hi", string),
+            something_else => panic!("Expected string, got {something_else}"),
+        }
+        assert_eq!("\"hi\"", object.to_string(), "Results should be quoted");
+        assert_eq!("hi", object.to_print_string(), "Printed strings are raw");
+
+        let nested = test_util::expect_evaled_program("[1, \"a\"]");
+        assert_eq!("[1, \"a\"]", nested.to_print_string());
+
+        assert!(matches!(
+            test_util::expect_evaled_program("\"apple\" < \"banana\""),
+            Object::Boolean(true)
+        ));
+        assert!(matches!(
+            eval::eval("1 < \"banana\"", &mut Environment::new_env_reference()),
+            EvaledProgram::EvalError(EvalError::InfixRightLeft(..))
+        ));
+    }
+
+    #[test]
+    fn eval_float_expression_test() {
+        let input_expected: Vec<(&str, f64)> = vec![
+            ("2.5", 2.5),
+            ("-2.5", -2.5),
+            ("1.5 + 1.25", 2.75),
+            ("5.0 / 2.0", 2.5),
+            ("2.0 * (1.5 - 0.5)", 2.0),
+        ];
+
+        test_util::assert_list(input_expected, |expected: &f64, input: &&str| {
+            match test_util::expect_evaled_program(input) {
+                Object::Float(float) => assert_eq!(expected, &float, "Wrong for '{input}'"),
+                something_else => panic!("Expected float, got {something_else} for '{input}'"),
+            }
+        });
+    }
+
+    #[test]
+    fn eval_index_expression_test() {
+        let input_expected: Vec<(&str, &str)> = vec![
+            ("[1, 2, 3][0]", "1"),
+            ("[1, 2, 3][1 + 1]", "3"),
+            ("let a: [1, 2, 3]. a[2]", "3"),
+            ("[1, 2, 3][3]", "null"),
+            ("[1, 2, 3][-1]", "null"),
+            ("(1, 2)", "(1, 2)"),
+            ("(1, 2)[1]", "2"),
+            ("let pair: fn(): (1, true)~ pair()[1]", "true"),
+        ];
+
+        test_util::assert_list(input_expected, |expected: &&str, input: &&str| {
+            let object = test_util::expect_evaled_program(input);
+            assert_eq!(expected, &object.to_string(), "Wrong result for '{input}'");
+        });
+    }
+
+    #[test]
+    fn eval_power_errors_test() {
+        assert!(matches!(
+            eval::eval("2 ^ 31", &mut Environment::new_env_reference()),
+            EvaledProgram::EvalError(EvalError::IntegerOverflow(2, Operator::Power, 31))
+        ));
+        assert!(matches!(
+            eval::eval("2 ^ -1", &mut Environment::new_env_reference()),
+            EvaledProgram::EvalError(EvalError::NegativeExponent(-1))
+        ));
+
+        match test_util::expect_evaled_program("2.0 ^ 0.5") {
+            Object::Float(float) => assert_eq!(2f64.sqrt(), float),
+            something_else => panic!("Expected float, got {something_else}"),
+        }
+    }
+
+    #[test]
+    fn division_by_zero_reports_span_test() {
+        let input = "let a: 10. a + 6 / (3 - 3)";
+
+        match eval::eval(input, &mut Environment::new_env_reference()) {
+            EvaledProgram::EvalError(EvalError::DivisionByZero(span)) => {
+                assert_eq!(&input[span.start..span.end], "6 / (3 - 3)")
+            }
+            _ => panic!("Expected division by zero"),
+        }
+    }
+
+    #[test]
+    fn arithmetic_mode_test() {
+        let overflowing = "2147483647 + 1";
+
+        assert!(matches!(
+            eval::eval(overflowing, &mut Environment::new_env_reference()),
+            EvaledProgram::EvalError(EvalError::IntegerOverflow(i32::MAX, Operator::Plus, 1))
+        ));
+
+        let mode_expected = [
+            (ArithmeticMode::Wrapping, i32::MIN),
+            (ArithmeticMode::Saturating, i32::MAX),
+        ];
+        for (mode, expected) in mode_expected {
+            let mut env = Environment::new_env_reference();
+            env.borrow()
+                .context()
+                .borrow_mut()
+                .set_arithmetic_mode(mode);
+
+            match eval::eval(overflowing, &mut env) {
+                EvaledProgram::Valid(Object::Integer(integer)) => {
+                    assert_eq!(expected, integer, "Wrong result in {mode:?} mode")
+                }
+                _ => panic!("Expected integer in {mode:?} mode"),
+            }
+        }
+    }
+
+    #[test]
+    fn negation_arithmetic_mode_test() {
+        let overflowing = "-(-2147483647 - 1)";
+
+        assert!(matches!(
+            eval::eval(overflowing, &mut Environment::new_env_reference()),
+            EvaledProgram::EvalError(EvalError::IntegerOverflow(0, Operator::Minus, i32::MIN))
+        ));
+
+        let mode_expected = [
+            (ArithmeticMode::Wrapping, i32::MIN),
+            (ArithmeticMode::Saturating, i32::MAX),
+        ];
+        for (mode, expected) in mode_expected {
+            let mut env = Environment::new_env_reference();
+            env.borrow()
+                .context()
+                .borrow_mut()
+                .set_arithmetic_mode(mode);
+
+            match eval::eval(overflowing, &mut env) {
+                EvaledProgram::Valid(Object::Integer(integer)) => {
+                    assert_eq!(expected, integer, "Wrong result in {mode:?} mode")
+                }
+                _ => panic!("Expected integer in {mode:?} mode"),
+            }
+        }
+    }
+
+    #[test]
+    fn float_mode_test() {
+        match test_util::expect_evaled_program("0.0 / 0.0") {
+            Object::Float(float) => assert!(float.is_nan()),
+            something_else => panic!("Expected NaN, got {something_else}"),
+        }
+        match test_util::expect_evaled_program("1.0 / 0.0") {
+            Object::Float(float) => assert_eq!(f64::INFINITY, float),
+            something_else => panic!("Expected infinity, got {something_else}"),
+        }
+
+        let input_expected: Vec<(&str, bool)> = vec![
+            ("let nan: 0.0 / 0.0. nan == nan", false),
+            ("let nan: 0.0 / 0.0. nan != nan", true),
+            ("let nan: 0.0 / 0.0. nan < 1.0", false),
+            ("let nan: 0.0 / 0.0. nan >= 1.0", false),
+            ("1.0 / 0.0 > 1000000.0", true),
+        ];
+        test_util::assert_list(input_expected, |expected: &bool, input: &&str| {
+            match test_util::expect_evaled_program(input) {
+                Object::Boolean(boolean) => assert_eq!(expected, &boolean, "Wrong for '{input}'"),
+                something_else => panic!("Expected boolean, got {something_else} for '{input}'"),
+            }
+        });
+
+        let mut env = Environment::new_env_reference();
+        env.borrow()
+            .context()
+            .borrow_mut()
+            .set_float_mode(FloatMode::Checked);
+        for input in ["0.0 / 0.0", "1.0 / 0.0", "10.0 ^ 400.0", "1.0 % 0.0"] {
+            assert!(
+                matches!(
+                    eval::eval(input, &mut env),
+                    EvaledProgram::EvalError(EvalError::FloatDomainError(..))
+                ),
+                "Expected '{input}' to fail"
+            );
+        }
+        match eval::eval("1.0 / 4.0", &mut env) {
+            EvaledProgram::Valid(Object::Float(float)) => assert_eq!(0.25, float),
+            _ => panic!("Expected finite float division to succeed"),
+        }
+    }
+
+    #[test]
+    fn division_mode_test() {
+        let input_truncated_floored = [
+            ("-7 / 2", -3, -4),
+            ("7 / -2", -3, -4),
+            ("-7 / -2", 3, 3),
+            ("7 / 2", 3, 3),
+            ("-8 / 2", -4, -4),
+            ("-7 % 2", -1, 1),
+            ("7 % -2", 1, -1),
+            ("-7 % -2", -1, -1),
+            ("7 % 2", 1, 1),
+            ("-8 % 2", 0, 0),
+        ];
+
+        for (input, truncated, floored) in input_truncated_floored {
+            for (mode, expected) in [
+                (DivisionMode::Truncate, truncated),
+                (DivisionMode::Floor, floored),
+            ] {
+                let mut env = Environment::new_env_reference();
+                env.borrow().context().borrow_mut().set_division_mode(mode);
+
+                match eval::eval(input, &mut env) {
+                    EvaledProgram::Valid(Object::Integer(integer)) => {
+                        assert_eq!(
+                            expected, integer,
+                            "Wrong result for '{input}' in {mode:?} mode"
+                        )
+                    }
+                    _ => panic!("Expected integer for '{input}' in {mode:?} mode"),
+                }
+            }
+        }
+
+        let mut env = Environment::new_env_reference();
+        env.borrow()
+            .context()
+            .borrow_mut()
+            .set_division_mode(DivisionMode::Floor);
+        assert_eq!(
+            "-2.0",
+            test_util::expect_evaled_program("-5.5 % 3.5").to_string()
+        );
+        match eval::eval("-5.5 % 3.5", &mut env) {
+            EvaledProgram::Valid(Object::Float(float)) => assert_eq!(1.5, float),
+            _ => panic!("Expected float modulo to floor"),
+        }
+        assert!(matches!(
+            eval::eval("1 % 0", &mut env),
+            EvaledProgram::EvalError(EvalError::DivisionByZero(_))
+        ));
+    }
+
+    #[test]
+    fn eval_block_expression_test() {
+        let input_expected: Vec<(&str, i32)> = vec![
+            ("let x: { let a: 1. a + 1 }. x", 2),
+            ("let a: 5. let x: { let a: 1. a * 10 }. x + a", 15),
+            ("{ 3 } * { 4 }", 12),
+        ];
+
+        test_util::assert_list(input_expected, |expected: &i32, input: &&str| {
+            match test_util::expect_evaled_program(input) {
+                Object::Integer(integer) => assert_eq!(expected, &integer, "Wrong for '{input}'"),
+                something_else => panic!("Expected integer, got {something_else} for '{input}'"),
+            }
+        });
+    }
+
+    #[test]
+    fn eval_hash_literal_test() {
+        let input_expected: Vec<(&str, &str)> = vec![
+            ("{\"a\": 1, 2: true}", "{\"a\": 1, 2: true}"),
+            ("{}", "{}"),
+            ("{\"a\": 1, \"a\": 2}", "{\"a\": 2}"),
+            ("let key: \"b\". {key: 1 + 1}[\"b\"]", "2"),
+            ("{true: \"yes\"}[true]", "\"yes\""),
+            ("{1: 2}[3]", "null"),
+        ];
+
+        test_util::assert_list(input_expected, |expected: &&str, input: &&str| {
+            let object = test_util::expect_evaled_program(input);
+            assert_eq!(expected, &object.to_string(), "Wrong result for '{input}'");
+        });
+
+        assert!(matches!(
+            eval::eval("{[1]: 2}", &mut Environment::new_env_reference()),
+            EvaledProgram::EvalError(EvalError::UnhashableKey(Object::Array(_)))
+        ));
+    }
+
+    #[test]
+    fn eval_null_coalescing_test() {
+        let input_expected: Vec<(&str, &str)> = vec![
+            ("[1][5] ?? 2", "2"),
+            ("1 ?? 2", "1"),
+            ("false ?? true", "false"),
+            ("[][0] ?? [][1] ?? 3", "3"),
+            ("{\"a\": 1}[\"b\"] ?? 1 + 1", "2"),
+            ("1 ?? missing", "1"),
+            ("1 ?? assert(false)", "1"),
+        ];
+
+        test_util::assert_list(input_expected, |expected: &&str, input: &&str| {
+            let object = test_util::expect_evaled_program(input);
+            assert_eq!(expected, &object.to_string(), "Wrong result for '{input}'");
+        });
+
+        assert!(matches!(
+            eval::eval("[][0] ?? missing", &mut Environment::new_env_reference()),
+            EvaledProgram::EvalError(EvalError::IdentifierNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn eval_equality_test() {
+        let input_expected: Vec<(&str, bool)> = vec![
+            ("[1, 2] == [1, 2]", true),
+            ("[1, 2] == [2, 1]", false),
+            ("[[1], \"a\"] == [[1], \"a\"]", true),
+            ("[1, 2] != [1, 2, 3]", true),
+            ("\"lasagna\" == \"lasagna\"", true),
+            ("\"lasagna\" != \"pasta\"", true),
+            ("1 == \"1\"", false),
+            ("1 != true", true),
+            ("[] == {}", false),
+            ("[][0] == [1][5]", true),
+            ("[][0] == false", false),
+            ("{\"a\": 1} == {\"a\": 1}", true),
+        ];
+
+        test_util::assert_list(input_expected, |expected: &bool, input: &&str| {
+            match test_util::expect_evaled_program(input) {
+                Object::Boolean(boolean) => assert_eq!(expected, &boolean, "Wrong for '{input}'"),
+                something_else => panic!("Expected boolean, got {something_else} for '{input}'"),
+            }
+        });
+    }
+
+    #[test]
+    fn operator_overload_test() {
+        let env = Environment::new_env_reference();
+        env.borrow().register_operator(
+            "tuple",
+            Operator::Plus,
+            Box::new(|operands| match operands.as_slice() {
+                [Object::Tuple(left), Object::Tuple(right)] => Ok(Object::Tuple(
+                    left.iter()
+                        .zip(right)
+                        .map(|pair| match pair {
+                            (Object::Integer(left), Object::Integer(right)) => {
+                                Ok(Object::Integer(left + right))
+                            }
+                            _ => Err(EvalError::UserError(String::from("expected integers"))),
+                        })
+                        .collect::<Result<Vec<Object>, EvalError>>()?,
+                )),
+                _ => Err(EvalError::UserError(String::from("expected tuples"))),
+            }),
+        );
+        env.borrow()
+            .register_operator("integer", Operator::Plus, Box::new(|_| Ok(Object::Null)));
+
+        let input_expected: Vec<(&str, &str)> = vec![
+            ("(1, 2) + (3, 4)", "(4, 6)"),
+            ("let a: (1, 1). a + a + a", "(3, 3)"),
+            ("1 + 2", "3"),
+            ("(1, 2) == (1, 2)", "true"),
+        ];
+
+        test_util::assert_list(
+            input_expected,
+            |expected: &&str, input: &&str| match eval::eval(input, &mut Rc::clone(&env)) {
+                EvaledProgram::Valid(object) => {
+                    assert_eq!(expected, &object.to_string(), "Wrong result for '{input}'")
+                }
+                _ => panic!("'{input}' should evaluate"),
+            },
+        );
+
+        for input in ["(1, 2) + [3, 4]", "(1, 2) - (3, 4)", "(1, 2) + 3"] {
+            assert!(
+                matches!(
+                    eval::eval(input, &mut Rc::clone(&env)),
+                    EvaledProgram::EvalError(_)
+                ),
+                "'{input}' should fail"
+            );
+        }
+        assert!(matches!(
+            eval::eval("(1, 2) + (3, 4)", &mut Environment::new_env_reference()),
+            EvaledProgram::EvalError(EvalError::InfixRightLeft(..))
+        ));
+    }
 }
@@ -1,8 +1,13 @@
-use std::rc::Rc;
+use std::{ptr, rc::Rc};
 
 use crate::parser::{
-    ast::{BlockStatement, Identifier},
-    expressions::functions::{CallExpression, FunctionLiteral},
+    ast::{BlockStatement, Identifier, Statement},
+    expressions::{
+        expression::Expression,
+        expression_statement::ExpressionStatement,
+        functions::{CallExpression, FunctionLiteral},
+    },
+    return_statement::ReturnStatement,
 };
 
 use super::{
@@ -14,57 +19,231 @@ use super::{
 #[derive(Debug, Clone)]
 pub struct FunctionObject {
     pub parameters: Vec<Identifier>,
+    pub defaults: Vec<Expression>,
+    pub rest_parameter: Option<Identifier>,
     pub body: BlockStatement,
     pub scope: EnvReference,
 }
 
 impl FunctionObject {
     pub fn call(&self, args: &[Object]) -> Result<Object, EvalError> {
+        let context = self.scope.borrow().context();
+        context.borrow_mut().enter_call()?;
+        let called = self.call_body(args);
+        context.borrow_mut().exit_call();
+
+        called
+    }
+
+    /// A call of the function to itself in tail position is not evaluated as
+    /// a nested call. The body is instead evaluated again with the arguments
+    /// of the tail call, so tail recursion runs in constant stack space.
+    fn call_body(&self, args: &[Object]) -> Result<Object, EvalError> {
+        let mut tail_call_args: Vec<Object>;
+        let mut args = args;
+
+        loop {
+            let mut extended_env = self.bind_arguments(args)?;
+
+            match self.eval_tail_block(&self.body, &mut extended_env)? {
+                TailResult::SelfCall(next_args) => {
+                    tail_call_args = next_args;
+                    args = &tail_call_args;
+                }
+                TailResult::Value(Object::ReturnValue(return_value)) => return Ok(*return_value),
+                TailResult::Value(Object::Break) => return Err(EvalError::BreakOutsideLoop),
+                TailResult::Value(Object::Continue) => return Err(EvalError::ContinueOutsideLoop),
+                TailResult::Value(obj) => return Ok(obj),
+            }
+        }
+    }
+
+    fn bind_arguments(&self, args: &[Object]) -> Result<EnvReference, EvalError> {
         let mut extended_env = Environment::new_from_enclosing(&self.scope);
-        extended_env
-            .borrow_mut()
-            .fill_from_params_and_arguments(&self.parameters, args)?;
+        let required = self.parameters.len() - self.defaults.len();
+        if args.len() < required {
+            return Err(EvalError::ArgumentMismatch(
+                self.parameters.clone(),
+                args.to_vec(),
+            ));
+        }
+
+        let supplied = args.len().min(self.parameters.len());
+        extended_env.borrow_mut().fill_from_params_and_arguments(
+            &self.parameters[..supplied],
+            &self.rest_parameter,
+            args,
+        )?;
+        self.fill_defaults(supplied, &mut extended_env)?;
+
+        Ok(extended_env)
+    }
+
+    /// Defaults are evaluated on every call, inside the scope of the call, so
+    /// they can refer to the parameters before them.
+    fn fill_defaults(&self, supplied: usize, env: &mut EnvReference) -> Result<(), EvalError> {
+        let first_default = self.parameters.len() - self.defaults.len();
+        for (position, parameter) in self.parameters.iter().enumerate().skip(supplied) {
+            let value = self.defaults[position - first_default].eval(env)?;
+            env.borrow_mut().set_identifier(&parameter.0, value)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// The value of a function body, or the arguments of a call the function
+/// makes to itself in tail position, which is left for the caller to make.
+enum TailResult {
+    Value(Object),
+    SelfCall(Vec<Object>),
+}
+
+/// Evaluation of the parts of a function body that can be in tail position.
+/// The rest of the body is evaluated as usual.
+impl FunctionObject {
+    fn eval_tail_block(
+        &self,
+        block: &BlockStatement,
+        env: &mut EnvReference,
+    ) -> Result<TailResult, EvalError> {
+        let mut block_env = Environment::new_from_enclosing(env);
+        let Some((last, statements)) = block.statements.split_last() else {
+            return Ok(TailResult::Value(Object::Void));
+        };
+
+        for statement in statements {
+            if let Statement::Return(_) = statement {
+                return self.eval_tail_statement(statement, &mut block_env);
+            }
+
+            let object = statement.eval(&mut block_env)?;
+            if let Object::ReturnValue(_) | Object::Break | Object::Continue = object {
+                return Ok(TailResult::Value(object));
+            }
+        }
+
+        self.eval_tail_statement(last, &mut block_env)
+    }
 
-        match self.body.eval(&mut extended_env)? {
-            Object::ReturnValue(return_value) => Ok(*return_value),
-            obj => Ok(obj),
+    fn eval_tail_statement(
+        &self,
+        statement: &Statement,
+        env: &mut EnvReference,
+    ) -> Result<TailResult, EvalError> {
+        match statement {
+            Statement::Expression(ExpressionStatement { expression }) => {
+                env.borrow().context().borrow_mut().take_step()?;
+                self.eval_tail_expression(expression, env)
+            }
+            Statement::Return(ReturnStatement {
+                return_value: Some(return_value),
+            }) => {
+                env.borrow().context().borrow_mut().take_step()?;
+                Ok(match self.eval_tail_expression(return_value, env)? {
+                    TailResult::Value(value) => {
+                        TailResult::Value(Object::ReturnValue(Box::new(value)))
+                    }
+                    self_call => self_call,
+                })
+            }
+            statement => Ok(TailResult::Value(statement.eval(env)?)),
+        }
+    }
+
+    fn eval_tail_expression(
+        &self,
+        expression: &Expression,
+        env: &mut EnvReference,
+    ) -> Result<TailResult, EvalError> {
+        match expression {
+            Expression::Call(call_expression) => self.eval_tail_call(call_expression, env),
+            Expression::If(if_expression) => {
+                env.borrow().context().borrow_mut().take_step()?;
+                match if_expression.eval_branch(env)? {
+                    Some(branch) => self.eval_tail_block(branch, env),
+                    None => Ok(TailResult::Value(Object::Void)),
+                }
+            }
+            Expression::Block(block) => {
+                env.borrow().context().borrow_mut().take_step()?;
+                self.eval_tail_block(block, env)
+            }
+            expression => Ok(TailResult::Value(expression.eval(env)?)),
+        }
+    }
+
+    fn eval_tail_call(
+        &self,
+        call_expression: &CallExpression,
+        env: &mut EnvReference,
+    ) -> Result<TailResult, EvalError> {
+        env.borrow().context().borrow_mut().take_step()?;
+        let function = call_expression.function.eval(env)?;
+        let args = call_expression.eval_arguments(env)?;
+
+        match &function {
+            Object::Function(called) if ptr::eq(called.as_ref(), self) => {
+                Ok(TailResult::SelfCall(args))
+            }
+            function => Ok(TailResult::Value(apply_function(function, &args, env)?)),
         }
     }
 }
 
 impl Evaluable for FunctionLiteral {
     fn eval(&self, env: &mut EnvReference) -> Result<Object, EvalError> {
-        Ok(Object::Function(FunctionObject {
+        // The scope is captured by reference rather than copied, so a function
+        // bound by an assign statement can see its own binding and recurse.
+        Ok(Object::Function(Rc::new(FunctionObject {
             parameters: self.parameters.clone(),
+            defaults: self.defaults.clone(),
+            rest_parameter: self.rest_parameter.clone(),
             body: self.body.clone(),
             scope: Rc::clone(env),
-        }))
+        })))
     }
 }
 
 impl Evaluable for CallExpression {
     fn eval(&self, env: &mut EnvReference) -> Result<Object, EvalError> {
-        let function = match self.function.eval(env)? {
-            Object::Function(function_object) => function_object,
-            unexpected_object => {
-                return Err(EvalError::UnexpectedFunctionExpression(unexpected_object))
-            }
-        };
+        let function = self.function.eval(env)?;
+        let args = self.eval_arguments(env)?;
+
+        apply_function(&function, &args, env)
+    }
+}
 
-        let args = self
-            .arguments
-            .iter()
-            .map(|expr| expr.eval(env))
-            .collect::<Result<Vec<Object>, EvalError>>()?;
+impl CallExpression {
+    fn eval_arguments(&self, env: &mut EnvReference) -> Result<Vec<Object>, EvalError> {
+        self.arguments.iter().map(|expr| expr.eval(env)).collect()
+    }
+}
 
-        function.call(&args)
+pub fn apply_function(
+    function: &Object,
+    args: &[Object],
+    env: &EnvReference,
+) -> Result<Object, EvalError> {
+    match function {
+        Object::Function(function_object) => function_object.call(args),
+        Object::Builtin(builtin) => builtin.call(args, env),
+        unexpected_object => Err(EvalError::UnexpectedFunctionExpression(
+            unexpected_object.clone(),
+        )),
     }
 }
 
 #[cfg(test)]
 mod tests {
     use crate::{
-        eval::objects::Object,
+        eval::{
+            self,
+            context::DEFAULT_MAX_CALL_DEPTH,
+            eval_error::EvalError,
+            objects::{Environment, Object},
+            EvaledProgram,
+        },
         parser::{
             ast::{BlockStatement, Identifier, Operator},
             expressions::expression::Expression,
@@ -91,6 +270,22 @@ mod tests {
         }
     }
 
+    #[test]
+    fn arrays_are_passed_by_value_test() {
+        let input = "
+            let numbers: [1, 2].
+            let extend: fn(array):
+                let array: push(array, 3).
+                array
+            ~
+            let extended: extend(numbers).
+            [numbers, extended]
+            ";
+
+        let object = test_util::expect_evaled_program(input);
+        assert_eq!("[[1, 2], [1, 2, 3]]", object.to_string());
+    }
+
     #[test]
     fn function_object_test() {
         let input = "fn(x): x + 2~ ";
@@ -136,4 +331,163 @@ mod tests {
             }
         });
     }
+
+    #[test]
+    fn recursive_function_test() {
+        let input = "
+            let fact: fn(n):
+                if n == 0:
+                    1
+                else:
+                    n * fact(n - 1)
+                ~
+            ~
+
+            fact(5)
+            ";
+
+        match test_util::expect_evaled_program(input) {
+            Object::Integer(integer) => assert_eq!(120, integer, "fact(5) should be 120"),
+            unexpected_object => panic!("expected integer, but got {unexpected_object}"),
+        }
+    }
+
+    #[test]
+    fn early_return_from_if_test() {
+        let input = "
+            let sign: fn(n):
+                if n > 0:
+                    return 1.
+                ~
+                let unreachable: 1 / 0.
+                return 2.
+            ~
+
+            sign(5)
+            ";
+
+        match test_util::expect_evaled_program(input) {
+            Object::Integer(integer) => assert_eq!(1, integer, "sign(5) should return early"),
+            unexpected_object => panic!("expected integer, but got {unexpected_object}"),
+        }
+    }
+
+    #[test]
+    fn infinite_recursion_test() {
+        let input = "
+            let forever: fn(n): 1 + forever(n + 1)~
+            forever(0)
+            ";
+
+        match eval::eval(input, &mut Environment::new_env_reference()) {
+            EvaledProgram::EvalError(EvalError::RecursionLimitExceeded(limit)) => {
+                assert_eq!(DEFAULT_MAX_CALL_DEPTH, limit)
+            }
+            _ => panic!("Expected infinite recursion to hit the recursion limit"),
+        }
+    }
+
+    #[test]
+    fn tail_call_test() {
+        let input_expected: Vec<(&str, &str)> = vec![
+            (
+                "let countdown: fn(n): if n == 0: \"done\" else: countdown(n - 1)~~
+                countdown(100000)",
+                "\"done\"",
+            ),
+            (
+                "let sum: fn(n, acc):
+                    if n == 0: return acc~
+                    return sum(n - 1, acc + n)
+                ~
+                sum(10000, 0)",
+                "50005000",
+            ),
+            (
+                "let count: fn(n, step: 1): { if n <= 0: n else: count(n - step, step)~ }~
+                count(50000, 2)",
+                "0",
+            ),
+        ];
+
+        // Without the optimization these would overflow the stack, or hit the
+        // recursion limit first.
+        test_util::assert_list(input_expected, |expected: &&str, input: &&str| {
+            let object = test_util::expect_evaled_program(input);
+            assert_eq!(expected, &object.to_string(), "Wrong result for '{input}'");
+        });
+
+        let mut env = Environment::new_env_reference();
+        env.borrow().context().borrow_mut().set_step_limit(10_000);
+        assert!(matches!(
+            eval::eval("let forever: fn(n): forever(n + 1)~ forever(0)", &mut env),
+            EvaledProgram::EvalError(EvalError::StepLimitExceeded(_))
+        ));
+    }
+
+    #[test]
+    fn configured_recursion_limit_test() {
+        let input = "
+            let countdown: fn(n): if n == 0: 0 else: 1 + countdown(n - 1)~~
+            countdown(10)
+            ";
+
+        let mut env = Environment::new_env_reference();
+        env.borrow().context().borrow_mut().set_max_call_depth(5);
+
+        assert!(matches!(
+            eval::eval(input, &mut env),
+            EvaledProgram::EvalError(EvalError::RecursionLimitExceeded(5))
+        ));
+    }
+
+    #[test]
+    fn rest_parameter_test() {
+        let input_expected: Vec<(&str, &str)> = vec![
+            ("let f: fn(first, rest...): rest~. f(1)", "[]"),
+            ("let f: fn(first, rest...): rest~. f(1, 2, 3)", "[2, 3]"),
+            ("let f: fn(first, rest...): first~. f(1, 2, 3)", "1"),
+            ("let f: fn(all...): len(all)~. f(1, true, \"a\")", "3"),
+            ("let f: fn(all...): all~. f()", "[]"),
+        ];
+
+        test_util::assert_list(input_expected, |expected: &&str, input: &&str| {
+            let object = test_util::expect_evaled_program(input);
+            assert_eq!(expected, &object.to_string(), "Wrong result for '{input}'");
+        });
+
+        assert!(matches!(
+            eval::eval(
+                "let f: fn(first, rest...): rest~. f()",
+                &mut Environment::new_env_reference()
+            ),
+            EvaledProgram::EvalError(EvalError::ArgumentMismatch(_, _))
+        ));
+    }
+
+    #[test]
+    fn default_parameter_test() {
+        let input_expected: Vec<(&str, &str)> = vec![
+            ("let f: fn(x, y: 10): x + y~. f(1)", "11"),
+            ("let f: fn(x, y: 10): x + y~. f(1, 2)", "3"),
+            ("let f: fn(x, y: x * 2): x + y~. f(3)", "9"),
+            ("let f: fn(x: 1, rest...): (x, rest)~. f()", "(1, [])"),
+            ("let f: fn(x: 1, rest...): (x, rest)~. f(5, 6)", "(5, [6])"),
+        ];
+
+        test_util::assert_list(input_expected, |expected: &&str, input: &&str| {
+            let object = test_util::expect_evaled_program(input);
+            assert_eq!(expected, &object.to_string(), "Wrong result for '{input}'");
+        });
+
+        for input in [
+            "let f: fn(x, y: 10): x~. f()",
+            "let f: fn(x, y: 10): x~. f(1, 2, 3)",
+        ] {
+            assert!(matches!(
+                eval::eval(input, &mut Environment::new_env_reference()),
+                EvaledProgram::EvalError(EvalError::ArgumentMismatch(_, _))
+            ));
+        }
+    }
 }
@@ -0,0 +1,177 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::parser::{
+    import_statement::ImportStatement, lexer::lexedtokens::LexedTokens, ParsedProgram, Parser,
+};
+
+use super::{
+    eval_error::EvalError,
+    expression_evaluator::Evaluable,
+    objects::{EnvReference, Object},
+    statement_evaluator,
+};
+
+impl Evaluable for ImportStatement {
+    fn eval(&self, env: &mut EnvReference) -> Result<Object, EvalError> {
+        let requested_path = resolve_import_path(&self.path, env);
+        let (path, source) = read_source(&requested_path)?;
+
+        if env.borrow().context().borrow().is_importing(&path) {
+            return Err(EvalError::ImportFailed {
+                path,
+                reason: String::from("cyclic import detected"),
+            });
+        }
+
+        let statements = match Parser::parse_tokens(LexedTokens::from(source.as_str())) {
+            ParsedProgram::ValidProgram(statements) => statements,
+            ParsedProgram::InvalidProgram(parse_errors) => {
+                return Err(EvalError::ImportFailed {
+                    path,
+                    reason: parse_errors
+                        .iter()
+                        .map(|parse_error| parse_error.to_string())
+                        .collect::<Vec<String>>()
+                        .join(", "),
+                })
+            }
+        };
+
+        env.borrow().context().borrow_mut().push_import(path);
+        let evaled = statement_evaluator::eval_statements(&statements, env);
+        env.borrow().context().borrow_mut().pop_import();
+        evaled?;
+
+        Ok(Object::Void)
+    }
+}
+
+/// Imports are resolved relative to the directory of the file currently being
+/// evaluated, falling back to the working directory when evaluating plain source.
+fn resolve_import_path(import_path: &str, env: &EnvReference) -> PathBuf {
+    match env.borrow().context().borrow().current_import() {
+        Some(importing_file) => match importing_file.parent() {
+            Some(directory) => directory.join(import_path),
+            None => PathBuf::from(import_path),
+        },
+        None => PathBuf::from(import_path),
+    }
+}
+
+pub fn read_source(path: &Path) -> Result<(PathBuf, String), EvalError> {
+    let import_failed = |error: std::io::Error| EvalError::ImportFailed {
+        path: path.to_path_buf(),
+        reason: error.to_string(),
+    };
+
+    let path = fs::canonicalize(path).map_err(import_failed)?;
+    let source = fs::read_to_string(&path).map_err(import_failed)?;
+
+    Ok((path, source))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::PathBuf};
+
+    use crate::eval::{
+        self,
+        eval_error::EvalError,
+        objects::{Environment, Object},
+        EvaledProgram,
+    };
+
+    /// A directory of source files in the temp directory, which is removed
+    /// when the fixture is dropped, also when the test fails.
+    struct Fixture(PathBuf);
+
+    impl Fixture {
+        fn new(name: &str, files: &[(&str, &str)]) -> Fixture {
+            let directory =
+                std::env::temp_dir().join(format!("lasagna_{name}_{}", std::process::id()));
+            fs::create_dir_all(&directory).expect("Should create fixture directory");
+
+            for (file_name, source) in files {
+                fs::write(directory.join(file_name), source).expect("Should write fixture file");
+            }
+
+            Fixture(directory)
+        }
+
+        fn path(&self, file_name: &str) -> PathBuf {
+            self.0.join(file_name)
+        }
+    }
+
+    impl Drop for Fixture {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn import_function_test() {
+        let fixture = Fixture::new(
+            "import_function",
+            &[
+                ("main.lasagna", "import \"math.lasagna\". add(2, 3)"),
+                ("math.lasagna", "let add: fn(x, y): x + y~"),
+            ],
+        );
+
+        let evaled = eval::eval_file(
+            &fixture.path("main.lasagna"),
+            &mut Environment::new_env_reference(),
+        );
+
+        match evaled {
+            EvaledProgram::Valid(Object::Integer(integer)) => assert_eq!(5, integer),
+            _ => panic!("Expected imported function to be callable"),
+        }
+    }
+
+    #[test]
+    fn import_missing_file_test() {
+        let fixture = Fixture::new(
+            "import_missing",
+            &[("main.lasagna", "import \"missing.lasagna\".")],
+        );
+
+        let evaled = eval::eval_file(
+            &fixture.path("main.lasagna"),
+            &mut Environment::new_env_reference(),
+        );
+
+        assert!(matches!(
+            evaled,
+            EvaledProgram::EvalError(EvalError::ImportFailed { .. })
+        ));
+    }
+
+    #[test]
+    fn cyclic_import_test() {
+        let fixture = Fixture::new(
+            "import_cyclic",
+            &[
+                ("a.lasagna", "import \"b.lasagna\"."),
+                ("b.lasagna", "import \"a.lasagna\"."),
+            ],
+        );
+
+        let evaled = eval::eval_file(
+            &fixture.path("a.lasagna"),
+            &mut Environment::new_env_reference(),
+        );
+
+        match evaled {
+            EvaledProgram::EvalError(EvalError::ImportFailed { path, reason }) => {
+                assert!(path.ends_with("a.lasagna"));
+                assert_eq!("cyclic import detected", reason);
+            }
+            _ => panic!("Expected cyclic import to fail"),
+        }
+    }
+}
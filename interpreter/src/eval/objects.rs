@@ -1,16 +1,80 @@
-use std::{cell::RefCell, collections::HashMap, fmt::Display, rc::Rc};
+use std::{
+    cell::RefCell,
+    cmp::Ordering,
+    collections::{BTreeMap, HashMap, HashSet},
+    fmt::Display,
+    io::{BufRead, Write},
+    rc::Rc,
+    time::Duration,
+};
 
-use crate::parser::ast::Identifier;
+use indexmap::IndexMap;
 
-use super::{eval_error::EvalError, function_evaluator::FunctionObject};
+use crate::parser::{
+    ast::{Identifier, Operator},
+    expressions::functions::format_parameters,
+};
+
+use super::{
+    builtins::{Builtin, NativeFunction},
+    context::{ContextReference, EvalContext},
+    eval_error::EvalError,
+    function_evaluator::FunctionObject,
+};
 
 #[derive(Debug, Clone)]
 pub enum Object {
     Integer(i32),
+    Float(f64),
     Boolean(bool),
+    Str(String),
+    Array(Vec<Object>),
+    Tuple(Vec<Object>),
+    Hash(Box<IndexMap<HashKey, Object>>),
+    Null,
     Void,
     ReturnValue(Box<Object>),
-    Function(FunctionObject),
+    Break,
+    Continue,
+    Function(Rc<FunctionObject>),
+    Builtin(Builtin),
+}
+
+/// The objects that can be used as keys in a hash.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum HashKey {
+    Integer(i32),
+    Boolean(bool),
+    Str(String),
+}
+
+impl TryFrom<Object> for HashKey {
+    type Error = EvalError;
+
+    fn try_from(object: Object) -> Result<Self, Self::Error> {
+        match object {
+            Object::Integer(integer) => Ok(HashKey::Integer(integer)),
+            Object::Boolean(boolean) => Ok(HashKey::Boolean(boolean)),
+            Object::Str(string) => Ok(HashKey::Str(string)),
+            unhashable_object => Err(EvalError::UnhashableKey(unhashable_object)),
+        }
+    }
+}
+
+impl From<HashKey> for Object {
+    fn from(key: HashKey) -> Self {
+        match key {
+            HashKey::Integer(integer) => Object::Integer(integer),
+            HashKey::Boolean(boolean) => Object::Boolean(boolean),
+            HashKey::Str(string) => Object::Str(string),
+        }
+    }
+}
+
+impl Display for HashKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        Object::from(self.clone()).fmt(f)
+    }
 }
 
 pub type EnvReference = Rc<RefCell<Environment>>;
@@ -19,6 +83,8 @@ pub type EnvReference = Rc<RefCell<Environment>>;
 pub struct Environment {
     scope: HashMap<String, Object>,
     outer_scopes: Option<EnvReference>,
+    constants: HashSet<String>,
+    context: ContextReference,
 }
 
 impl Environment {
@@ -26,6 +92,8 @@ impl Environment {
         Environment {
             scope: HashMap::new(),
             outer_scopes: None,
+            constants: HashSet::new(),
+            context: EvalContext::new_context_reference(),
         }
     }
 
@@ -33,15 +101,72 @@ impl Environment {
         Rc::new(RefCell::new(Environment::new()))
     }
 
+    /// Creates an environment where output from built-ins like `puts` is
+    /// written to the given writer instead of stdout.
+    pub fn new_with_output(output: Rc<RefCell<dyn Write>>) -> EnvReference {
+        let env = Environment::new();
+        env.context.borrow_mut().set_output(output);
+
+        Rc::new(RefCell::new(env))
+    }
+
+    /// Creates an environment where built-ins like `readLine` read from the
+    /// given reader instead of stdin.
+    pub fn new_with_input(input: Rc<RefCell<dyn BufRead>>) -> EnvReference {
+        let env = Environment::new();
+        env.context.borrow_mut().set_input(input);
+
+        Rc::new(RefCell::new(env))
+    }
+
+    /// Creates an environment where built-ins like `now` ask the given clock
+    /// for the time instead of the system clock.
+    pub fn new_with_clock(clock: Rc<dyn Fn() -> Duration>) -> EnvReference {
+        let env = Environment::new();
+        env.context.borrow_mut().set_clock(clock);
+
+        Rc::new(RefCell::new(env))
+    }
+
     pub fn new_from_enclosing(env: &EnvReference) -> EnvReference {
         let env = Environment {
             scope: HashMap::new(),
             outer_scopes: Some(Rc::clone(env)),
+            constants: HashSet::new(),
+            context: Rc::clone(&env.borrow().context),
         };
 
         Rc::new(RefCell::new(env))
     }
 
+    /// Copies the bindings of this scope into a new environment, while the
+    /// outer scopes and the evaluation context stay shared with the original.
+    /// Assignments made in the fork are therefore not seen by the original,
+    /// but changes to an outer scope are seen by both.
+    pub fn fork(env: &EnvReference) -> EnvReference {
+        Rc::new(RefCell::new(env.borrow().clone()))
+    }
+
+    pub fn context(&self) -> ContextReference {
+        Rc::clone(&self.context)
+    }
+
+    /// Makes a function from the embedding program callable from Lasagna.
+    /// Bindings made by the program shadow a registered built-in, which in
+    /// turn shadows a built-in of the language with the same name.
+    pub fn register_builtin(&self, name: &str, function: NativeFunction) {
+        self.context.borrow_mut().register_builtin(name, function);
+    }
+
+    /// Gives an operator a meaning for a type the language has no meaning
+    /// for, like `+` between two tuples. Operators on integers, floats,
+    /// booleans and strings can not be overloaded.
+    pub fn register_operator(&self, type_name: &str, operator: Operator, function: NativeFunction) {
+        self.context
+            .borrow_mut()
+            .register_operator(type_name, operator, function);
+    }
+
     pub fn get_identifier(&self, identifier: &str) -> Option<Object> {
         match self.scope.get(identifier) {
             Some(object) => Some(object.clone()),
@@ -52,30 +177,72 @@ impl Environment {
         }
     }
 
-    pub fn set_identifier(&mut self, identifier: &str, object: Object) {
+    /// The bindings made in this scope, sorted by name.
+    pub fn dump(&self) -> Vec<(String, Object)> {
+        let mut bindings: Vec<(String, Object)> = self
+            .scope
+            .iter()
+            .map(|(identifier, object)| (identifier.clone(), object.clone()))
+            .collect();
+        bindings.sort_by(|(left, _), (right, _)| left.cmp(right));
+        bindings
+    }
+
+    /// The bindings visible from this scope, including those of the outer
+    /// scopes that are not shadowed, sorted by name.
+    pub fn dump_all(&self) -> Vec<(String, Object)> {
+        let mut bindings: BTreeMap<String, Object> = match &self.outer_scopes {
+            Some(outer_scope) => outer_scope.borrow().dump_all().into_iter().collect(),
+            None => BTreeMap::new(),
+        };
+        bindings.extend(self.dump());
+        bindings.into_iter().collect()
+    }
+
+    pub fn set_identifier(&mut self, identifier: &str, object: Object) -> Result<(), EvalError> {
+        if self.constants.contains(identifier) {
+            return Err(EvalError::ReassignConstant(String::from(identifier)));
+        }
+
         self.scope.insert(String::from(identifier), object);
+        Ok(())
+    }
+
+    pub fn set_constant(&mut self, identifier: &str, object: Object) -> Result<(), EvalError> {
+        self.set_identifier(identifier, object)?;
+        self.constants.insert(String::from(identifier));
+        Ok(())
     }
 
+    /// Arguments are bound as copies, so a function can never change an array
+    /// that the caller still holds. Arguments beyond the named parameters are
+    /// collected into an array bound to the rest parameter.
     pub fn fill_from_params_and_arguments(
         &mut self,
         parameters: &[Identifier],
+        rest_parameter: &Option<Identifier>,
         arguments: &[Object],
     ) -> Result<(), EvalError> {
-        if parameters.len() != arguments.len() {
+        let matches_parameters = match rest_parameter {
+            Some(_) => arguments.len() >= parameters.len(),
+            None => arguments.len() == parameters.len(),
+        };
+        if !matches_parameters {
             return Err(EvalError::ArgumentMismatch(
                 parameters.to_vec(),
                 arguments.to_vec(),
             ));
         }
 
+        let (named_arguments, rest_arguments) = arguments.split_at(parameters.len());
+        if let Some(rest_parameter) = rest_parameter {
+            self.set_identifier(&rest_parameter.0, Object::Array(rest_arguments.to_vec()))?;
+        }
+
         parameters
             .iter()
-            .zip(arguments.iter())
-            .for_each(|(param, argument)| {
-                self.set_identifier(&param.0, argument.clone());
-            });
-
-        Ok(())
+            .zip(named_arguments.iter())
+            .try_for_each(|(param, argument)| self.set_identifier(&param.0, argument.clone()))
     }
 }
 
@@ -98,16 +265,369 @@ impl<T: ToString> FunctionListable for Vec<T> {
     }
 }
 
+impl Object {
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Object::Integer(_) => "integer",
+            Object::Float(_) => "float",
+            Object::Boolean(_) => "boolean",
+            Object::Str(_) => "string",
+            Object::Array(_) => "array",
+            Object::Tuple(_) => "tuple",
+            Object::Hash(_) => "hash",
+            Object::Null => "null",
+            Object::Void | Object::Break | Object::Continue => "void",
+            Object::ReturnValue(object) => object.type_name(),
+            Object::Function(_) | Object::Builtin(_) => "function",
+        }
+    }
+
+    pub fn is_truthy(&self) -> bool {
+        match self {
+            Object::Integer(integer) => *integer != 0,
+            Object::Float(float) => *float != 0.0,
+            Object::Boolean(boolean) => *boolean,
+            Object::Str(string) => !string.is_empty(),
+            Object::Array(elements) | Object::Tuple(elements) => !elements.is_empty(),
+            Object::Hash(pairs) => !pairs.is_empty(),
+            Object::Null | Object::Void | Object::Break | Object::Continue => false,
+            Object::ReturnValue(object) => object.is_truthy(),
+            Object::Function(_) | Object::Builtin(_) => true,
+        }
+    }
+
+    /// The representation used when printing a value directly, as opposed to
+    /// `Display` which quotes strings to distinguish them from other values.
+    pub fn to_print_string(&self) -> String {
+        match self {
+            Object::Str(string) => string.clone(),
+            object => object.to_string(),
+        }
+    }
+}
+
+impl PartialEq for Object {
+    fn eq(&self, other: &Self) -> bool {
+        use Object::*;
+
+        match (self, other) {
+            (Integer(left), Integer(right)) => left == right,
+            (Float(left), Float(right)) => left == right,
+            (Boolean(left), Boolean(right)) => left == right,
+            (Str(left), Str(right)) => left == right,
+            (Array(left), Array(right)) | (Tuple(left), Tuple(right)) => left == right,
+            (Hash(left), Hash(right)) => left == right,
+            (Null, Null) | (Void, Void) | (Break, Break) | (Continue, Continue) => true,
+            (ReturnValue(left), ReturnValue(right)) => left == right,
+            (Function(left), Function(right)) => {
+                left.parameters == right.parameters
+                    && left.defaults == right.defaults
+                    && left.rest_parameter == right.rest_parameter
+                    && left.body == right.body
+                    && Rc::ptr_eq(&left.scope, &right.scope)
+            }
+            (Builtin(left), Builtin(right)) => left == right,
+            _ => false,
+        }
+    }
+}
+
+/// Only numbers and strings of the same type have an ordering, every other
+/// pair of objects is incomparable.
+impl PartialOrd for Object {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        use Object::*;
+
+        match (self, other) {
+            (Integer(left), Integer(right)) => left.partial_cmp(right),
+            (Float(left), Float(right)) => left.partial_cmp(right),
+            (Str(left), Str(right)) => left.partial_cmp(right),
+            _ => None,
+        }
+    }
+}
+
+/// Values serialize to their JSON counterparts, while functions, which have
+/// no such counterpart, serialize to a placeholder.
+#[cfg(feature = "serde")]
+impl serde::Serialize for Object {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeMap;
+
+        match self {
+            Object::Integer(integer) => serializer.serialize_i32(*integer),
+            Object::Float(float) => serializer.serialize_f64(*float),
+            Object::Boolean(boolean) => serializer.serialize_bool(*boolean),
+            Object::Str(string) => serializer.serialize_str(string),
+            Object::Array(elements) | Object::Tuple(elements) => serializer.collect_seq(elements),
+            Object::Hash(pairs) => serializer.collect_map(
+                pairs
+                    .iter()
+                    .map(|(key, value)| (Object::from(key.clone()).to_print_string(), value)),
+            ),
+            Object::ReturnValue(object) => object.serialize(serializer),
+            Object::Null | Object::Void | Object::Break | Object::Continue => {
+                serializer.serialize_none()
+            }
+            Object::Function(_) | Object::Builtin(_) => {
+                let mut map = serializer.serialize_map(Some(1))?;
+                map.serialize_entry("type", "function")?;
+                map.end()
+            }
+        }
+    }
+}
+
 impl Display for Object {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.write_visiting(f, &mut HashSet::new())
+    }
+}
+
+impl Object {
+    /// Writes the object while keeping track of the arrays, tuples and hashes
+    /// it is inside of. A container that would be written inside itself is
+    /// written as `[...]`, so a self-referential object can still be printed.
+    fn write_visiting(
+        &self,
+        f: &mut dyn std::fmt::Write,
+        visiting: &mut HashSet<*const Object>,
+    ) -> std::fmt::Result {
         use Object::*;
 
+        let is_container = matches!(self, Array(_) | Tuple(_) | Hash(_));
+        if is_container && !visiting.insert(self) {
+            return write!(f, "[...]");
+        }
+
         match self {
-            Integer(number) => write!(f, "{number}"),
-            Boolean(boolean) => write!(f, "{boolean}"),
-            Void => write!(f, ""),
-            ReturnValue(object) => write!(f, "{object}"),
-            Function(function) => write!(f, "fn ({})", function.parameters.to_function_string()),
+            Integer(number) => write!(f, "{number}")?,
+            // Whole floats keep their decimal point, so they can be told
+            // apart from integers.
+            Float(number) if number.is_finite() && number.fract() == 0.0 => {
+                write!(f, "{number}.0")?
+            }
+            Float(number) => write!(f, "{number}")?,
+            Boolean(boolean) => write!(f, "{boolean}")?,
+            Str(string) => write!(f, "\"{string}\"")?,
+            Array(elements) => {
+                write!(f, "[")?;
+                write_elements(f, elements, visiting)?;
+                write!(f, "]")?
+            }
+            Tuple(elements) => {
+                write!(f, "(")?;
+                write_elements(f, elements, visiting)?;
+                write!(f, ")")?
+            }
+            Hash(pairs) => {
+                write!(f, "{{")?;
+                for (index, (key, value)) in pairs.iter().enumerate() {
+                    if index > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{key}: ")?;
+                    value.write_visiting(f, visiting)?;
+                }
+                write!(f, "}}")?
+            }
+            Null => write!(f, "null")?,
+            Void | Break | Continue => {}
+            ReturnValue(object) => object.write_visiting(f, visiting)?,
+            Function(function) => write!(
+                f,
+                "fn ({})",
+                format_parameters(
+                    &function.parameters,
+                    &function.defaults,
+                    &function.rest_parameter
+                )
+            )?,
+            Builtin(builtin) => write!(f, "builtin {builtin}")?,
+        }
+
+        if is_container {
+            visiting.remove(&(self as *const Object));
+        }
+        Ok(())
+    }
+}
+
+fn write_elements(
+    f: &mut dyn std::fmt::Write,
+    elements: &[Object],
+    visiting: &mut HashSet<*const Object>,
+) -> std::fmt::Result {
+    for (index, element) in elements.iter().enumerate() {
+        if index > 0 {
+            write!(f, ", ")?;
+        }
+        element.write_visiting(f, visiting)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cmp::Ordering;
+
+    use crate::eval;
+
+    use super::{Environment, HashSet, Object};
+
+    #[test]
+    fn object_ordering_test() {
+        assert_eq!(
+            Object::Integer(1).partial_cmp(&Object::Integer(2)),
+            Some(Ordering::Less)
+        );
+        assert_eq!(
+            Object::Float(2.5).partial_cmp(&Object::Float(2.5)),
+            Some(Ordering::Equal)
+        );
+        assert_eq!(
+            Object::Str(String::from("b")).partial_cmp(&Object::Str(String::from("a"))),
+            Some(Ordering::Greater)
+        );
+        assert!(Object::Integer(1) < Object::Integer(2));
+    }
+
+    #[test]
+    fn incomparable_objects_test() {
+        let incomparable_pairs = [
+            (Object::Integer(1), Object::Float(1.0)),
+            (Object::Integer(1), Object::Str(String::from("1"))),
+            (Object::Boolean(true), Object::Boolean(false)),
+            (Object::Null, Object::Integer(0)),
+            (
+                Object::Array(Vec::from([Object::Integer(1)])),
+                Object::Array(Vec::from([Object::Integer(2)])),
+            ),
+        ];
+
+        for (left, right) in incomparable_pairs {
+            assert_eq!(left.partial_cmp(&right), None, "{left} and {right}");
         }
     }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serialize_object_to_json_test() {
+        use crate::parser::test_util;
+
+        let array = test_util::expect_evaled_program("[1, \"a\", true, 1.5]");
+        assert_eq!(
+            serde_json::json!([1, "a", true, 1.5]),
+            serde_json::to_value(&array).expect("Array should serialize")
+        );
+
+        let function = test_util::expect_evaled_program("[fn(x): x~, len]");
+        assert_eq!(
+            serde_json::json!([{"type": "function"}, {"type": "function"}]),
+            serde_json::to_value(&function).expect("Functions should serialize")
+        );
+
+        assert_eq!(
+            serde_json::Value::Null,
+            serde_json::to_value(Object::Null).expect("Null should serialize")
+        );
+    }
+
+    #[test]
+    fn forked_environment_test() {
+        let mut env = Environment::new_env_reference();
+        eval::eval("let x: 1.", &mut env);
+
+        let mut fork = Environment::fork(&env);
+        eval::eval("let x: 2.", &mut fork);
+
+        assert_eq!(Some(Object::Integer(1)), env.borrow().get_identifier("x"));
+        assert_eq!(Some(Object::Integer(2)), fork.borrow().get_identifier("x"));
+
+        let inner = Environment::new_from_enclosing(&env);
+        let inner_fork = Environment::fork(&inner);
+        env.borrow_mut()
+            .set_identifier("y", Object::Integer(3))
+            .expect("Should set identifier");
+        assert_eq!(
+            Some(Object::Integer(3)),
+            inner_fork.borrow().get_identifier("y")
+        );
+    }
+
+    #[test]
+    fn dump_environment_test() {
+        let mut env = Environment::new_env_reference();
+        eval::eval("let y: 2. let x: 1.", &mut env);
+
+        assert_eq!(
+            vec![
+                (String::from("x"), Object::Integer(1)),
+                (String::from("y"), Object::Integer(2)),
+            ],
+            env.borrow().dump()
+        );
+
+        let inner = Environment::new_from_enclosing(&env);
+        inner
+            .borrow_mut()
+            .set_identifier("x", Object::Str(String::from("shadowed")))
+            .expect("Should set identifier");
+
+        assert_eq!(
+            vec![(String::from("x"), Object::Str(String::from("shadowed")))],
+            inner.borrow().dump()
+        );
+        assert_eq!(
+            vec![
+                (String::from("x"), Object::Str(String::from("shadowed"))),
+                (String::from("y"), Object::Integer(2)),
+            ],
+            inner.borrow().dump_all()
+        );
+    }
+
+    #[test]
+    fn display_nested_objects_test() {
+        let nested = eval::eval(
+            "[1, (2.0, \"a\"), {\"b\": [true]}, []]",
+            &mut Environment::new_env_reference(),
+        );
+        match nested {
+            eval::EvaledProgram::Valid(object) => {
+                assert_eq!("[1, (2.0, \"a\"), {\"b\": [true]}, []]", object.to_string())
+            }
+            _ => panic!("Nested objects should evaluate"),
+        }
+    }
+
+    #[test]
+    fn display_cyclic_object_test() {
+        // Objects own their elements, so a real cycle can not be built yet.
+        // Marking the outer array as already being written makes it look to
+        // the writer as if the array had been found inside itself.
+        let cyclic = Object::Array(vec![
+            Object::Integer(1),
+            Object::Tuple(vec![Object::Integer(2)]),
+        ]);
+        let mut visiting = HashSet::from([&cyclic as *const Object]);
+
+        let mut written = String::new();
+        cyclic
+            .write_visiting(&mut written, &mut visiting)
+            .expect("Should write object");
+        assert_eq!("[...]", written);
+
+        let Object::Array(elements) = &cyclic else {
+            unreachable!()
+        };
+        let mut visiting = HashSet::from([&elements[1] as *const Object]);
+        let mut written = String::new();
+        cyclic
+            .write_visiting(&mut written, &mut visiting)
+            .expect("Should write object");
+        assert_eq!("[1, [...]]", written);
+
+        assert_eq!("[1, (2)]", cyclic.to_string());
+    }
 }
@@ -1,18 +1,21 @@
-use tracing::{span, Level};
+use tracing::{event, span, Level};
 
 use crate::parser::{
-    assign_statement::AssignStatement,
+    assign_statement::{AssignStatement, ParallelAssignStatement},
     ast::{BlockStatement, Identifier, Statement},
     expressions::expression_statement::ExpressionStatement,
     return_statement::ReturnStatement,
+    while_statement::WhileStatement,
 };
 
 use super::{
     eval_error::EvalError,
     expression_evaluator::Evaluable,
-    objects::{EnvReference, Object},
+    objects::{EnvReference, Environment, HashKey, Object},
 };
 
+/// A `return` outside of any function ends the program with its value, unless
+/// the context forbids returning from the top level.
 pub fn eval_statements(
     statements: &Vec<Statement>,
     env: &mut EnvReference,
@@ -20,38 +23,134 @@ pub fn eval_statements(
     let mut object: Object = Object::Void;
 
     for statement in statements {
+        event!(Level::DEBUG, "Evaluating top-level statement: {statement}");
         object = statement.eval(env)?;
 
-        if let Object::ReturnValue(value) = object {
-            return Ok(*value);
+        match object {
+            Object::ReturnValue(value) if allows_top_level_return(env) => return Ok(*value),
+            Object::ReturnValue(_) => return Err(EvalError::ReturnOutsideFunction),
+            Object::Break => return Err(EvalError::BreakOutsideLoop),
+            Object::Continue => return Err(EvalError::ContinueOutsideLoop),
+            _ => {}
         }
     }
 
     Ok(object)
 }
 
+fn allows_top_level_return(env: &EnvReference) -> bool {
+    env.borrow().context().borrow().allows_top_level_return()
+}
+
 impl Evaluable for Statement {
     fn eval(&self, env: &mut EnvReference) -> Result<Object, EvalError> {
         let expression_statement_span = span!(Level::DEBUG, "Eval");
         let _enter = expression_statement_span.enter();
 
+        env.borrow().context().borrow_mut().take_step()?;
         match self {
             Statement::Expression(ExpressionStatement { expression }) => expression.eval(env),
             Statement::Return(return_statement) => return_statement.eval(env),
             Statement::Assign(assign_statement) => assign_statement.eval(env),
+            Statement::ConstAssign(assign_statement) => assign_statement.eval_const(env),
+            Statement::ParallelAssign(parallel_assign) => parallel_assign.eval(env),
+            Statement::Import(import_statement) => import_statement.eval(env),
+            Statement::While(while_statement) => while_statement.eval(env),
+            Statement::DoWhile(while_statement) => while_statement.eval_loop(env, false),
+            Statement::Break => Ok(Object::Break),
+            Statement::Continue => Ok(Object::Continue),
         }
     }
 }
 
 impl Evaluable for AssignStatement {
     fn eval(&self, env: &mut EnvReference) -> Result<Object, EvalError> {
+        let mut value = self.eval_assignment(env)?;
+
+        if !self.indices.is_empty() {
+            let indices = self
+                .indices
+                .iter()
+                .map(|index| index.eval(env))
+                .collect::<Result<Vec<Object>, EvalError>>()?;
+            let target = self.identifier.eval(env)?;
+            value = assign_at_index(target, &indices, value)?;
+        }
+
+        env.borrow_mut().set_identifier(&self.identifier.0, value)?;
+        Ok(Object::Void)
+    }
+}
+
+/// Objects are values, so assigning to an element gives an updated copy of
+/// the object, which is then bound in place of the original. An array element
+/// must already exist, while a hash can be given a new key.
+fn assign_at_index(target: Object, indices: &[Object], value: Object) -> Result<Object, EvalError> {
+    let Some((index, rest)) = indices.split_first() else {
+        return Ok(value);
+    };
+
+    match (target, index) {
+        (Object::Array(mut elements), Object::Integer(position)) => {
+            let length = elements.len();
+            let element = usize::try_from(*position)
+                .ok()
+                .and_then(|position| elements.get_mut(position))
+                .ok_or(EvalError::IndexOutOfBounds(*position, length))?;
+            *element = assign_at_index(std::mem::replace(element, Object::Null), rest, value)?;
+            Ok(Object::Array(elements))
+        }
+        (Object::Hash(mut pairs), key) => {
+            let key = HashKey::try_from(key.clone())?;
+            let current = pairs.get(&key).cloned().unwrap_or(Object::Null);
+            let updated = assign_at_index(current, rest, value)?;
+            pairs.insert(key, updated);
+            Ok(Object::Hash(pairs))
+        }
+        (target, index) => Err(EvalError::IndexNotSupported(target, index.clone())),
+    }
+}
+
+impl AssignStatement {
+    fn eval_const(&self, env: &mut EnvReference) -> Result<Object, EvalError> {
+        let value = self.eval_assignment(env)?;
+
+        env.borrow_mut().set_constant(&self.identifier.0, value)?;
+        Ok(Object::Void)
+    }
+
+    fn eval_assignment(&self, env: &mut EnvReference) -> Result<Object, EvalError> {
         let value = self.assignment.eval(env)?;
 
         if let Object::Void = value {
-            return Err(EvalError::VoidAssignment(self.assignment.clone()));
+            return Err(EvalError::VoidAssignment(Box::new(self.assignment.clone())));
+        }
+
+        match self.annotated_type() {
+            Some(expected) if expected != value.type_name() => Err(EvalError::TypeMismatch {
+                expected: expected.to_string(),
+                got: value,
+            }),
+            _ => Ok(value),
         }
+    }
+}
 
-        env.borrow_mut().set_identifier(&self.identifier.0, value);
+impl Evaluable for ParallelAssignStatement {
+    fn eval(&self, env: &mut EnvReference) -> Result<Object, EvalError> {
+        let mut values = Vec::with_capacity(self.assignments.len());
+        for assignment in &self.assignments {
+            match assignment.eval(env)? {
+                Object::Void => {
+                    return Err(EvalError::VoidAssignment(Box::new(assignment.clone())))
+                }
+                value => values.push(value),
+            }
+        }
+
+        for (identifier, value) in self.identifiers.iter().zip(values) {
+            env.borrow_mut().set_identifier(&identifier.0, value)?;
+        }
         Ok(Object::Void)
     }
 }
@@ -67,18 +166,69 @@ impl Evaluable for Identifier {
 
 impl Evaluable for ReturnStatement {
     fn eval(&self, env: &mut EnvReference) -> Result<Object, EvalError> {
-        Ok(Object::ReturnValue(Box::new(self.return_value.eval(env)?)))
+        let return_value = match &self.return_value {
+            Some(return_value) => return_value.eval(env)?,
+            None => Object::Null,
+        };
+
+        Ok(Object::ReturnValue(Box::new(return_value)))
+    }
+}
+
+impl Evaluable for WhileStatement {
+    /// The body shares the scope of the loop rather than getting a block scope,
+    /// so assignments in the body are seen by the condition of the next iteration.
+    ///
+    /// The loop yields the value of the last body statement it executed, which
+    /// for a `break` or `continue` is the statement before it. A loop whose body
+    /// never ran yields null.
+    fn eval(&self, env: &mut EnvReference) -> Result<Object, EvalError> {
+        self.eval_loop(env, true)
+    }
+}
+
+impl WhileStatement {
+    /// A `do` loop skips checking the condition before the first iteration,
+    /// while a `continue` in it still checks the condition before the next.
+    fn eval_loop(&self, env: &mut EnvReference, checks_first: bool) -> Result<Object, EvalError> {
+        let mut result = Object::Null;
+        let mut checks_condition = checks_first;
+
+        loop {
+            if checks_condition {
+                match self.condition.eval(env)? {
+                    Object::Boolean(true) => {}
+                    Object::Boolean(false) => return Ok(result),
+                    unexpected_condition => {
+                        return Err(EvalError::NonBooleanConditional(unexpected_condition))
+                    }
+                }
+            }
+            checks_condition = true;
+
+            for statement in &self.body.statements {
+                match statement.eval(env)? {
+                    Object::Break => return Ok(result),
+                    Object::Continue => break,
+                    return_value @ Object::ReturnValue(_) => return Ok(return_value),
+                    value => result = value,
+                }
+            }
+        }
     }
 }
 
 impl Evaluable for BlockStatement {
+    /// Blocks get their own scope, so assignments inside a block shadow outer
+    /// bindings instead of overwriting them, while outer values stay readable.
     fn eval(&self, env: &mut EnvReference) -> Result<Object, EvalError> {
+        let mut block_env = Environment::new_from_enclosing(env);
         let mut object: Object = Object::Void;
 
         for statement in &self.statements {
-            object = statement.eval(env)?;
+            object = statement.eval(&mut block_env)?;
 
-            if let Object::ReturnValue(_) = object {
+            if let Object::ReturnValue(_) | Object::Break | Object::Continue = object {
                 return Ok(object);
             }
         }
@@ -89,7 +239,15 @@ impl Evaluable for BlockStatement {
 
 #[cfg(test)]
 mod tests {
-    use crate::{eval::objects::Object, parser::test_util};
+    use crate::{
+        eval::{
+            self,
+            eval_error::EvalError,
+            objects::{Environment, Object},
+            EvaledProgram,
+        },
+        parser::test_util,
+    };
 
     #[test]
     fn eval_return_statement_test() {
@@ -123,6 +281,50 @@ mod tests {
         });
     }
 
+    #[test]
+    fn eval_bare_return_statement_test() {
+        let input_expected: Vec<(&str, &str)> = vec![
+            ("let f: fn(): return.~. f()", "null"),
+            ("let f: fn(x): if x > 0: return.~ x~. f(1)", "null"),
+            ("let f: fn(x): if x > 0: return.~ x~. f(-1)", "-1"),
+            ("return. 9.", "null"),
+        ];
+
+        test_util::assert_list(input_expected, |expected: &&str, input: &&str| {
+            let object = test_util::expect_evaled_program(input);
+            assert_eq!(expected, &object.to_string(), "Wrong result for '{input}'");
+        });
+    }
+
+    #[test]
+    fn top_level_return_policy_test() {
+        let mut env = Environment::new_env_reference();
+        match eval::eval("return 5. 6.", &mut env) {
+            EvaledProgram::Valid(Object::Integer(integer)) => assert_eq!(5, integer),
+            _ => panic!("Expected a top level return to be the value of the program"),
+        }
+
+        env.borrow()
+            .context()
+            .borrow_mut()
+            .set_allow_top_level_return(false);
+
+        for input in ["return 5.", "if true: return 5.~", "while true: return 5.~"] {
+            assert!(
+                matches!(
+                    eval::eval(input, &mut env),
+                    EvaledProgram::EvalError(EvalError::ReturnOutsideFunction)
+                ),
+                "Expected '{input}' to fail"
+            );
+        }
+
+        match eval::eval("let f: fn(): return 5.~. f() + 1", &mut env) {
+            EvaledProgram::Valid(Object::Integer(integer)) => assert_eq!(6, integer),
+            _ => panic!("Expected a return inside a function to be allowed"),
+        }
+    }
+
     #[test]
     fn eval_assignment_statement_test() {
         let input_expected: Vec<(&str, i32)> = vec![
@@ -145,4 +347,256 @@ mod tests {
             }
         });
     }
+
+    #[test]
+    fn eval_indexed_assignment_test() {
+        let input_expected: Vec<(&str, &str)> = vec![
+            ("let a: [1, 2, 3]. let a[0]: 99. a", "[99, 2, 3]"),
+            (
+                "let a: [1, 2, 3]. let a[len(a) - 1]: a[0] * 10. a",
+                "[1, 2, 10]",
+            ),
+            ("let h: {\"a\": 1}. let h[\"a\"]: 2. h", "{\"a\": 2}"),
+            (
+                "let h: {\"a\": 1}. let h[\"b\"]: 2. h",
+                "{\"a\": 1, \"b\": 2}",
+            ),
+            (
+                "let m: [[1, 2], [3, 4]]. let m[1][0]: 0. m",
+                "[[1, 2], [0, 4]]",
+            ),
+            ("let h: {1: [true]}. let h[1][0]: false. h", "{1: [false]}"),
+            ("let a: [1]. let b: a. let b[0]: 2. a", "[1]"),
+            ("let a: [1]. let a[0] integer: 2. a", "[2]"),
+        ];
+
+        test_util::assert_list(input_expected, |expected: &&str, input: &&str| {
+            let object = test_util::expect_evaled_program(input);
+            assert_eq!(expected, &object.to_string(), "Wrong result for '{input}'");
+        });
+
+        let input_errors = [
+            ("let a: [1, 2]. let a[2]: 0.", "E1036"),
+            ("let a: [1, 2]. let a[-1]: 0.", "E1036"),
+            ("let a: [1, 2]. let a[\"0\"]: 0.", "E1020"),
+            ("let t: (1, 2). let t[0]: 0.", "E1020"),
+            ("let h: {}. let h[[1]]: 0.", "E1021"),
+            ("let h: {}. let h[\"a\"][0]: 0.", "E1020"),
+            ("let b[0]: 0.", "E1008"),
+            ("const a: [1]. let a[0]: 2.", "E1019"),
+            ("let a: [1]. let a[0] string: 2.", "E1032"),
+        ];
+
+        for (input, code) in input_errors {
+            match eval::eval(input, &mut Environment::new_env_reference()) {
+                EvaledProgram::EvalError(eval_error) => {
+                    assert_eq!(code, eval_error.code(), "Wrong error for '{input}'")
+                }
+                _ => panic!("'{input}' should fail"),
+            }
+        }
+    }
+
+    #[test]
+    fn eval_block_scope_test() {
+        let input_expected: Vec<(&str, i32)> = vec![
+            ("let x: 1. if true: let x: 99.~ x", 1),
+            ("let x: 1. if false: 2 else: let x: 99.~ x", 1),
+            ("let x: 1. if true: let y: x + 1. y~", 2),
+            ("let x: 1. let f: fn(): if true: let x: 5.~ x~ f()", 1),
+        ];
+
+        test_util::assert_list(input_expected, |expected: &i32, input: &&str| {
+            match test_util::expect_evaled_program(input) {
+                Object::Integer(integer) => assert_eq!(expected, &integer, "Wrong for '{input}'"),
+                something_else => panic!("Expected integer, got {something_else} for '{input}'"),
+            }
+        });
+    }
+
+    #[test]
+    fn eval_const_statement_test() {
+        match test_util::expect_evaled_program("const x: 5. x + 1") {
+            Object::Integer(integer) => assert_eq!(6, integer),
+            something_else => panic!("Expected integer, got {something_else}"),
+        }
+
+        for input in ["const x: 5. let x: 6.", "const x: 5. const x: 6."] {
+            match eval::eval(input, &mut Environment::new_env_reference()) {
+                EvaledProgram::EvalError(EvalError::ReassignConstant(identifier)) => {
+                    assert_eq!("x", identifier)
+                }
+                _ => panic!("Expected reassigning a constant to fail for '{input}'"),
+            }
+        }
+    }
+
+    #[test]
+    fn eval_annotated_assignment_test() {
+        let input_expected: Vec<(&str, &str)> = vec![
+            ("let a integer: 5. a", "5"),
+            ("let x int: 5. x", "5"),
+            ("let a bool: true. let b str: \"b\". a", "true"),
+            ("let a float: 0.5. a", "0.5"),
+            ("let a boolean: 1 < 2. a", "true"),
+            ("const a string: \"x\". a", "\"x\""),
+            ("let a array: [1]. a", "[1]"),
+            ("let f function: fn(x): x~. f(2)", "2"),
+        ];
+
+        test_util::assert_list(input_expected, |expected: &&str, input: &&str| {
+            let object = test_util::expect_evaled_program(input);
+            assert_eq!(expected, &object.to_string(), "Wrong result for '{input}'");
+        });
+
+        match eval::eval(
+            "let a integer: true.",
+            &mut Environment::new_env_reference(),
+        ) {
+            EvaledProgram::EvalError(EvalError::TypeMismatch { expected, got }) => {
+                assert_eq!("integer", expected);
+                assert_eq!(Object::Boolean(true), got);
+            }
+            _ => panic!("Expected assigning a boolean to an integer to fail"),
+        }
+        assert!(matches!(
+            eval::eval(
+                "let a: 1. let a integer: \"one\". a",
+                &mut Environment::new_env_reference()
+            ),
+            EvaledProgram::EvalError(EvalError::TypeMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn eval_parallel_assign_statement_test() {
+        let input_expected: Vec<(&str, i32)> = vec![
+            ("let a, b: 1, 2. a + b", 3),
+            ("let a, b: 1, 2. let a, b: b, a. a * 10 + b", 21),
+            ("let a: 5. let a, b: 1, a. b", 5),
+        ];
+
+        test_util::assert_list(input_expected, |expected: &i32, input: &&str| {
+            match test_util::expect_evaled_program(input) {
+                Object::Integer(integer) => assert_eq!(expected, &integer, "Wrong for '{input}'"),
+                something_else => panic!("Expected integer, got {something_else} for '{input}'"),
+            }
+        });
+    }
+
+    #[test]
+    fn eval_while_statement_test() {
+        let input_expected: Vec<(&str, i32)> = vec![
+            ("let i: 0. while i < 5: let i: i + 1.~ i", 5),
+            (
+                "let i: 0. while true: let i: i + 1. if i == 3: break.~~ i",
+                3,
+            ),
+            (
+                "
+                let i: 0.
+                let sum: 0.
+                while i < 5:
+                    let i: i + 1.
+                    if i == 2: continue.~
+                    let sum: sum + i.
+                ~
+                sum
+                ",
+                13,
+            ),
+            ("let f: fn(): while true: return 7.~~ f()", 7),
+        ];
+
+        test_util::assert_list(input_expected, |expected: &i32, input: &&str| {
+            match test_util::expect_evaled_program(input) {
+                Object::Integer(integer) => assert_eq!(expected, &integer, "Wrong for '{input}'"),
+                something_else => panic!("Expected integer, got {something_else} for '{input}'"),
+            }
+        });
+    }
+
+    #[test]
+    fn eval_do_while_statement_test() {
+        let input_expected: Vec<(&str, &str)> = vec![
+            (
+                "let runs: 0. do: let runs: runs + 1.~ while false. runs",
+                "1",
+            ),
+            ("let i: 0. do: let i: i + 1.~ while i < 5. i", "5"),
+            ("let last: { do: 7.~ while false. }. last", "7"),
+            (
+                "let i: 0. do: let i: i + 1. if i == 3: break.~~ while true. i",
+                "3",
+            ),
+            (
+                "let i: 0. do: let i: i + 1. continue. let i: 100.~ while i < 4. i",
+                "4",
+            ),
+            ("let f: fn(): do: return 2.~ while true.~ f()", "2"),
+        ];
+
+        test_util::assert_list(input_expected, |expected: &&str, input: &&str| {
+            let object = test_util::expect_evaled_program(input);
+            assert_eq!(expected, &object.to_string(), "Wrong result for '{input}'");
+        });
+
+        assert!(matches!(
+            eval::eval("do: 1.~ while 1.", &mut Environment::new_env_reference()),
+            EvaledProgram::EvalError(EvalError::NonBooleanConditional(_))
+        ));
+    }
+
+    #[test]
+    fn while_statement_yields_last_value_test() {
+        let input_expected: Vec<(&str, &str)> = vec![
+            ("let i: 0. let last: { while i < 3: let i: i + 1. i * 10.~ }. last", "30"),
+            ("let last: { while false: 1.~ }. last", "null"),
+            (
+                "let i: 0. let f: fn(): while true: let i: i + 1. i. if i == 4: break.~~~ f()",
+                "4",
+            ),
+            (
+                "let i: 0. let f: fn(): while i < 3: let i: i + 1. \"odd\". if i == 2: continue.~ i.~~ f()",
+                "3",
+            ),
+        ];
+
+        test_util::assert_list(input_expected, |expected: &&str, input: &&str| {
+            let object = test_util::expect_evaled_program(input);
+            assert_eq!(expected, &object.to_string(), "Wrong result for '{input}'");
+        });
+    }
+
+    #[test]
+    fn loop_control_outside_loop_should_fail_test() {
+        assert!(matches!(
+            eval::eval("break.", &mut Environment::new_env_reference()),
+            EvaledProgram::EvalError(EvalError::BreakOutsideLoop)
+        ));
+        assert!(matches!(
+            eval::eval(
+                "let f: fn(): continue.~ f()",
+                &mut Environment::new_env_reference()
+            ),
+            EvaledProgram::EvalError(EvalError::ContinueOutsideLoop)
+        ));
+    }
+
+    #[test]
+    fn step_limit_stops_infinite_loop_test() {
+        let mut env = Environment::new_env_reference();
+        env.borrow().context().borrow_mut().set_step_limit(1000);
+
+        assert!(matches!(
+            eval::eval("while true: 1.~", &mut env),
+            EvaledProgram::EvalError(EvalError::StepLimitExceeded(1000))
+        ));
+
+        env.borrow().context().borrow_mut().set_step_limit(1000);
+        match eval::eval("let i: 0. while i < 10: let i: i + 1.~ i", &mut env) {
+            EvaledProgram::Valid(Object::Integer(integer)) => assert_eq!(10, integer),
+            _ => panic!("Expected a short loop to finish within the step limit"),
+        }
+    }
 }
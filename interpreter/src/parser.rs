@@ -1,45 +1,160 @@
 pub(crate) mod assign_statement;
 pub(crate) mod ast;
 pub(crate) mod expressions;
-pub(crate) mod lexer;
+pub(crate) mod import_statement;
+pub mod lexer;
 pub(crate) mod parse_errors;
+pub(crate) mod pretty_print;
 pub(crate) mod return_statement;
+pub(crate) mod while_statement;
 
 #[cfg(test)]
 pub(crate) mod test_util;
 
-use tracing::{event, span, Level};
+/// Embedders name operators when overloading them for a type.
+pub use ast::Operator;
+
+use tracing::{event, field, span, Level};
 
 use crate::{
     parser::assign_statement::AssignStatement,
     parser::ast::Statement,
-    parser::expressions::expression_statement::ExpressionStatement,
-    parser::lexer::{lexedtokens::LexedTokens, token::Token},
-    parser::parse_errors::ParseError,
+    parser::expressions::{
+        expression::Expression, expression_statement::ExpressionStatement,
+        functions::FunctionLiteral,
+    },
+    parser::import_statement::ImportStatement,
+    parser::lexer::{
+        lexedtokens::LexedTokens,
+        span::Span,
+        token::{Precedence, Token},
+    },
+    parser::parse_errors::{ParseError, StatementError},
     parser::return_statement::ReturnStatement,
+    parser::while_statement::WhileStatement,
 };
 
+/// Deep enough for any program written by hand, while shallow enough that
+/// the recursive descent does not overflow the stack.
+const DEFAULT_NESTING_LIMIT: usize = 128;
+
 pub struct Parser {
     pub tokens: LexedTokens,
+    nesting_depth: usize,
+    nesting_limit: usize,
 }
 
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize),
+    serde(tag = "type", content = "value")
+)]
 pub enum ParsedProgram {
     ValidProgram(Vec<Statement>),
-    InvalidProgram(Vec<ParseError>),
+    InvalidProgram(
+        #[cfg_attr(feature = "serde", serde(serialize_with = "serialize_parse_errors"))]
+        Vec<StatementError>,
+    ),
+}
+
+/// Parse errors wrap errors from the standard library that can not be
+/// serialized, so they are serialized as their messages instead.
+#[cfg(feature = "serde")]
+fn serialize_parse_errors<S: serde::Serializer>(
+    parse_errors: &[StatementError],
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.collect_seq(parse_errors.iter().map(ToString::to_string))
+}
+
+/// Parses the source without evaluating it, and renders every operation of
+/// the program in parentheses, one statement per line. An invalid program is
+/// rendered as its parse errors.
+pub fn parse_and_dump(source: &str) -> String {
+    match Parser::parse_tokens(LexedTokens::from(source)) {
+        ParsedProgram::InvalidProgram(parse_errors) => {
+            let errors: String = parse_errors
+                .iter()
+                .map(|parse_error| format!("  {parse_error}\n"))
+                .collect();
+            format!("Found parse errors:\n{errors}")
+        }
+        valid_program => format!("{valid_program:#}"),
+    }
+}
+
+/// Parses the source as a single expression rather than as a program. The
+/// expression may end with a period, but anything after it is an error.
+pub fn parse_expression(source: &str) -> Result<Expression, Vec<ParseError>> {
+    let mut parser = Parser::from_tokens(LexedTokens::from(source));
+    parser
+        .parse_expression()
+        .map_err(|parse_error| vec![parse_error])
 }
 
 impl Parser {
+    pub fn new() -> Parser {
+        Parser::from_tokens(LexedTokens::from(""))
+    }
+
+    fn from_tokens(tokens: LexedTokens) -> Parser {
+        Parser {
+            tokens,
+            nesting_depth: 0,
+            nesting_limit: DEFAULT_NESTING_LIMIT,
+        }
+    }
+
     pub fn parse_tokens(tokens: LexedTokens) -> ParsedProgram {
-        let mut parser = Parser { tokens };
+        Parser::from_tokens(tokens).parse()
+    }
 
-        parser.parse_program()
+    /// Limits how deeply expressions and blocks may be nested before parsing
+    /// fails with [`ParseError::NestingLimitExceeded`].
+    pub fn set_nesting_limit(&mut self, nesting_limit: usize) {
+        self.nesting_limit = nesting_limit;
+    }
+
+    /// Runs a parse function one nesting level deeper, failing instead of
+    /// recursing further once the nesting limit is reached.
+    pub(crate) fn nested<T>(
+        &mut self,
+        parse: impl FnOnce(&mut Parser) -> Result<T, ParseError>,
+    ) -> Result<T, ParseError> {
+        if self.nesting_depth >= self.nesting_limit {
+            return Err(ParseError::NestingLimitExceeded(self.nesting_limit));
+        }
+
+        self.nesting_depth += 1;
+        let parsed = parse(self);
+        self.nesting_depth -= 1;
+        parsed
+    }
+
+    /// Prepares the parser for new source code, reusing the source buffer of
+    /// the previous source so many small programs can share one parser.
+    pub fn reset(&mut self, source_code: &str) {
+        self.tokens.relex(source_code);
+    }
+
+    pub fn parse(&mut self) -> ParsedProgram {
+        let parse_span = span!(Level::DEBUG, "Parse", statements = field::Empty);
+        let _enter = parse_span.enter();
+
+        let parsed_program = self.parse_program();
+
+        if let ParsedProgram::ValidProgram(statements) = &parsed_program {
+            parse_span.record("statements", statements.len());
+        }
+
+        parsed_program
     }
 
     fn parse_program(&mut self) -> ParsedProgram {
         let mut statements: Vec<Statement> = Vec::new();
-        let mut parse_errors: Vec<ParseError> = Vec::new();
+        let mut parse_errors: Vec<StatementError> = Vec::new();
 
-        while self.tokens.peek().is_some() {
+        while let Some(start) = self.tokens.peek_span() {
             let statement_span = span!(Level::DEBUG, "Statement");
             let _enter = statement_span.enter();
 
@@ -50,8 +165,13 @@ impl Parser {
                 }
                 Err(parse_error) => {
                     event!(Level::DEBUG, "Error parsing statement: {parse_error:?}");
+                    let end = self.tokens.previous_span().end.max(start.end);
+                    parse_errors.push(StatementError {
+                        statement: statements.len() + parse_errors.len(),
+                        span: Span::new(start.start, end),
+                        error: parse_error,
+                    });
                     self.tokens.iterate_to_next_statement();
-                    parse_errors.push(parse_error)
                 }
             };
         }
@@ -63,19 +183,48 @@ impl Parser {
         ParsedProgram::ValidProgram(statements)
     }
 
+    fn parse_expression(&mut self) -> Result<Expression, ParseError> {
+        let first_token = self.tokens.expect()?;
+        let expression = Expression::parse(self, first_token, Precedence::Lowest)?;
+        self.tokens.expect_optional_token(Token::Period);
+
+        match self.tokens.consume() {
+            Some(trailing_token) => Err(ParseError::TrailingTokens(trailing_token)),
+            None => Ok(expression),
+        }
+    }
+
     fn parse_statement(&mut self) -> Result<Statement, ParseError> {
         match self.tokens.peek() {
             Some(Token::Return) => ReturnStatement::parse_return_statement(self),
             Some(Token::Let) => AssignStatement::parse(self),
+            Some(Token::Const) => AssignStatement::parse_const(self),
+            Some(Token::Import) => ImportStatement::parse(self),
+            Some(Token::Func) => FunctionLiteral::parse_statement(self),
+            Some(Token::While) => WhileStatement::parse(self),
+            Some(Token::Do) => WhileStatement::parse_do_while(self),
+            Some(Token::Break | Token::Continue) => WhileStatement::parse_loop_control(self),
             Some(_) => ExpressionStatement::parse(self),
             None => Err(ParseError::ExpectedToken),
         }
     }
 }
 
+impl Default for Parser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::parser::test_util;
+    use crate::parser::{
+        self,
+        ast::Operator,
+        expressions::expression::Expression,
+        parse_errors::{ParseError, StatementError},
+        test_util, ParsedProgram, Parser,
+    };
 
     #[test]
     fn test_operator_precedence() {
@@ -83,7 +232,7 @@ mod tests {
             input: String,
             expected: String,
         }
-        let test_cases: [TestCase; 24] = [
+        let test_cases: [TestCase; 32] = [
             ("-a * b", "((-a) * b)"),
             ("!-a", "(!(-a))"),
             ("a + b + c", "((a + b) + c)"),
@@ -91,10 +240,13 @@ mod tests {
             ("a * b * c", "((a * b) * c)"),
             ("a * b / c", "((a * b) / c)"),
             ("a + b / c", "(a + (b / c))"),
+            ("a ?? b + c == d", "(a ?? ((b + c) == d))"),
             ("a + b * c + d / e - f", "(((a + (b * c)) + (d / e)) - f)"),
             ("3 + 4. -5 * 5.", "(3 + 4)((-5) * 5)"),
             ("5 > 4 == -3 < 4", "((5 > 4) == ((-3) < 4))"),
             ("5 < 4 != 3 > 4", "((5 < 4) != (3 > 4))"),
+            ("5 >= 4 == -3 <= 4", "((5 >= 4) == ((-3) <= 4))"),
+            ("a + b <= c * d", "((a + b) <= (c * d))"),
             (
                 "3 + 4 * 5 == 3 * 1 + 4 * 5",
                 "((3 + (4 * 5)) == ((3 * 1) + (4 * 5)))",
@@ -105,6 +257,9 @@ mod tests {
             ("3 < 5 == true", "((3 < 5) == true)"),
             ("1 + (2 + 3) + 4", "((1 + (2 + 3)) + 4)"),
             ("(5 + 5) * 2", "((5 + 5) * 2)"),
+            ("2 ^ 2 ^ 3", "(2 ^ (2 ^ 3))"),
+            ("a * b ^ c * d", "((a * (b ^ c)) * d)"),
+            ("(2 ^ 2) ^ 3", "((2 ^ 2) ^ 3)"),
             ("2 / (5 + 5)", "(2 / (5 + 5))"),
             ("-(5 + 5)", "(-(5 + 5))"),
             ("!(true == true)", "(!(true == true))"),
@@ -117,6 +272,14 @@ mod tests {
                 "add(a + b + c * d / f + g)",
                 "add((((a + b) + ((c * d) / f)) + g))",
             ),
+            (
+                "a * [1, 2, 3, 4][b * c] * d",
+                "((a * ([1, 2, 3, 4][(b * c)])) * d)",
+            ),
+            (
+                "add(a * b[2], b[1], 2 * [1, 2][1])",
+                "add((a * (b[2])), (b[1]), (2 * ([1, 2][1])))",
+            ),
         ]
         .map(|(input, expected)| TestCase {
             input: input.to_string(),
@@ -132,7 +295,110 @@ mod tests {
                 panic!("Found parser errors");
             }
 
-            assert_eq!(actual.to_string().replace('\n', ""), testcase.expected);
+            assert_eq!(format!("{actual:#}").replace('\n', ""), testcase.expected);
         }
     }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serialize_program_to_json_test() {
+        let program = test_util::parse_program("5 + 5");
+
+        let json = serde_json::to_value(&program).expect("Program should serialize");
+        let expression = &json["value"][0]["value"]["expression"];
+
+        assert_eq!(json["type"], "ValidProgram");
+        assert_eq!(expression["type"], "Infix");
+        assert_eq!(expression["value"]["operator"], "Plus");
+        assert_eq!(expression["value"]["left"]["type"], "IntegerLiteral");
+        assert_eq!(expression["value"]["left"]["value"], 5);
+
+        let invalid_program = test_util::parse_program("let: 5.");
+        let json = serde_json::to_value(&invalid_program).expect("Errors should serialize");
+        assert_eq!(json["type"], "InvalidProgram");
+        assert!(json["value"][0].is_string());
+    }
+
+    #[test]
+    fn parse_and_dump_test() {
+        assert_eq!("((5 + 5) * 2)\n", parser::parse_and_dump("(5 + 5) * 2"));
+        assert_eq!(
+            "let x: (1 + (2 * 3)).\n(-x)\n",
+            parser::parse_and_dump("let x: 1 + 2 * 3. -x")
+        );
+        assert_eq!(
+            "Found parse errors:\n  expected ')' but found end of input\n",
+            parser::parse_and_dump("(1, 2")
+        );
+    }
+
+    #[test]
+    fn parse_expression_test() {
+        assert_eq!(
+            test_util::create_infix_expression(
+                Expression::IntegerLiteral(1),
+                Expression::IntegerLiteral(2),
+                Operator::Plus,
+            ),
+            parser::parse_expression("1 + 2").expect("'1 + 2' should parse")
+        );
+        assert_eq!(
+            test_util::create_identifierliteral("a"),
+            parser::parse_expression("a.").expect("'a.' should parse")
+        );
+
+        let input_expected = vec![
+            ("1 +", "ExpectedToken"),
+            ("", "ExpectedToken"),
+            ("1 2", "TrailingTokens(Int(\"2\"))"),
+            ("1. 2", "TrailingTokens(Int(\"2\"))"),
+            ("let a: 1.", "NoPrefixExpression(Let)"),
+        ];
+
+        test_util::assert_list(input_expected, |expected: &&str, input: &&str| {
+            match parser::parse_expression(input) {
+                Err(parse_errors) => match parse_errors.as_slice() {
+                    [parse_error] => {
+                        assert_eq!(expected, &format!("{parse_error:?}"), "Wrong for '{input}'")
+                    }
+                    _ => panic!("Expected a single error for '{input}'"),
+                },
+                Ok(expression) => panic!("'{input}' should not parse, got {expression}"),
+            }
+        });
+    }
+
+    #[test]
+    fn deep_nesting_should_fail_test() {
+        let depth = 5000;
+        let input = format!("{}1{}.", "(".repeat(depth), ")".repeat(depth));
+
+        match test_util::parse_program(&input) {
+            ParsedProgram::InvalidProgram(parse_errors) => assert!(matches!(
+                parse_errors.as_slice(),
+                [StatementError {
+                    error: ParseError::NestingLimitExceeded(_),
+                    ..
+                }]
+            )),
+            ParsedProgram::ValidProgram(_) => panic!("Deep nesting should not parse"),
+        }
+    }
+
+    #[test]
+    fn nesting_limit_test() {
+        let nested_ifs = "if true: if true: if true: 1~~~.";
+
+        let mut parser = Parser::new();
+        parser.reset(nested_ifs);
+        assert!(matches!(parser.parse(), ParsedProgram::ValidProgram(_)));
+
+        parser.set_nesting_limit(4);
+        parser.reset(nested_ifs);
+        assert!(matches!(
+            parser.parse(),
+            ParsedProgram::InvalidProgram(parse_errors)
+                if matches!(parse_errors.as_slice(), [StatementError { error: ParseError::NestingLimitExceeded(4), .. }])
+        ));
+    }
 }
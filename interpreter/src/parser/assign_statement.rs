@@ -9,15 +9,92 @@ use super::{
 };
 
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct AssignStatement {
     pub identifier: Identifier,
+    /// The indices in `let a[0][1]: 5.`, which assign into the array or hash
+    /// bound to the identifier instead of binding the identifier itself.
+    pub indices: Vec<Expression>,
+    /// The type name in `let x integer: 5.`, which the value is checked
+    /// against when it is assigned. It is one of the names in `TYPE_NAMES`.
+    pub type_annotation: Option<Identifier>,
     pub assignment: Expression,
 }
 
+/// The names a type annotation can use, with the type each stands for. The
+/// types are named as `Object::type_name` names them, and `int`, `bool` and
+/// `str` are aliases for `integer`, `boolean` and `string`.
+const TYPE_NAMES: [(&str, &str); 12] = [
+    ("integer", "integer"),
+    ("int", "integer"),
+    ("float", "float"),
+    ("boolean", "boolean"),
+    ("bool", "boolean"),
+    ("string", "string"),
+    ("str", "string"),
+    ("array", "array"),
+    ("tuple", "tuple"),
+    ("hash", "hash"),
+    ("null", "null"),
+    ("function", "function"),
+];
+
+/// Binds several identifiers at once, as in `let a, b: 1, 2.`. Every value is
+/// evaluated before any identifier is bound, so `let a, b: b, a.` swaps them.
+#[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ParallelAssignStatement {
+    pub identifiers: Vec<Identifier>,
+    pub assignments: Vec<Expression>,
+}
+
 impl AssignStatement {
     pub fn parse(parser: &mut Parser) -> Result<Statement, ParseError> {
         parser.tokens.expect_token(Token::Let)?;
         let identifier = parser.tokens.expected_identifier()?;
+
+        if parser.tokens.next_token_is(&Token::Comma) {
+            return ParallelAssignStatement::parse(parser, identifier);
+        }
+
+        let mut indices = Vec::new();
+        while parser.tokens.next_token_is(&Token::LBracket) {
+            parser.tokens.consume();
+            let next_token = parser.tokens.expect()?;
+            indices.push(Expression::parse(parser, next_token, Precedence::Lowest)?);
+            parser.tokens.expect_token(Token::RBracket)?;
+        }
+
+        Ok(Statement::Assign(Self::parse_binding(
+            parser, identifier, indices,
+        )?))
+    }
+
+    pub fn parse_const(parser: &mut Parser) -> Result<Statement, ParseError> {
+        parser.tokens.expect_token(Token::Const)?;
+        let identifier = parser.tokens.expected_identifier()?;
+        Ok(Statement::ConstAssign(Self::parse_binding(
+            parser,
+            identifier,
+            Vec::new(),
+        )?))
+    }
+
+    fn parse_binding(
+        parser: &mut Parser,
+        identifier: Identifier,
+        indices: Vec<Expression>,
+    ) -> Result<AssignStatement, ParseError> {
+        let type_annotation = match parser.tokens.peek() {
+            Some(Token::Ident(_)) => {
+                let type_annotation = parser.tokens.expected_identifier()?;
+                if resolve_type_name(&type_annotation.0).is_none() {
+                    return Err(ParseError::UnknownType(type_annotation));
+                }
+                Some(type_annotation)
+            }
+            _ => None,
+        };
         parser.tokens.expect_token(Token::Assign)?;
 
         let next_token = parser.tokens.expect()?;
@@ -25,25 +102,107 @@ impl AssignStatement {
 
         parser.tokens.expect_optional_token(Token::Period);
 
-        Ok(Statement::Assign(AssignStatement {
+        Ok(AssignStatement {
             identifier,
+            indices,
+            type_annotation,
             assignment: expression,
+        })
+    }
+}
+
+fn resolve_type_name(type_annotation: &str) -> Option<&'static str> {
+    TYPE_NAMES
+        .iter()
+        .find(|(name, _)| *name == type_annotation)
+        .map(|(_, type_name)| *type_name)
+}
+
+impl ParallelAssignStatement {
+    fn parse(parser: &mut Parser, first_identifier: Identifier) -> Result<Statement, ParseError> {
+        let mut identifiers = Vec::from([first_identifier]);
+        while parser.tokens.next_token_is(&Token::Comma) {
+            parser.tokens.consume();
+            identifiers.push(parser.tokens.expected_identifier()?);
+        }
+        parser.tokens.expect_token(Token::Assign)?;
+
+        let mut assignments = Vec::new();
+        loop {
+            let next_token = parser.tokens.expect()?;
+            assignments.push(Expression::parse(parser, next_token, Precedence::Lowest)?);
+
+            if !parser.tokens.next_token_is(&Token::Comma) {
+                break;
+            }
+            parser.tokens.consume();
+        }
+
+        if identifiers.len() != assignments.len() {
+            return Err(ParseError::AssignmentArityMismatch {
+                targets: identifiers.len(),
+                values: assignments.len(),
+            });
+        }
+
+        parser.tokens.expect_optional_token(Token::Period);
+
+        Ok(Statement::ParallelAssign(ParallelAssignStatement {
+            identifiers,
+            assignments,
         }))
     }
 }
 
+impl Display for ParallelAssignStatement {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let identifiers: Vec<String> = self.identifiers.iter().map(ToString::to_string).collect();
+        let assignments: Vec<String> = self.assignments.iter().map(ToString::to_string).collect();
+        write!(f, "{}: {}.", identifiers.join(", "), assignments.join(", "))
+    }
+}
+
+impl AssignStatement {
+    /// The name of the type the annotation stands for, which aliases are
+    /// resolved to.
+    pub fn annotated_type(&self) -> Option<&'static str> {
+        self.type_annotation
+            .as_ref()
+            .and_then(|type_annotation| resolve_type_name(&type_annotation.0))
+    }
+
+    /// The identifier and its indices, followed by the type annotation if
+    /// there is one.
+    pub fn target(&self) -> String {
+        let indices: String = self
+            .indices
+            .iter()
+            .map(|index| format!("[{index}]"))
+            .collect();
+        match &self.type_annotation {
+            Some(type_annotation) => format!("{}{indices} {type_annotation}", self.identifier),
+            None => format!("{}{indices}", self.identifier),
+        }
+    }
+}
+
 impl Display for AssignStatement {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "let {}: {}.", self.identifier, self.assignment)
+        write!(f, "{}: ", self.target())?;
+        self.assignment.fmt(f)?;
+        write!(f, ".")
     }
 }
 
 #[cfg(test)]
 mod tests {
     use crate::parser::{
-        ast::{Identifier, Statement},
+        assign_statement::ParallelAssignStatement,
+        ast::{Identifier, Operator, Statement},
         expressions::expression::Expression,
+        parse_errors::ParseError,
         test_util::{self},
+        ParsedProgram,
     };
 
     #[test]
@@ -95,4 +254,136 @@ mod tests {
             incorrect => panic!("Expected let-statement, but got {incorrect:?}"),
         };
     }
+
+    #[test]
+    fn parse_annotated_assign_statement() {
+        let statements = test_util::expect_parsed_program("let x integer: 5. let y: 6.");
+
+        match statements.as_slice() {
+            [Statement::Assign(annotated), Statement::Assign(unannotated)] => {
+                assert_eq!(
+                    annotated.type_annotation,
+                    Some(Identifier(String::from("integer")))
+                );
+                assert_eq!(annotated.assignment, Expression::IntegerLiteral(5));
+                assert_eq!(annotated.to_string(), "x integer: 5.");
+                assert_eq!(unannotated.type_annotation, None);
+            }
+            incorrect => panic!("Expected two let-statements, but got {incorrect:?}"),
+        }
+
+        match test_util::expect_parsed_program("const name string: \"lasagna\".").first() {
+            Some(Statement::ConstAssign(assign_statement)) => assert_eq!(
+                assign_statement.type_annotation,
+                Some(Identifier(String::from("string")))
+            ),
+            incorrect => panic!("Expected const statement, but got {incorrect:?}"),
+        }
+
+        match test_util::expect_parsed_program("let x int: 5.").first() {
+            Some(Statement::Assign(assign_statement)) => {
+                assert_eq!(assign_statement.to_string(), "x int: 5.");
+                assert_eq!(assign_statement.annotated_type(), Some("integer"));
+            }
+            incorrect => panic!("Expected let-statement, but got {incorrect:?}"),
+        }
+    }
+
+    #[test]
+    fn unknown_type_annotation_should_fail() {
+        match test_util::parse_program("let x number: 5.") {
+            ParsedProgram::InvalidProgram(parse_errors) => assert!(matches!(
+                parse_errors.first().map(|parse_error| &parse_error.error),
+                Some(ParseError::UnknownType(Identifier(type_annotation))) if type_annotation == "number"
+            )),
+            ParsedProgram::ValidProgram(_) => panic!("Unknown type annotation should not parse"),
+        }
+    }
+
+    #[test]
+    fn parse_const_assign_statement() {
+        let statements = test_util::expect_parsed_program("const x: 5.");
+
+        match statements.first() {
+            Some(Statement::ConstAssign(assign_statement)) => {
+                assert_eq!(assign_statement.identifier, Identifier(String::from("x")));
+                assert_eq!(assign_statement.assignment, Expression::IntegerLiteral(5));
+            }
+            incorrect => panic!("Expected const statement, but got {incorrect:?}"),
+        }
+    }
+
+    #[test]
+    fn parse_indexed_assign_statement() {
+        let statements = test_util::expect_parsed_program("let a[0]: 99. let b[\"x\"][i + 1]: 1.");
+
+        match statements.as_slice() {
+            [Statement::Assign(array_element), Statement::Assign(nested_element)] => {
+                assert_eq!(array_element.identifier, Identifier(String::from("a")));
+                assert_eq!(array_element.indices, vec![Expression::IntegerLiteral(0)]);
+                assert_eq!(array_element.assignment, Expression::IntegerLiteral(99));
+                assert_eq!(
+                    nested_element.indices,
+                    vec![
+                        Expression::StringLiteral(String::from("x")),
+                        test_util::create_infix_expression(
+                            test_util::create_identifierliteral("i"),
+                            Expression::IntegerLiteral(1),
+                            Operator::Plus,
+                        ),
+                    ]
+                );
+                assert_eq!(nested_element.to_string(), "b[\"x\"][i + 1]: 1.");
+            }
+            incorrect => panic!("Expected two let-statements, but got {incorrect:?}"),
+        }
+
+        for input in [
+            "let a[0: 1.",
+            "let a[]: 1.",
+            "const a[0]: 1.",
+            "let a[0], b: 1, 2.",
+        ] {
+            assert!(
+                test_util::has_parser_errors(&test_util::parse_program(input)),
+                "'{input}' should not parse"
+            );
+        }
+    }
+
+    #[test]
+    fn parse_parallel_assign_statement() {
+        let statements = test_util::expect_parsed_program("let a, b: 1, 2 + 3.");
+
+        assert_eq!(
+            statements,
+            vec![Statement::ParallelAssign(ParallelAssignStatement {
+                identifiers: vec![Identifier(String::from("a")), Identifier(String::from("b")),],
+                assignments: vec![
+                    Expression::IntegerLiteral(1),
+                    test_util::create_infix_expression(
+                        Expression::IntegerLiteral(2),
+                        Expression::IntegerLiteral(3),
+                        Operator::Plus,
+                    ),
+                ],
+            })]
+        );
+    }
+
+    #[test]
+    fn parallel_assign_arity_mismatch_should_fail() {
+        for input in ["let a, b: 1.", "let a, b: 1, 2, 3."] {
+            match test_util::parse_program(input) {
+                ParsedProgram::InvalidProgram(parse_errors) => assert!(
+                    matches!(
+                        parse_errors.first().map(|parse_error| &parse_error.error),
+                        Some(ParseError::AssignmentArityMismatch { .. })
+                    ),
+                    "Wrong error for '{input}': {parse_errors:?}"
+                ),
+                ParsedProgram::ValidProgram(_) => panic!("'{input}' should not parse"),
+            }
+        }
+    }
 }
@@ -1,38 +1,53 @@
-use std::fmt::Display;
-
-use crate::eval::objects::FunctionListable;
+use std::{cmp::Ordering, fmt::Display};
 
 use super::{
-    assign_statement::AssignStatement,
+    assign_statement::{AssignStatement, ParallelAssignStatement},
     expressions::{
         expression::Expression, expression_statement::ExpressionStatement,
         functions::CallExpression, if_expression::IfExpression,
     },
-    lexer::token::Token,
+    import_statement::ImportStatement,
+    lexer::token::{Precedence, Token},
     parse_errors::ParseError,
     return_statement::ReturnStatement,
+    while_statement::WhileStatement,
     ParsedProgram,
 };
 
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize),
+    serde(tag = "type", content = "value")
+)]
 pub enum Statement {
     Assign(AssignStatement),
+    ConstAssign(AssignStatement),
+    ParallelAssign(ParallelAssignStatement),
     Return(ReturnStatement),
     Expression(ExpressionStatement),
+    Import(ImportStatement),
+    While(WhileStatement),
+    DoWhile(WhileStatement),
+    Break,
+    Continue,
 }
 
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct BlockStatement {
     pub statements: Vec<Statement>,
 }
 
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum PrefixOperator {
     Bang,
     Minus,
 }
 
-#[derive(PartialEq, Debug, Clone)]
+#[derive(PartialEq, Eq, Hash, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum Operator {
     Bang,
     Minus,
@@ -42,10 +57,16 @@ pub enum Operator {
     NotEquals,
     GreaterThan,
     LessThan,
+    GreaterThanOrEqual,
+    LessThanOrEqual,
     DividedBy,
+    Modulo,
+    Power,
+    NullCoalesce,
 }
 
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Identifier(pub String);
 
 impl Identifier {
@@ -65,7 +86,8 @@ impl Display for ParsedProgram {
         match self {
             ParsedProgram::ValidProgram(statements) => {
                 for statement in statements {
-                    writeln!(f, "{}", statement)?;
+                    statement.fmt(f)?;
+                    writeln!(f)?;
                 }
             }
             ParsedProgram::InvalidProgram(errors) => {
@@ -82,9 +104,30 @@ impl Display for ParsedProgram {
 impl Display for Statement {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Statement::Assign(assign_statement) => write!(f, "{assign_statement}"),
-            Statement::Return(expression) => write!(f, "{expression}"),
-            Statement::Expression(expression) => write!(f, "{expression}"),
+            Statement::Assign(assign_statement) => {
+                write!(f, "let ")?;
+                assign_statement.fmt(f)
+            }
+            Statement::ConstAssign(assign_statement) => {
+                write!(f, "const ")?;
+                assign_statement.fmt(f)
+            }
+            Statement::ParallelAssign(parallel_assign_statement) => {
+                write!(f, "let ")?;
+                parallel_assign_statement.fmt(f)
+            }
+            Statement::Return(expression) => expression.fmt(f),
+            Statement::Expression(expression) => expression.fmt(f),
+            Statement::Import(import_statement) => import_statement.fmt(f),
+            Statement::While(while_statement) => while_statement.fmt(f),
+            Statement::DoWhile(while_statement) => {
+                write!(f, "do: ")?;
+                while_statement.body.fmt(f)?;
+                write!(f, " while ")?;
+                while_statement.condition.fmt(f)
+            }
+            Statement::Break => write!(f, "break"),
+            Statement::Continue => write!(f, "continue"),
         }
     }
 }
@@ -93,44 +136,226 @@ impl Display for BlockStatement {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         self.statements
             .iter()
-            .try_for_each(|statement| write!(f, "{}", statement))
+            .try_for_each(|statement| statement.fmt(f))
     }
 }
 
 impl Display for Expression {
+    /// Expressions are written with only the parentheses their precedence
+    /// requires, while the alternate format `{:#}` parenthesizes every operation.
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Expression::IdentifierLiteral(ident) => write!(f, "{ident}"),
             Expression::IntegerLiteral(integer_literal) => write!(f, "{integer_literal}"),
-            Expression::Prefix { right, operator } => write!(f, "({operator}{right})"),
+            Expression::FloatLiteral(float_literal) => write!(f, "{float_literal}"),
+            Expression::Prefix {
+                right, operator, ..
+            } => parenthesize_alternate(f, |f| {
+                write!(f, "{operator}")?;
+                write_operand(f, right, matches!(right.as_ref(), Expression::Infix { .. }))
+            }),
             Expression::Infix {
                 left,
                 right,
                 operator,
-            } => write!(f, "({left} {operator} {right})"),
+                ..
+            } => parenthesize_alternate(f, |f| {
+                write_operand(f, left, operator.needs_parentheses(left, true))?;
+                write!(f, " {operator} ")?;
+                write_operand(f, right, operator.needs_parentheses(right, false))
+            }),
             Expression::BooleanLiteral(boolean) => write!(f, "{boolean}"),
+            Expression::StringLiteral(string) => write!(f, "\"{string}\""),
             Expression::If(IfExpression {
                 condition,
                 consequence,
                 alternative,
             }) => {
-                write!(f, "if {condition}: {consequence}")?;
+                write!(f, "if ")?;
+                condition.fmt(f)?;
+                write!(f, ": ")?;
+                consequence.fmt(f)?;
                 match alternative {
                     Some(found_alternative) => {
-                        write!(f, " else: {found_alternative}")
+                        write!(f, " else: ")?;
+                        found_alternative.fmt(f)
                     }
                     None => Ok(()),
                 }
             }
-            Expression::Function(function_literal) => {
-                write!(f, "{function_literal}")
-            }
+            Expression::Function(function_literal) => function_literal.fmt(f),
             Expression::Call(CallExpression {
                 function,
                 arguments,
+                ..
             }) => {
-                write!(f, "{function}({})", arguments.to_function_string())
+                write_operand(f, function, function.is_operation())?;
+                write!(f, "(")?;
+                write_list(f, arguments)?;
+                write!(f, ")")
             }
+            Expression::ArrayLiteral(elements) => {
+                write!(f, "[")?;
+                write_list(f, elements)?;
+                write!(f, "]")
+            }
+            Expression::TupleLiteral(elements) => {
+                write!(f, "(")?;
+                write_list(f, elements)?;
+                write!(f, ")")
+            }
+            Expression::Index { left, index, .. } => parenthesize_alternate(f, |f| {
+                write_operand(f, left, left.is_operation())?;
+                write!(f, "[")?;
+                index.fmt(f)?;
+                write!(f, "]")
+            }),
+            Expression::HashLiteral(pairs) => {
+                write!(f, "{{")?;
+                for (position, (key, value)) in pairs.iter().enumerate() {
+                    if position > 0 {
+                        write!(f, ", ")?;
+                    }
+                    key.fmt(f)?;
+                    write!(f, ": ")?;
+                    value.fmt(f)?;
+                }
+                write!(f, "}}")
+            }
+            Expression::Block(block) => {
+                write!(f, "{{ ")?;
+                block.fmt(f)?;
+                write!(f, " }}")
+            }
+        }
+    }
+}
+
+impl Expression {
+    fn is_operation(&self) -> bool {
+        matches!(self, Expression::Prefix { .. } | Expression::Infix { .. })
+    }
+}
+
+fn parenthesize_alternate<F>(f: &mut std::fmt::Formatter<'_>, write: F) -> std::fmt::Result
+where
+    F: FnOnce(&mut std::fmt::Formatter<'_>) -> std::fmt::Result,
+{
+    if !f.alternate() {
+        return write(f);
+    }
+
+    write!(f, "(")?;
+    write(f)?;
+    write!(f, ")")
+}
+
+fn write_operand(
+    f: &mut std::fmt::Formatter<'_>,
+    operand: &Expression,
+    needs_parentheses: bool,
+) -> std::fmt::Result {
+    if !needs_parentheses || f.alternate() {
+        return operand.fmt(f);
+    }
+
+    write!(f, "(")?;
+    operand.fmt(f)?;
+    write!(f, ")")
+}
+
+fn write_list(f: &mut std::fmt::Formatter<'_>, elements: &[Expression]) -> std::fmt::Result {
+    for (position, element) in elements.iter().enumerate() {
+        if position > 0 {
+            write!(f, ", ")?;
+        }
+        element.fmt(f)?;
+    }
+
+    Ok(())
+}
+
+impl Operator {
+    pub fn as_token(&self) -> Token {
+        match self {
+            Operator::Bang => Token::Bang,
+            Operator::Minus => Token::Minus,
+            Operator::Plus => Token::Add,
+            Operator::Multiply => Token::Asterix,
+            Operator::Equals => Token::Equal,
+            Operator::NotEquals => Token::NotEqual,
+            Operator::GreaterThan => Token::GreaterThan,
+            Operator::LessThan => Token::LessThan,
+            Operator::GreaterThanOrEqual => Token::GreaterThanEqual,
+            Operator::LessThanOrEqual => Token::LessThanEqual,
+            Operator::DividedBy => Token::Slash,
+            Operator::Modulo => Token::Percent,
+            Operator::Power => Token::Caret,
+            Operator::NullCoalesce => Token::DoubleQuestion,
+        }
+    }
+
+    pub fn from_token(token: &Token) -> Option<Operator> {
+        match token {
+            Token::Bang => Some(Operator::Bang),
+            Token::Minus => Some(Operator::Minus),
+            Token::Add => Some(Operator::Plus),
+            Token::Asterix => Some(Operator::Multiply),
+            Token::Equal => Some(Operator::Equals),
+            Token::NotEqual => Some(Operator::NotEquals),
+            Token::GreaterThan => Some(Operator::GreaterThan),
+            Token::LessThan => Some(Operator::LessThan),
+            Token::GreaterThanEqual => Some(Operator::GreaterThanOrEqual),
+            Token::LessThanEqual => Some(Operator::LessThanOrEqual),
+            Token::Slash => Some(Operator::DividedBy),
+            Token::Percent => Some(Operator::Modulo),
+            Token::Caret => Some(Operator::Power),
+            Token::DoubleQuestion => Some(Operator::NullCoalesce),
+            _ => None,
+        }
+    }
+
+    pub fn precedence(&self) -> Precedence {
+        match self {
+            Operator::Equals | Operator::NotEquals => Precedence::Equals,
+            Operator::GreaterThan
+            | Operator::LessThan
+            | Operator::GreaterThanOrEqual
+            | Operator::LessThanOrEqual => Precedence::LessGreater,
+            Operator::Plus | Operator::Minus => Precedence::Sum,
+            Operator::Multiply | Operator::DividedBy | Operator::Modulo => Precedence::Product,
+            Operator::Power => Precedence::Power,
+            Operator::NullCoalesce => Precedence::Coalesce,
+            Operator::Bang => Precedence::Prefix,
+        }
+    }
+
+    pub fn is_right_associative(&self) -> bool {
+        matches!(self, Operator::Power)
+    }
+
+    /// An operand with lower precedence than the operator has to be
+    /// parenthesized, as does one with equal precedence on the side the
+    /// operator does not associate towards. Comparisons do not associate at all.
+    fn needs_parentheses(&self, operand: &Expression, is_left: bool) -> bool {
+        let Expression::Infix {
+            operator: operand_operator,
+            ..
+        } = operand
+        else {
+            return false;
+        };
+
+        match operand_operator
+            .precedence()
+            .partial_cmp(&self.precedence())
+        {
+            Some(Ordering::Less) => true,
+            Some(Ordering::Equal) => {
+                self.is_right_associative() == is_left
+                    || self.precedence() == Precedence::LessGreater
+            }
+            _ => false,
         }
     }
 }
@@ -161,7 +386,12 @@ impl Display for Operator {
             Operator::NotEquals => write!(f, "!="),
             Operator::GreaterThan => write!(f, ">"),
             Operator::LessThan => write!(f, "<"),
+            Operator::GreaterThanOrEqual => write!(f, ">="),
+            Operator::LessThanOrEqual => write!(f, "<="),
             Operator::DividedBy => write!(f, "/"),
+            Operator::Modulo => write!(f, "%"),
+            Operator::Power => write!(f, "^"),
+            Operator::NullCoalesce => write!(f, "??"),
         }
     }
 }
@@ -170,8 +400,12 @@ impl Display for Operator {
 mod tests {
 
     use crate::parser::{
-        assign_statement::AssignStatement, ast::Identifier, expressions::expression::Expression,
-        return_statement::ReturnStatement, test_util, ParsedProgram,
+        assign_statement::AssignStatement,
+        ast::{Identifier, Operator},
+        expressions::expression::Expression,
+        lexer::token::{Precedence, Token},
+        return_statement::ReturnStatement,
+        test_util, ParsedProgram,
     };
 
     use super::Statement;
@@ -181,10 +415,12 @@ mod tests {
         let program: ParsedProgram = ParsedProgram::ValidProgram(Vec::from([
             Statement::Assign(AssignStatement {
                 identifier: Identifier(String::from("foo")),
+                indices: Vec::new(),
+                type_annotation: None,
                 assignment: Expression::IdentifierLiteral(Identifier(String::from("bar"))),
             }),
             Statement::Return(ReturnStatement {
-                return_value: test_util::create_identifierliteral("hey"),
+                return_value: Some(test_util::create_identifierliteral("hey")),
             }),
         ]));
 
@@ -194,4 +430,126 @@ return hey
 
         assert_eq!(expected_program, format!("{program}"));
     }
+
+    #[test]
+    fn test_minimal_parentheses_display() {
+        let input_expected: Vec<(&str, &str)> = vec![
+            ("a + b + c", "a + b + c"),
+            ("a + (b + c)", "a + (b + c)"),
+            ("a - (b - c)", "a - (b - c)"),
+            ("(a + b) * c", "(a + b) * c"),
+            ("a + b * c", "a + b * c"),
+            ("2 ^ 2 ^ 3", "2 ^ 2 ^ 3"),
+            ("(2 ^ 2) ^ 3", "(2 ^ 2) ^ 3"),
+            ("-(5 + 5)", "-(5 + 5)"),
+            ("!-a", "!-a"),
+            ("5 > 4 == 3 < 4", "5 > 4 == 3 < 4"),
+            ("(1 < 2) > 3", "(1 < 2) > 3"),
+            ("add(a + b, (c + d) * e)", "add(a + b, (c + d) * e)"),
+            ("(a + b)[1 + 1]", "(a + b)[1 + 1]"),
+        ];
+
+        test_util::assert_list(input_expected, |expected: &&str, input: &&str| {
+            let program = test_util::parse_program(input);
+            assert_eq!(expected, &program.to_string().trim(), "Input '{input}'");
+        });
+    }
+
+    #[test]
+    fn test_operator_token_round_trip() {
+        let operators = [
+            Operator::Bang,
+            Operator::Minus,
+            Operator::Plus,
+            Operator::Multiply,
+            Operator::Equals,
+            Operator::NotEquals,
+            Operator::GreaterThan,
+            Operator::LessThan,
+            Operator::GreaterThanOrEqual,
+            Operator::LessThanOrEqual,
+            Operator::DividedBy,
+            Operator::Modulo,
+            Operator::Power,
+            Operator::NullCoalesce,
+        ];
+
+        for operator in operators {
+            let token = operator.as_token();
+            assert_eq!(Some(operator.clone()), Operator::from_token(&token));
+        }
+        assert_eq!(None, Operator::from_token(&Token::Comma));
+    }
+
+    #[test]
+    fn test_precedence_ordering() {
+        let tightening = [
+            Precedence::Lowest,
+            Precedence::Coalesce,
+            Precedence::Equals,
+            Precedence::LessGreater,
+            Precedence::Sum,
+            Precedence::Product,
+            Precedence::Power,
+            Precedence::Prefix,
+            Precedence::Call,
+            Precedence::Index,
+        ];
+        for pair in tightening.windows(2) {
+            assert!(
+                pair[0] < pair[1],
+                "{:?} should bind looser than {:?}",
+                pair[0],
+                pair[1]
+            );
+            assert_eq!(pair[0], pair[1].looser());
+        }
+        assert_eq!(Precedence::Lowest, Precedence::Lowest.looser());
+
+        let tightening_tokens = [
+            Token::Comma,
+            Token::DoubleQuestion,
+            Token::Equal,
+            Token::LessThan,
+            Token::Add,
+            Token::Asterix,
+            Token::Caret,
+            Token::LParen,
+            Token::LBracket,
+        ];
+        for pair in tightening_tokens.windows(2) {
+            assert!(
+                pair[0].get_precedence() < pair[1].get_precedence(),
+                "{} should bind looser than {}",
+                pair[0],
+                pair[1]
+            );
+        }
+    }
+
+    #[test]
+    fn test_operator_tokens_share_precedence() {
+        let infix_tokens = [
+            Token::Minus,
+            Token::Add,
+            Token::Asterix,
+            Token::Slash,
+            Token::Percent,
+            Token::Equal,
+            Token::NotEqual,
+            Token::GreaterThan,
+            Token::LessThan,
+            Token::GreaterThanEqual,
+            Token::LessThanEqual,
+            Token::Caret,
+            Token::DoubleQuestion,
+        ];
+
+        for token in infix_tokens {
+            let operator = Operator::from_token(&token).expect("Infix token should be an operator");
+            assert_eq!(operator.precedence(), token.get_precedence());
+        }
+        assert_eq!(Precedence::Product, Token::Caret.get_right_precedence());
+        assert_eq!(Precedence::Sum, Token::Add.get_right_precedence());
+    }
 }
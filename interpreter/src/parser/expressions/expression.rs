@@ -1,34 +1,58 @@
+use std::num::ParseIntError;
+
 use tracing::{event, Level};
 
 use crate::parser::{
     ast::{BlockStatement, Identifier, Operator, PrefixOperator, Statement},
-    lexer::token::{HasInfix, Precedence, Token},
+    lexer::{
+        span::Span,
+        token::{HasInfix, Precedence, Token},
+    },
     parse_errors::{ParseError, TokenExpectation},
     Parser,
 };
 
 use super::{
+    expression_statement::ExpressionStatement,
     functions::{CallExpression, FunctionLiteral},
     if_expression::IfExpression,
 };
 
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize),
+    serde(tag = "type", content = "value")
+)]
 pub enum Expression {
     IdentifierLiteral(Identifier),
     IntegerLiteral(i32),
+    FloatLiteral(f64),
     BooleanLiteral(bool),
+    StringLiteral(String),
     Prefix {
         right: Box<Expression>,
         operator: PrefixOperator,
+        span: Span,
     },
     Infix {
         left: Box<Expression>,
         right: Box<Expression>,
         operator: Operator,
+        span: Span,
     },
     If(IfExpression),
     Function(FunctionLiteral),
     Call(CallExpression),
+    ArrayLiteral(Vec<Expression>),
+    TupleLiteral(Vec<Expression>),
+    Index {
+        left: Box<Expression>,
+        index: Box<Expression>,
+        span: Span,
+    },
+    Block(BlockStatement),
+    HashLiteral(Vec<(Expression, Expression)>),
 }
 
 impl Expression {
@@ -36,6 +60,14 @@ impl Expression {
         parser: &mut Parser,
         current_token: Token,
         precedence: Precedence,
+    ) -> Result<Expression, ParseError> {
+        parser.nested(|parser| Self::parse_nested(parser, current_token, precedence))
+    }
+
+    fn parse_nested(
+        parser: &mut Parser,
+        current_token: Token,
+        precedence: Precedence,
     ) -> Result<Expression, ParseError> {
         event!(
             Level::DEBUG,
@@ -44,14 +76,27 @@ impl Expression {
             precedence
         );
 
+        let start = parser.tokens.previous_span().start;
         let mut left = Self::parse_prefix_expression(parser, &current_token)?;
         event!(Level::DEBUG, "Found prefix expression {:?}", left);
 
+        // Comparisons are not associative, so `1 < 2 < 3` has to be written
+        // with explicit parentheses.
+        let mut previous_comparison: Option<Token> = None;
         while parser.tokens.next_token_has_infix()
             && precedence < parser.tokens.next_token_precedence()
         {
             let next_token = parser.tokens.expect()?;
-            left = Self::parse_infix_expression(parser, left, &next_token)?;
+            previous_comparison = match (previous_comparison, &next_token) {
+                (Some(first), second) if second.get_precedence() == Precedence::LessGreater => {
+                    return Err(ParseError::ChainedComparison(first, second.clone()))
+                }
+                (None, comparison) if comparison.get_precedence() == Precedence::LessGreater => {
+                    Some(comparison.clone())
+                }
+                _ => None,
+            };
+            left = Self::parse_infix_expression(parser, left, &next_token, start)?;
         }
 
         event!(Level::DEBUG, "Completed parsing of expression: {:?}", left);
@@ -67,15 +112,27 @@ impl Expression {
             Token::Ident(literal) => Ok(Expression::IdentifierLiteral(Identifier(
                 literal.to_string(),
             ))),
-            Token::Int(integer_literal) => match integer_literal.parse::<i32>() {
+            Token::Int(integer_literal) => match parse_integer_literal(integer_literal) {
                 Ok(parsed_number) => Ok(Expression::IntegerLiteral(parsed_number)),
                 Err(error) => Err(ParseError::ParseIntegerError(token.clone(), error)),
             },
+            Token::Float(float_literal) => match float_literal.parse::<f64>() {
+                Ok(parsed_number) => Ok(Expression::FloatLiteral(parsed_number)),
+                Err(error) => Err(ParseError::ParseFloatError(token.clone(), error)),
+            },
             Token::Bang => Self::create_prefix_expression(parser, PrefixOperator::Bang),
             Token::Minus => Self::create_prefix_expression(parser, PrefixOperator::Minus),
             Token::LParen => Self::create_grouped_expression(parser),
+            Token::LBracket => Self::create_array_literal(parser),
+            Token::LBrace => Self::create_brace_expression(parser),
             Token::If => IfExpression::parse_if_expression(parser),
             Token::Func => FunctionLiteral::parse(parser),
+            Token::Str(string_literal) => Ok(Expression::StringLiteral(string_literal.clone())),
+            Token::InvalidEscape(escape) => Err(ParseError::InvalidEscape(escape.clone())),
+            Token::InvalidDigitSeparator(literal) => {
+                Err(ParseError::InvalidDigitSeparator(literal.clone()))
+            }
+            Token::UnterminatedComment => Err(ParseError::UnterminatedComment),
             Token::True => Ok(Expression::BooleanLiteral(true)),
             Token::False => Ok(Expression::BooleanLiteral(false)),
             unexpected_token => Err(ParseError::NoPrefixExpression(unexpected_token.clone())),
@@ -97,6 +154,7 @@ impl Expression {
         parser: &mut Parser,
         operator: PrefixOperator,
     ) -> Result<Expression, ParseError> {
+        let start = parser.tokens.previous_span().start;
         let token = match parser.tokens.consume() {
             Some(token) => Ok(token),
             None => Err(ParseError::NoPrefixPartner),
@@ -106,17 +164,84 @@ impl Expression {
         Ok(Expression::Prefix {
             right: Box::new(right),
             operator,
+            span: Span::new(start, parser.tokens.previous_span().end),
         })
     }
 
+    /// A parenthesized expression followed by a comma is a tuple, so `(x)` is
+    /// just `x` while `(x, y)` creates a tuple of two elements.
     fn create_grouped_expression(parser: &mut Parser) -> Result<Expression, ParseError> {
+        parser.nested(Self::parse_grouped_expression)
+    }
+
+    fn parse_grouped_expression(parser: &mut Parser) -> Result<Expression, ParseError> {
         let next_token = parser.tokens.expect()?;
-        let grouped_expression = Self::parse(parser, next_token, Precedence::Lowest);
+        let grouped_expression = Self::parse(parser, next_token, Precedence::Lowest)?;
+
+        if parser.tokens.next_token_is(&Token::Comma) {
+            let mut elements = Vec::from([grouped_expression]);
+            elements.extend(Self::parse_expression_list(parser, Token::RParen)?);
+            return Ok(Expression::TupleLiteral(elements));
+        }
+
         parser.tokens.expect_token(Token::RParen)?;
-        grouped_expression
+        Ok(grouped_expression)
+    }
+
+    fn create_index_expression(
+        parser: &mut Parser,
+        left: Expression,
+        start: usize,
+    ) -> Result<Expression, ParseError> {
+        let next_token = parser.tokens.expect()?;
+        let index = Self::parse(parser, next_token, Precedence::Lowest)?;
+        parser.tokens.expect_token(Token::RBracket)?;
+
+        Ok(Expression::Index {
+            left: Box::from(left),
+            index: Box::from(index),
+            span: Span::new(start, parser.tokens.previous_span().end),
+        })
+    }
+
+    fn create_array_literal(parser: &mut Parser) -> Result<Expression, ParseError> {
+        let elements = Self::parse_expression_list(parser, Token::RBracket)?;
+        Ok(Expression::ArrayLiteral(elements))
+    }
+
+    pub fn parse_expression_list(
+        parser: &mut Parser,
+        closing_token: Token,
+    ) -> Result<Vec<Expression>, ParseError> {
+        let mut expressions: Vec<Expression> = Vec::from([]);
+        while let Some(token) = parser.tokens.peek() {
+            match token {
+                token if token == &closing_token => {
+                    parser.tokens.consume();
+                    return Ok(expressions);
+                }
+                Token::Comma => {
+                    parser.tokens.consume();
+                }
+                _ => {
+                    let current_token = parser
+                        .tokens
+                        .consume()
+                        .expect("Expected a token after peeking");
+
+                    expressions.push(Self::parse(parser, current_token, Precedence::Lowest)?);
+                }
+            }
+        }
+
+        Err(ParseError::single_unexpected(&closing_token, None))
     }
 
     pub fn parse_blockstatement(parser: &mut Parser) -> Result<BlockStatement, ParseError> {
+        parser.nested(Self::parse_block_statements)
+    }
+
+    fn parse_block_statements(parser: &mut Parser) -> Result<BlockStatement, ParseError> {
         let mut statements: Vec<Statement> = Vec::new();
         while !parser.tokens.next_token_is(&Token::Lasagna)
             && !parser.tokens.next_token_is(&Token::Else)
@@ -127,10 +252,91 @@ impl Expression {
         Ok(BlockStatement { statements })
     }
 
+    /// Braces hold either a hash literal or a block. They are told apart by
+    /// whether the first expression is followed by a `:`, and `{}` is an
+    /// empty hash.
+    fn create_brace_expression(parser: &mut Parser) -> Result<Expression, ParseError> {
+        match parser.tokens.peek() {
+            Some(Token::RBrace) => {
+                parser.tokens.consume();
+                return Ok(Expression::HashLiteral(Vec::new()));
+            }
+            Some(
+                Token::Let
+                | Token::Const
+                | Token::Return
+                | Token::Import
+                | Token::Func
+                | Token::While
+                | Token::Do
+                | Token::Break
+                | Token::Continue,
+            ) => return Self::create_block_expression(parser, Vec::new()),
+            _ => {}
+        }
+
+        let first_token = parser.tokens.expect()?;
+        let first_expression = Self::parse(parser, first_token, Precedence::Lowest)?;
+        if parser.tokens.next_token_is(&Token::Assign) {
+            return Self::create_hash_literal(parser, first_expression);
+        }
+
+        parser.tokens.expect_optional_token(Token::Period);
+        let first_statement = Statement::Expression(ExpressionStatement {
+            expression: first_expression,
+        });
+        Self::create_block_expression(parser, vec![first_statement])
+    }
+
+    fn create_hash_literal(
+        parser: &mut Parser,
+        first_key: Expression,
+    ) -> Result<Expression, ParseError> {
+        let mut pairs: Vec<(Expression, Expression)> = Vec::new();
+        let mut key = first_key;
+        loop {
+            parser.tokens.expect_token(Token::Assign)?;
+            let value_token = parser.tokens.expect()?;
+            let value = Self::parse(parser, value_token, Precedence::Lowest)?;
+            pairs.push((key, value));
+
+            match parser.tokens.expect()? {
+                Token::RBrace => return Ok(Expression::HashLiteral(pairs)),
+                Token::Comma if parser.tokens.next_token_is(&Token::RBrace) => {
+                    parser.tokens.consume();
+                    return Ok(Expression::HashLiteral(pairs));
+                }
+                Token::Comma => {}
+                unexpected_token => {
+                    return Err(ParseError::multiple_unexpected(
+                        vec![Token::Comma, Token::RBrace],
+                        Some(&unexpected_token),
+                    ))
+                }
+            }
+
+            let key_token = parser.tokens.expect()?;
+            key = Self::parse(parser, key_token, Precedence::Lowest)?;
+        }
+    }
+
+    fn create_block_expression(
+        parser: &mut Parser,
+        mut statements: Vec<Statement>,
+    ) -> Result<Expression, ParseError> {
+        while !parser.tokens.next_token_is(&Token::RBrace) {
+            statements.push(parser.parse_statement()?);
+        }
+        parser.tokens.expect_token(Token::RBrace)?;
+
+        Ok(Expression::Block(BlockStatement { statements }))
+    }
+
     fn parse_infix_expression(
         parser: &mut Parser,
         left: Expression,
         token: &Token,
+        start: usize,
     ) -> Result<Expression, ParseError> {
         event!(
             Level::DEBUG,
@@ -139,7 +345,7 @@ impl Expression {
         );
         match token.has_infix() {
             HasInfix::Arithmic(operator) => {
-                let precedence = token.get_precedence();
+                let precedence = token.get_right_precedence();
                 let next_token = parser.tokens.expect()?;
                 let right = Self::parse(parser, next_token, precedence)?;
 
@@ -147,20 +353,38 @@ impl Expression {
                     left: Box::from(left),
                     right: Box::from(right),
                     operator,
+                    span: Span::new(start, parser.tokens.previous_span().end),
                 })
             }
-            HasInfix::Call() => CallExpression::parse(parser, left),
+            HasInfix::Call() => CallExpression::parse(parser, left, start),
+            HasInfix::Method() => CallExpression::parse_method(parser, left, start),
+            HasInfix::Index() => Self::create_index_expression(parser, left, start),
             HasInfix::No(token) => Err(ParseError::NoInfixExpression(token.clone())),
         }
     }
 }
 
+fn parse_integer_literal(literal: &str) -> Result<i32, ParseIntError> {
+    if let Some(digits) = literal.strip_prefix("0x") {
+        return i32::from_str_radix(digits, 16);
+    }
+    if let Some(digits) = literal.strip_prefix("0b") {
+        return i32::from_str_radix(digits, 2);
+    }
+
+    literal.parse::<i32>()
+}
+
 #[cfg(test)]
 mod tests {
     use crate::parser::{
-        ast::{Identifier, Operator, PrefixOperator, Statement},
+        assign_statement::AssignStatement,
+        ast::{BlockStatement, Identifier, Operator, PrefixOperator, Statement},
         expressions::{expression::Expression, expression_statement::ExpressionStatement},
-        test_util,
+        lexer::span::Span,
+        lexer::token::Token,
+        parse_errors::ParseError,
+        test_util, ParsedProgram,
     };
 
     #[test]
@@ -177,6 +401,33 @@ mod tests {
                 expression: Expression::IntegerLiteral(5)
             })
         ));
+
+        let statements = test_util::expect_parsed_program("0xFF. 0b1010.");
+        assert_eq!(
+            statements,
+            vec![
+                Statement::Expression(ExpressionStatement {
+                    expression: Expression::IntegerLiteral(255)
+                }),
+                Statement::Expression(ExpressionStatement {
+                    expression: Expression::IntegerLiteral(10)
+                }),
+            ]
+        );
+
+        assert!(matches!(
+            test_util::parse_program("0xG."),
+            ParsedProgram::InvalidProgram(parse_errors)
+                if matches!(parse_errors.first().map(|parse_error| &parse_error.error), Some(ParseError::ParseIntegerError(..)))
+        ));
+
+        let statements = test_util::expect_parsed_program("1_000_000.");
+        assert!(matches!(
+            statements.first(),
+            Some(Statement::Expression(ExpressionStatement {
+                expression: Expression::IntegerLiteral(1000000)
+            }))
+        ));
     }
 
     #[test]
@@ -304,6 +555,22 @@ mod tests {
                 "5 < 5".to_string(),
                 test_util::create_infix_test_case(IntegerLiteral(5), IntegerLiteral(5), LessThan),
             ),
+            (
+                "5 <= 5".to_string(),
+                test_util::create_infix_test_case(
+                    IntegerLiteral(5),
+                    IntegerLiteral(5),
+                    LessThanOrEqual,
+                ),
+            ),
+            (
+                "5 >= 5".to_string(),
+                test_util::create_infix_test_case(
+                    IntegerLiteral(5),
+                    IntegerLiteral(5),
+                    GreaterThanOrEqual,
+                ),
+            ),
             (
                 "5 == 5".to_string(),
                 test_util::create_infix_test_case(IntegerLiteral(5), IntegerLiteral(5), Equals),
@@ -348,4 +615,183 @@ mod tests {
 
         test_util::assert_list(test_cases, asserter);
     }
+
+    #[test]
+    fn test_array_literal() {
+        let statements = test_util::expect_parsed_program("[1, 2 * 2, foo]");
+
+        let parsed_statement = statements.first().expect("Should be one statement");
+
+        assert_eq!(
+            parsed_statement,
+            &Statement::Expression(ExpressionStatement {
+                expression: Expression::ArrayLiteral(Vec::from([
+                    Expression::IntegerLiteral(1),
+                    test_util::create_infix_expression(
+                        Expression::IntegerLiteral(2),
+                        Expression::IntegerLiteral(2),
+                        Operator::Multiply,
+                    ),
+                    test_util::create_identifierliteral("foo"),
+                ]))
+            })
+        );
+    }
+
+    #[test]
+    fn test_grouped_and_tuple_expression() {
+        let grouped = test_util::expect_parsed_program("(1)");
+        assert_eq!(
+            grouped.first().expect("Should be one statement"),
+            &Statement::Expression(ExpressionStatement {
+                expression: Expression::IntegerLiteral(1)
+            })
+        );
+
+        let tuple = test_util::expect_parsed_program("(1, 2)");
+        assert_eq!(
+            tuple.first().expect("Should be one statement"),
+            &Statement::Expression(ExpressionStatement {
+                expression: Expression::TupleLiteral(Vec::from([
+                    Expression::IntegerLiteral(1),
+                    Expression::IntegerLiteral(2),
+                ]))
+            })
+        );
+    }
+
+    #[test]
+    fn test_index_expression() {
+        let statements = test_util::expect_parsed_program("foo[1 + 1]");
+
+        assert_eq!(
+            statements.first().expect("Should be one statement"),
+            &Statement::Expression(ExpressionStatement {
+                expression: Expression::Index {
+                    left: Box::from(test_util::create_identifierliteral("foo")),
+                    index: Box::from(test_util::create_infix_expression(
+                        Expression::IntegerLiteral(1),
+                        Expression::IntegerLiteral(1),
+                        Operator::Plus,
+                    )),
+                    span: Span::default(),
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn expressions_track_their_span() {
+        let input = "let a: 1 + foo(2 * 3)[0].";
+        let statements = test_util::expect_parsed_program(input);
+
+        let Some(Statement::Assign(assignment)) = statements.first() else {
+            panic!("Should parse an assignment");
+        };
+        let Expression::Infix { right, span, .. } = &assignment.assignment else {
+            panic!("Should parse an infix expression");
+        };
+        assert_eq!(&input[span.start..span.end], "1 + foo(2 * 3)[0]");
+
+        let Expression::Index { left, span, .. } = right.as_ref() else {
+            panic!("Should parse an index expression");
+        };
+        assert_eq!(&input[span.start..span.end], "foo(2 * 3)[0]");
+
+        let Expression::Call(call) = left.as_ref() else {
+            panic!("Should parse a call expression");
+        };
+        assert_eq!(&input[call.span.start..call.span.end], "foo(2 * 3)");
+    }
+
+    #[test]
+    fn chained_comparison_should_fail_test() {
+        match test_util::parse_program("1 < 2 < 3") {
+            ParsedProgram::InvalidProgram(parse_errors) => assert!(matches!(
+                parse_errors.first().map(|parse_error| &parse_error.error),
+                Some(ParseError::ChainedComparison(
+                    Token::LessThan,
+                    Token::LessThan
+                ))
+            )),
+            ParsedProgram::ValidProgram(_) => panic!("Chained comparison should not parse"),
+        }
+
+        test_util::expect_parsed_program("(1 < 2) == (2 < 3).");
+        test_util::expect_parsed_program("1 < 2 == 2 >= 3.");
+    }
+
+    #[test]
+    fn test_block_expression() {
+        let statements = test_util::expect_parsed_program("let x: { let a: 1. a + 1 }.");
+
+        assert_eq!(
+            statements,
+            vec![Statement::Assign(AssignStatement {
+                identifier: Identifier(String::from("x")),
+                indices: Vec::new(),
+                type_annotation: None,
+                assignment: Expression::Block(BlockStatement {
+                    statements: vec![
+                        Statement::Assign(AssignStatement {
+                            identifier: Identifier(String::from("a")),
+                            indices: Vec::new(),
+                            type_annotation: None,
+                            assignment: Expression::IntegerLiteral(1),
+                        }),
+                        Statement::Expression(ExpressionStatement {
+                            expression: test_util::create_infix_expression(
+                                test_util::create_identifierliteral("a"),
+                                Expression::IntegerLiteral(1),
+                                Operator::Plus,
+                            )
+                        }),
+                    ]
+                }),
+            })]
+        );
+
+        assert!(test_util::has_parser_errors(&test_util::parse_program(
+            "let x: { let a: 1. a"
+        )));
+    }
+
+    #[test]
+    fn test_hash_literal() {
+        let statements = test_util::expect_parsed_program("{\"a\": 1, 2: b, true: 1 + 1}");
+
+        assert_eq!(
+            statements,
+            vec![Statement::Expression(ExpressionStatement {
+                expression: Expression::HashLiteral(vec![
+                    (
+                        Expression::StringLiteral(String::from("a")),
+                        Expression::IntegerLiteral(1)
+                    ),
+                    (
+                        Expression::IntegerLiteral(2),
+                        test_util::create_identifierliteral("b")
+                    ),
+                    (
+                        Expression::BooleanLiteral(true),
+                        test_util::create_infix_expression(
+                            Expression::IntegerLiteral(1),
+                            Expression::IntegerLiteral(1),
+                            Operator::Plus,
+                        )
+                    ),
+                ])
+            })]
+        );
+
+        assert_eq!(
+            test_util::expect_parsed_program("{}"),
+            vec![Statement::Expression(ExpressionStatement {
+                expression: Expression::HashLiteral(Vec::new())
+            })]
+        );
+        assert!(test_util::has_parser_errors(&test_util::parse_program(
+            "{\"a\": 1 \"b\": 2}"
+        )));
+    }
 }
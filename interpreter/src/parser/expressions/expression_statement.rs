@@ -12,19 +12,27 @@ use crate::parser::{
 use super::expression::Expression;
 
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct ExpressionStatement {
     pub expression: Expression,
 }
 
 impl Display for ExpressionStatement {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self.expression)
+        self.expression.fmt(f)
     }
 }
 
 impl ExpressionStatement {
     pub fn parse(parser: &mut Parser) -> Result<Statement, ParseError> {
         let first_token = parser.tokens.expect()?;
+        Self::parse_from_token(parser, first_token)
+    }
+
+    pub fn parse_from_token(
+        parser: &mut Parser,
+        first_token: Token,
+    ) -> Result<Statement, ParseError> {
         event!(
             Level::DEBUG,
             "Parsing expression statement with starting token {first_token:?}"
@@ -2,71 +2,174 @@ use std::fmt::Display;
 
 use tracing::{event, span, Level};
 
-use crate::{
-    eval::objects::FunctionListable,
-    parser::{
-        ast::{BlockStatement, Identifier},
-        lexer::token::{Precedence, Token},
-        parse_errors::{ParseError, TokenExpectation},
-        Parser,
+use crate::parser::{
+    assign_statement::AssignStatement,
+    ast::{BlockStatement, Identifier, Statement},
+    lexer::{
+        span::Span,
+        token::{Precedence, Token},
     },
+    parse_errors::{ParseError, TokenExpectation},
+    Parser,
 };
 
-use super::expression::Expression;
+use super::{expression::Expression, expression_statement::ExpressionStatement};
+
+type Parameters = (Vec<Identifier>, Vec<Expression>, Option<Identifier>);
 
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct FunctionLiteral {
     pub parameters: Vec<Identifier>,
+    /// Defaults for the last parameters, in order.
+    pub defaults: Vec<Expression>,
+    pub rest_parameter: Option<Identifier>,
     pub body: BlockStatement,
 }
 
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct CallExpression {
     pub function: Box<Expression>,
     pub arguments: Vec<Expression>,
+    pub span: Span,
 }
 
 impl Display for FunctionLiteral {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
-            "fn({}): {}",
-            self.parameters.to_function_string(),
-            self.body
-        )
+            "fn({}): ",
+            format_parameters(&self.parameters, &self.defaults, &self.rest_parameter)
+        )?;
+        self.body.fmt(f)
     }
 }
 
+pub fn format_parameters(
+    parameters: &[Identifier],
+    defaults: &[Expression],
+    rest_parameter: &Option<Identifier>,
+) -> String {
+    let first_default = parameters.len() - defaults.len();
+    let mut formatted: Vec<String> = parameters[..first_default]
+        .iter()
+        .map(Identifier::to_string)
+        .collect();
+    formatted.extend(
+        parameters[first_default..]
+            .iter()
+            .zip(defaults)
+            .map(|(parameter, default)| format!("{parameter}: {default}")),
+    );
+    if let Some(rest_parameter) = rest_parameter {
+        formatted.push(format!("{rest_parameter}..."));
+    }
+
+    formatted.join(", ")
+}
+
 impl FunctionLiteral {
+    /// `fn add(x, y): x + y~` is sugar for `let add: fn(x, y): x + y~`, while a
+    /// statement starting with an anonymous function is an expression statement.
+    pub fn parse_statement(parser: &mut Parser) -> Result<Statement, ParseError> {
+        let function_token = parser.tokens.expect_token(Token::Func)?;
+        if !matches!(parser.tokens.peek(), Some(Token::Ident(_))) {
+            return ExpressionStatement::parse_from_token(parser, function_token);
+        }
+
+        let identifier = parser.tokens.expected_identifier()?;
+        let function = Self::parse(parser)?;
+        parser.tokens.expect_optional_token(Token::Period);
+
+        Ok(Statement::Assign(AssignStatement {
+            identifier,
+            indices: Vec::new(),
+            type_annotation: None,
+            assignment: function,
+        }))
+    }
+
     pub fn parse(parser: &mut Parser) -> Result<Expression, ParseError> {
         let function_span = span!(Level::DEBUG, "Function");
         let _enter = function_span.enter();
 
         event!(Level::DEBUG, "Parsing function");
-        let parameters: Vec<Identifier> = Self::parse_function_parameters(parser)?;
+        let (parameters, defaults, rest_parameter) = Self::parse_function_parameters(parser)?;
         event!(Level::DEBUG, "Found parameters {parameters:?}");
 
-        parser.tokens.expect_token(Token::Assign)?;
+        let body = Self::parse_body(parser)?;
 
-        let body: BlockStatement = Expression::parse_blockstatement(parser)?;
-        parser.tokens.expect_token(Token::Lasagna)?;
+        Ok(Expression::Function(FunctionLiteral {
+            parameters,
+            defaults,
+            rest_parameter,
+            body,
+        }))
+    }
 
-        Ok(Expression::Function(FunctionLiteral { parameters, body }))
+    /// A body is either a block ending with `~`, or after an `=>` a single
+    /// expression, which is parsed as a block holding only that expression.
+    fn parse_body(parser: &mut Parser) -> Result<BlockStatement, ParseError> {
+        match parser.tokens.expect()? {
+            Token::Assign => {
+                let body = Expression::parse_blockstatement(parser)?;
+                parser.tokens.expect_token(Token::Lasagna)?;
+                Ok(body)
+            }
+            Token::Arrow => {
+                let next_token = parser.tokens.expect()?;
+                let expression = Expression::parse(parser, next_token, Precedence::Lowest)?;
+                Ok(BlockStatement {
+                    statements: vec![Statement::Expression(ExpressionStatement { expression })],
+                })
+            }
+            unexpected_token => Err(ParseError::multiple_unexpected(
+                Vec::from([Token::Assign, Token::Arrow]),
+                Some(&unexpected_token),
+            )),
+        }
     }
 
-    fn parse_function_parameters(parser: &mut Parser) -> Result<Vec<Identifier>, ParseError> {
+    /// A parameter followed by `: expr` has a default value, and every
+    /// parameter after it needs one too. A parameter followed by `...`
+    /// collects the remaining arguments, and has to be the last parameter.
+    fn parse_function_parameters(parser: &mut Parser) -> Result<Parameters, ParseError> {
         let mut parameters: Vec<Identifier> = Vec::from([]);
+        let mut defaults: Vec<Expression> = Vec::new();
         while let Some(token) = parser.tokens.consume() {
             match token {
                 Token::LParen | Token::Comma => match parser.tokens.peek() {
                     Some(Token::RParen) => {
                         parser.tokens.consume();
-                        return Ok(parameters);
+                        return Ok((parameters, defaults, None));
+                    }
+                    Some(_) => {
+                        let parameter = Expression::parse_literal(parser)?;
+                        if parser.tokens.next_token_is(&Token::Period) {
+                            for _ in 0..3 {
+                                parser.tokens.expect_token(Token::Period)?;
+                            }
+                            parser.tokens.expect_token(Token::RParen)?;
+                            return Ok((parameters, defaults, Some(parameter)));
+                        }
+
+                        if parser.tokens.next_token_is(&Token::Assign) {
+                            parser.tokens.consume();
+                            let default_token = parser.tokens.expect()?;
+                            defaults.push(Expression::parse(
+                                parser,
+                                default_token,
+                                Precedence::Lowest,
+                            )?);
+                        } else if !defaults.is_empty() {
+                            return Err(ParseError::RequiredParameterAfterDefault(parameter));
+                        }
+                        parameters.push(parameter);
                     }
-                    Some(_) => parameters.push(Expression::parse_literal(parser)?),
                     None => return Err(ParseError::ExpectedToken),
                 },
-                Token::RParen => return Ok(parameters),
+                Token::RParen => return Ok((parameters, defaults, None)),
                 unexpected_token => {
                     return Err(ParseError::UnexpectedToken {
                         expected_token: TokenExpectation::MultipleExpectation(Vec::from([
@@ -79,61 +182,68 @@ impl FunctionLiteral {
             }
         }
 
-        Ok(parameters)
+        Ok((parameters, defaults, None))
     }
 }
 
 impl CallExpression {
-    pub fn parse(parser: &mut Parser, function: Expression) -> Result<Expression, ParseError> {
+    pub fn parse(
+        parser: &mut Parser,
+        function: Expression,
+        start: usize,
+    ) -> Result<Expression, ParseError> {
         let call_span = span!(Level::DEBUG, "Call");
         let _enter = call_span.enter();
 
+        let arguments = Self::parse_function_arguments(parser)?;
         Ok(Expression::Call(CallExpression {
             function: Box::from(function),
-            arguments: Self::parse_function_arguments(parser)?,
+            arguments,
+            span: Span::new(start, parser.tokens.previous_span().end),
         }))
     }
 
-    fn parse_function_arguments(parser: &mut Parser) -> Result<Vec<Expression>, ParseError> {
-        event!(Level::DEBUG, "Parsing function arguments");
-        let mut parameters: Vec<Expression> = Vec::from([]);
-        while let Some(token) = parser.tokens.peek() {
-            match token {
-                Token::RParen => {
-                    parser.tokens.consume();
-                    return Ok(parameters);
-                }
-                Token::Comma => {
-                    parser.tokens.consume();
-                }
-                _ => {
-                    let current_token = parser
-                        .tokens
-                        .consume()
-                        .expect("Expected a token after peeking");
-
-                    parameters.push(Expression::parse(
-                        parser,
-                        current_token,
-                        Precedence::Lowest,
-                    )?);
-                }
-            }
+    /// `receiver.method(args)` is sugar for `method(receiver, args)`, and the
+    /// parentheses can be left out when there are no other arguments. They
+    /// can not be left out at the end of a statement, since `x.y.` is lexed
+    /// as the two statements `x` and `y`.
+    pub fn parse_method(
+        parser: &mut Parser,
+        receiver: Expression,
+        start: usize,
+    ) -> Result<Expression, ParseError> {
+        let method = parser.tokens.expected_identifier()?;
+
+        let mut arguments = vec![receiver];
+        if parser.tokens.next_token_is(&Token::LParen) {
+            parser.tokens.consume();
+            arguments.extend(Self::parse_function_arguments(parser)?);
         }
 
-        Ok(parameters)
+        Ok(Expression::Call(CallExpression {
+            function: Box::from(Expression::IdentifierLiteral(method)),
+            arguments,
+            span: Span::new(start, parser.tokens.previous_span().end),
+        }))
+    }
+
+    fn parse_function_arguments(parser: &mut Parser) -> Result<Vec<Expression>, ParseError> {
+        event!(Level::DEBUG, "Parsing function arguments");
+        Expression::parse_expression_list(parser, Token::RParen)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use crate::parser::{
-        ast::{BlockStatement, Operator, Statement},
+        ast::{BlockStatement, Identifier, Operator, Statement},
         expressions::{
             expression::Expression, expression_statement::ExpressionStatement,
             functions::CallExpression,
         },
-        test_util,
+        lexer::span::Span,
+        parse_errors::ParseError,
+        test_util, ParsedProgram,
     };
 
     #[test]
@@ -166,6 +276,7 @@ mod tests {
         let expected_statement = Expression::Call(CallExpression {
             function: Box::from(test_util::create_identifierliteral("add")),
             arguments: expected_arguments,
+            span: Span::default(),
         });
         assert_eq!(
             statement,
@@ -230,4 +341,143 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn test_named_function_definition() {
+        let sugared = test_util::expect_parsed_program("fn add(x, y): x + y~");
+        let explicit = test_util::expect_parsed_program("let add: fn(x, y): x + y~.");
+
+        assert_eq!(sugared, explicit);
+        assert!(matches!(sugared.first(), Some(Statement::Assign(_))));
+
+        let anonymous = test_util::expect_parsed_program("fn(x): x~(1)");
+        assert!(matches!(
+            anonymous.first(),
+            Some(Statement::Expression(ExpressionStatement {
+                expression: Expression::Call(_)
+            }))
+        ));
+    }
+
+    #[test]
+    fn test_arrow_function_body() {
+        assert_eq!(
+            test_util::expect_parsed_program("let inc: fn(x) => x + 1."),
+            test_util::expect_parsed_program("let inc: fn(x): x + 1~.")
+        );
+        assert_eq!(
+            test_util::expect_parsed_program("fn add(x, y) => x + y"),
+            test_util::expect_parsed_program("fn add(x, y): x + y~")
+        );
+        assert_eq!(
+            test_util::expect_parsed_program("map(fn(x) => x * 2, [1, 2])"),
+            test_util::expect_parsed_program("map(fn(x): x * 2~, [1, 2])")
+        );
+        assert_eq!(
+            test_util::expect_parsed_program("fn(x) => fn(y) => x + y"),
+            test_util::expect_parsed_program("fn(x): fn(y): x + y~~")
+        );
+
+        assert!(test_util::has_parser_errors(&test_util::parse_program(
+            "fn(x) x + 1"
+        )));
+    }
+
+    #[test]
+    fn test_rest_parameter() {
+        let statements = test_util::expect_parsed_program("fn(first, rest...): first~");
+
+        let Some(Statement::Expression(ExpressionStatement {
+            expression: Expression::Function(function),
+        })) = statements.first()
+        else {
+            panic!("Should parse a function literal");
+        };
+        assert_eq!(function.parameters, vec![Identifier(String::from("first"))]);
+        assert_eq!(
+            function.rest_parameter,
+            Some(Identifier(String::from("rest")))
+        );
+        assert_eq!(function.to_string(), "fn(first, rest...): first");
+
+        assert!(test_util::has_parser_errors(&test_util::parse_program(
+            "fn(rest..., last): last~"
+        )));
+        assert!(test_util::has_parser_errors(&test_util::parse_program(
+            "fn(rest..): rest~"
+        )));
+    }
+
+    #[test]
+    fn test_default_parameters() {
+        let statements = test_util::expect_parsed_program("fn(x, y: 10, z: x + 1): x~");
+
+        let Some(Statement::Expression(ExpressionStatement {
+            expression: Expression::Function(function),
+        })) = statements.first()
+        else {
+            panic!("Should parse a function literal");
+        };
+        assert_eq!(function.parameters.len(), 3);
+        assert_eq!(
+            function.defaults,
+            vec![
+                Expression::IntegerLiteral(10),
+                test_util::create_infix_expression(
+                    test_util::create_identifierliteral("x"),
+                    Expression::IntegerLiteral(1),
+                    Operator::Plus,
+                )
+            ]
+        );
+        assert_eq!(function.to_string(), "fn(x, y: 10, z: x + 1): x");
+
+        match test_util::parse_program("fn(x: 1, y): y~") {
+            ParsedProgram::InvalidProgram(parse_errors) => assert!(matches!(
+                parse_errors.first().map(|parse_error| &parse_error.error),
+                Some(ParseError::RequiredParameterAfterDefault(_))
+            )),
+            ParsedProgram::ValidProgram(_) => {
+                panic!("Required parameter after a default should not parse")
+            }
+        }
+    }
+
+    #[test]
+    fn test_method_call() {
+        let method = test_util::expect_parsed_program("arr.len");
+        assert_eq!(
+            method,
+            vec![Statement::Expression(ExpressionStatement {
+                expression: Expression::Call(CallExpression {
+                    function: Box::from(test_util::create_identifierliteral("len")),
+                    arguments: vec![test_util::create_identifierliteral("arr")],
+                    span: Span::default(),
+                })
+            })]
+        );
+
+        assert_eq!(
+            test_util::expect_parsed_program("arr.push(1).len"),
+            test_util::expect_parsed_program("len(push(arr, 1))")
+        );
+
+        assert_eq!(
+            test_util::expect_parsed_program("let a: 1. let b: a.let c: 2. c"),
+            test_util::expect_parsed_program("let a: 1. let b: a. let c: 2. c")
+        );
+
+        let statements = test_util::expect_parsed_program("arr. len");
+        assert_eq!(
+            statements,
+            vec![
+                Statement::Expression(ExpressionStatement {
+                    expression: test_util::create_identifierliteral("arr")
+                }),
+                Statement::Expression(ExpressionStatement {
+                    expression: test_util::create_identifierliteral("len")
+                }),
+            ]
+        );
+    }
 }
@@ -8,6 +8,7 @@ use crate::parser::{
 use super::expression::Expression;
 
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct IfExpression {
     pub condition: Box<Expression>,
     pub consequence: BlockStatement,
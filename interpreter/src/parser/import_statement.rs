@@ -0,0 +1,68 @@
+use std::fmt::Display;
+
+use super::{ast::Statement, lexer::token::Token, parse_errors::ParseError, Parser};
+
+#[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ImportStatement {
+    pub path: String,
+}
+
+impl ImportStatement {
+    pub fn parse(parser: &mut Parser) -> Result<Statement, ParseError> {
+        parser.tokens.expect_token(Token::Import)?;
+
+        let path = match parser.tokens.expect()? {
+            Token::Str(path) => path,
+            unexpected_token => {
+                return Err(ParseError::single_unexpected(
+                    &Token::Str(String::from("")),
+                    Some(&unexpected_token),
+                ))
+            }
+        };
+
+        parser.tokens.expect_optional_token(Token::Period);
+
+        Ok(Statement::Import(ImportStatement { path }))
+    }
+}
+
+impl Display for ImportStatement {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "import \"{}\".", self.path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::{
+        ast::Statement, import_statement::ImportStatement, parse_errors::ParseError, test_util,
+        ParsedProgram,
+    };
+
+    #[test]
+    fn parse_import_statement_test() {
+        let statements = test_util::expect_parsed_program("import \"math.lasagna\".");
+
+        assert_eq!(
+            statements,
+            vec![Statement::Import(ImportStatement {
+                path: String::from("math.lasagna")
+            })]
+        );
+    }
+
+    #[test]
+    fn import_without_path_should_fail_test() {
+        match test_util::parse_program("import math.") {
+            ParsedProgram::InvalidProgram(parse_errors) => {
+                assert!(matches!(
+                    parse_errors.first().map(|parse_error| &parse_error.error),
+                    Some(ParseError::UnexpectedToken { .. })
+                ))
+            }
+            ParsedProgram::ValidProgram(_) => panic!("Import of identifier should not parse"),
+        }
+    }
+}
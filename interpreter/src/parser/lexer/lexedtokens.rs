@@ -1,62 +1,233 @@
-use std::{iter::Peekable, str::Chars, vec::IntoIter};
+use std::{iter::Peekable, str::CharIndices};
 
 use crate::{parser::ast::Identifier, parser::parse_errors::ParseError};
 
-use super::token::{HasInfix, ParsedMultipartToken, ParsedToken, Precedence, Token};
-
-#[derive(Debug)]
-pub struct LexedTokens {
-    token_iter: Peekable<IntoIter<Token>>,
+use super::{
+    span::Span,
+    token::{HasInfix, ParsedMultipartToken, ParsedToken, Precedence, Token},
+    trivia::{Trivia, TriviaMode, TriviaToken},
+};
+
+/// Lexes source code one token at a time, so tokens are only produced as
+/// they are asked for instead of all up front.
+#[derive(Debug, Default)]
+pub struct Tokenizer {
+    source: String,
+    offset: usize,
+    /// The end of the previous token, if it can be followed by a method access.
+    expression_end: Option<usize>,
+    trivia_mode: TriviaMode,
 }
 
-impl From<&str> for LexedTokens {
-    fn from(source_code: &str) -> Self {
-        let mut code_iter = source_code.chars().peekable();
+impl Tokenizer {
+    pub fn new(source_code: &str, trivia_mode: TriviaMode) -> Tokenizer {
+        let mut tokenizer = Tokenizer {
+            trivia_mode,
+            ..Tokenizer::default()
+        };
+        tokenizer.reset(source_code);
+        tokenizer
+    }
 
-        let mut tokens: Vec<Token> = Vec::new();
-        while let Some(current_char) = code_iter.next() {
-            if current_char.is_whitespace() {
-                continue;
-            }
+    /// Starts over on new source code, reusing the buffer that held the
+    /// previous source.
+    pub fn reset(&mut self, source_code: &str) {
+        self.source.clear();
+        self.source.push_str(source_code);
+        self.offset = 0;
+        self.expression_end = None;
+    }
+
+    /// Lexes the next token together with its span in the source code.
+    pub fn next_spanned(&mut self) -> Option<(Token, Span)> {
+        self.read_trivia(false);
+        let offset = self.offset;
+        let source_code = &self.source[offset..];
+        let mut code_iter = source_code.char_indices().peekable();
+
+        let (start, current_char) = code_iter.next()?;
+        if source_code.starts_with("/*") {
+            self.offset = self.source.len();
+            self.expression_end = None;
+            let span = Span::new(offset, self.source.len());
+            return Some((Token::UnterminatedComment, span));
+        }
 
-            let lexed_token: Token = match Token::from(current_char) {
-                ParsedToken::CompleteToken(token) => token,
-                ParsedToken::PossibleMultipart(first_part) => {
-                    match Token::lex_second_part(first_part, code_iter.peek().cloned()) {
-                        ParsedMultipartToken::Multipart(token) => {
-                            code_iter.next();
-                            token
-                        }
-                        ParsedMultipartToken::OnlyOnePart(token) => token,
+        let lexed_token: Token = match Token::from(current_char) {
+            ParsedToken::CompleteToken(token) => token,
+            ParsedToken::PossibleMultipart(first_part) => {
+                let second_part = code_iter.peek().map(|(_, char)| *char);
+                match Token::lex_second_part(first_part, second_part) {
+                    ParsedMultipartToken::Multipart(token) => {
+                        code_iter.next();
+                        token
                     }
+                    ParsedMultipartToken::OnlyOnePart(token) => token,
                 }
-                ParsedToken::AlphabeticStart => {
-                    let literal: String = read_literal(&mut code_iter, current_char, |char| {
-                        char.is_alphabetic() && char != &','
-                    });
+            }
+            ParsedToken::AlphabeticStart => {
+                let literal: String = read_literal(&mut code_iter, current_char, |char| {
+                    char.is_alphabetic() && char != &','
+                });
 
-                    Token::parse_keyword(&literal)
-                }
-                ParsedToken::NumericStart => {
-                    let literal: String =
-                        read_literal(&mut code_iter, current_char, |char| char.is_numeric());
+                Token::parse_keyword(&literal)
+            }
+            ParsedToken::NumericStart => read_number(&mut code_iter, current_char),
+            ParsedToken::StringStart => read_string(&mut code_iter),
+        };
+
+        // A `.` is a method access rather than the end of a statement when it
+        // is squeezed between an expression and an identifier, as in `arr.len`.
+        let lexed_token = match lexed_token {
+            Token::Period
+                if self.expression_end == Some(offset + start)
+                    && is_method_access(&source_code[start + 1..]) =>
+            {
+                Token::MethodAccess
+            }
+            token => token,
+        };
+
+        let end = code_iter
+            .peek()
+            .map_or(source_code.len(), |(position, _)| *position);
+        let span = Span::new(offset + start, offset + end);
+
+        self.offset = span.end;
+        self.expression_end = matches!(
+            lexed_token,
+            Token::Ident(_) | Token::Str(_) | Token::RParen | Token::RBracket
+        )
+        .then_some(span.end);
+
+        Some((lexed_token, span))
+    }
+
+    /// Lexes the next token together with the trivia around it. Trivia after
+    /// a token on the same line trails it, while other trivia leads the token
+    /// after it. Trivia at the end of the source trails the last token.
+    pub fn next_with_trivia(&mut self) -> Option<TriviaToken> {
+        let leading = self.read_trivia(false);
+        let (token, span) = self.next_spanned()?;
+        let mut trailing = self.read_trivia(true);
+
+        let line_end = self.offset;
+        let remaining = self.read_trivia(false);
+        match self.offset == self.source.len() {
+            true => trailing.extend(remaining),
+            false => self.offset = line_end,
+        }
 
-                    Token::Int(literal)
+        Some(TriviaToken {
+            token,
+            span,
+            leading,
+            trailing,
+        })
+    }
+
+    /// Reads past the whitespace and comments ahead, which are only returned
+    /// when trivia is preserved. An unterminated comment is left unread, so
+    /// that it is lexed as a token.
+    fn read_trivia(&mut self, until_newline: bool) -> Vec<Trivia> {
+        let mut trivia = Vec::new();
+        loop {
+            let rest = &self.source[self.offset..];
+            let mut rest_iter = rest.char_indices().peekable();
+            let (length, is_comment) = match rest_iter.next() {
+                Some((_, '\n')) if until_newline => break,
+                Some((_, char)) if char.is_whitespace() => {
+                    let length = rest
+                        .find(|char: char| !char.is_whitespace() || (until_newline && char == '\n'))
+                        .unwrap_or(rest.len());
+                    (length, false)
+                }
+                Some((_, '/')) if rest_iter.next_if(|(_, char)| char == &'*').is_some() => {
+                    if !skip_block_comment(&mut rest_iter) {
+                        break;
+                    }
+                    let length = rest_iter
+                        .peek()
+                        .map_or(rest.len(), |(position, _)| *position);
+                    (length, true)
                 }
+                _ => break,
             };
 
-            tokens.push(lexed_token);
+            if self.trivia_mode == TriviaMode::Preserve {
+                let text = self.source[self.offset..self.offset + length].to_string();
+                trivia.push(match is_comment {
+                    true => Trivia::Comment(text),
+                    false => Trivia::Whitespace(text),
+                });
+            }
+            self.offset += length;
         }
 
+        trivia
+    }
+}
+
+/// Whether the source after a squeezed `.` makes it a method access. It does
+/// when an identifier follows, unless the identifier ends the statement, as
+/// `y` does in `x.y.`. A keyword following the `.` starts a new statement.
+fn is_method_access(after_period: &str) -> bool {
+    let word_end = after_period
+        .find(|char: char| !char.is_alphabetic())
+        .unwrap_or(after_period.len());
+    let (word, after_word) = after_period.split_at(word_end);
+    if word.is_empty() || !matches!(Token::parse_keyword(word), Token::Ident(_)) {
+        return false;
+    }
+
+    match after_word.strip_prefix('.') {
+        Some(after_period) => is_method_access(after_period),
+        None => true,
+    }
+}
+
+impl Iterator for Tokenizer {
+    type Item = Token;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_spanned().map(|(token, _)| token)
+    }
+}
+
+/// The tokens of a program, lexed lazily as the parser reads them.
+#[derive(Debug)]
+pub struct LexedTokens {
+    tokenizer: Tokenizer,
+    peeked: Option<(Token, Span)>,
+    previous_span: Span,
+}
+
+impl From<&str> for LexedTokens {
+    fn from(source_code: &str) -> Self {
         LexedTokens {
-            token_iter: tokens.into_iter().peekable(),
+            tokenizer: Tokenizer::new(source_code, TriviaMode::Discard),
+            peeked: None,
+            previous_span: Span::default(),
         }
     }
 }
 
 impl LexedTokens {
+    /// Replaces the tokens with the tokens of the given source code, reusing
+    /// the buffer that held the previous source code.
+    pub fn relex(&mut self, source_code: &str) {
+        self.tokenizer.reset(source_code);
+        self.peeked = None;
+        self.previous_span = Span::default();
+    }
+
     pub fn consume(&mut self) -> Option<Token> {
-        self.token_iter.next()
+        let (token, span) = match self.peeked.take() {
+            Some(peeked) => peeked,
+            None => self.tokenizer.next_spanned()?,
+        };
+        self.previous_span = span;
+        Some(token)
     }
 
     pub fn expect(&mut self) -> Result<Token, ParseError> {
@@ -67,25 +238,39 @@ impl LexedTokens {
     }
 
     pub fn peek(&mut self) -> Option<&Token> {
-        self.token_iter.peek()
+        if self.peeked.is_none() {
+            self.peeked = self.tokenizer.next_spanned();
+        }
+        self.peeked.as_ref().map(|(token, _)| token)
+    }
+
+    /// The span of the next token, if there is one.
+    pub fn peek_span(&mut self) -> Option<Span> {
+        self.peek()?;
+        self.peeked.as_ref().map(|(_, span)| *span)
+    }
+
+    /// The span of the most recently consumed token.
+    pub fn previous_span(&self) -> Span {
+        self.previous_span
     }
 
     pub fn next_token_has_infix(&mut self) -> bool {
-        match self.token_iter.peek() {
+        match self.peek() {
             Some(token) => !matches!(token.has_infix(), HasInfix::No(_)),
             None => false,
         }
     }
 
     pub fn next_token_is(&mut self, is_token: &Token) -> bool {
-        match self.token_iter.peek() {
+        match self.peek() {
             Some(token) => is_token == token,
             None => false,
         }
     }
 
     pub fn iterate_to_next_statement(&mut self) {
-        for token in self.token_iter.by_ref() {
+        while let Some(token) = self.consume() {
             if token == Token::Period {
                 break;
             }
@@ -93,21 +278,24 @@ impl LexedTokens {
     }
 
     pub fn expect_token(&mut self, expected_token_type: Token) -> Result<Token, ParseError> {
-        match self.token_iter.next_if_eq(&expected_token_type) {
-            Some(token) => Ok(token),
-            None => Err(ParseError::single_unexpected(
-                &expected_token_type,
-                self.token_iter.peek(),
-            )),
+        if self.next_token_is(&expected_token_type) {
+            return self.expect();
         }
+
+        Err(ParseError::single_unexpected(
+            &expected_token_type,
+            self.peek(),
+        ))
     }
 
     pub fn expect_optional_token(&mut self, expected_token_type: Token) {
-        self.token_iter.next_if_eq(&expected_token_type);
+        if self.next_token_is(&expected_token_type) {
+            self.consume();
+        }
     }
 
     pub fn expected_identifier(&mut self) -> Result<Identifier, ParseError> {
-        match self.token_iter.peek() {
+        match self.peek() {
             Some(peeked_token) => {
                 let parsed_identifier = Identifier::parse_from_token(peeked_token)?;
                 self.consume();
@@ -125,13 +313,13 @@ impl LexedTokens {
     }
 }
 
-fn read_literal<F>(iterator: &mut Peekable<Chars>, first_char: char, read_until: F) -> String
+fn read_literal<F>(iterator: &mut Peekable<CharIndices>, first_char: char, read_until: F) -> String
 where
     F: Fn(&char) -> bool,
 {
     let mut literal = String::from(first_char);
 
-    while let Some(c) = iterator.peek().cloned().filter(|c| read_until(c)) {
+    while let Some((_, c)) = iterator.peek().cloned().filter(|(_, c)| read_until(c)) {
         literal.push(c);
         iterator.next();
     }
@@ -139,9 +327,143 @@ where
     literal
 }
 
+/// A period only continues a number when it is directly followed by a digit,
+/// otherwise it is left to terminate the statement.
+fn read_number(iterator: &mut Peekable<CharIndices>, first_char: char) -> Token {
+    if let Some(prefix) = read_radix_prefix(iterator, first_char) {
+        let digits = read_literal(iterator, prefix, |char| char.is_alphanumeric());
+        return Token::Int(format!("{first_char}{digits}"));
+    }
+
+    let literal: String = read_literal(iterator, first_char, |char| {
+        char.is_numeric() || char == &'_'
+    });
+    let Some(mut literal) = strip_digit_separators(&literal) else {
+        return Token::InvalidDigitSeparator(literal);
+    };
+
+    let mut lookahead = iterator.clone();
+    let is_decimal = lookahead.next().map(|(_, char)| char) == Some('.')
+        && lookahead.peek().is_some_and(|(_, char)| char.is_numeric());
+
+    if !is_decimal {
+        return Token::Int(literal);
+    }
+
+    iterator.next();
+    let (_, first_decimal) = iterator.next().expect("Already peeked a decimal");
+    let decimals = read_literal(iterator, first_decimal, |char| char.is_numeric());
+    literal.push('.');
+    literal.push_str(&decimals);
+
+    Token::Float(literal)
+}
+
+/// Hexadecimal and binary literals start with `0x` and `0b`. The digits are
+/// validated when the literal is parsed.
+fn read_radix_prefix(iterator: &mut Peekable<CharIndices>, first_char: char) -> Option<char> {
+    if first_char != '0' {
+        return None;
+    }
+
+    iterator
+        .next_if(|(_, char)| char == &'x' || char == &'b')
+        .map(|(_, prefix)| prefix)
+}
+
+/// Underscores may separate digits, as in `1_000_000`, but can not be
+/// trailing or follow another underscore.
+fn strip_digit_separators(literal: &str) -> Option<String> {
+    if literal.ends_with('_') || literal.contains("__") {
+        return None;
+    }
+
+    Some(literal.replace('_', ""))
+}
+
+/// A `\u{...}` escape is decoded into the codepoint it names. The rest of an
+/// invalid string is still read, so lexing continues after its closing quote.
+fn read_string(iterator: &mut Peekable<CharIndices>) -> Token {
+    let mut literal = String::new();
+    let mut invalid_escape: Option<String> = None;
+
+    while let Some((_, c)) = iterator.next() {
+        match c {
+            '"' => {
+                return match invalid_escape {
+                    Some(escape) => Token::InvalidEscape(escape),
+                    None => Token::Str(literal),
+                }
+            }
+            '\\' if iterator.next_if(|(_, char)| char == &'u').is_some() => {
+                match read_unicode_escape(iterator) {
+                    Ok(decoded) => literal.push(decoded),
+                    Err(escape) => {
+                        invalid_escape.get_or_insert(escape);
+                    }
+                }
+            }
+            c => literal.push(c),
+        }
+    }
+
+    Token::Illegal
+}
+
+/// Skips a block comment whose opening `/*` has been read, including any
+/// comments nested inside it. Returns whether the comment was closed.
+fn skip_block_comment(iterator: &mut Peekable<CharIndices>) -> bool {
+    let mut depth = 1;
+    while let Some((_, c)) = iterator.next() {
+        match c {
+            '/' if iterator.next_if(|(_, char)| char == &'*').is_some() => depth += 1,
+            '*' if iterator.next_if(|(_, char)| char == &'/').is_some() => {
+                depth -= 1;
+                if depth == 0 {
+                    return true;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    false
+}
+
+fn read_unicode_escape(iterator: &mut Peekable<CharIndices>) -> Result<char, String> {
+    let mut escape = String::from("\\u");
+    if iterator.next_if(|(_, char)| char == &'{').is_none() {
+        return Err(escape);
+    }
+    escape.push('{');
+
+    let mut digits = String::new();
+    while let Some((_, c)) = iterator.next_if(|(_, char)| char != &'"') {
+        escape.push(c);
+        if c == '}' {
+            break;
+        }
+        digits.push(c);
+    }
+
+    let is_hex = !digits.is_empty() && digits.chars().all(|c| c.is_ascii_hexdigit());
+    if !escape.ends_with('}') || !is_hex {
+        return Err(escape);
+    }
+
+    u32::from_str_radix(&digits, 16)
+        .ok()
+        .and_then(char::from_u32)
+        .ok_or(escape)
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::parser::lexer::{lexedtokens::LexedTokens, token::Token};
+    use crate::parser::lexer::{
+        lexedtokens::{LexedTokens, Tokenizer},
+        token::Token,
+        trivia::{Trivia, TriviaMode},
+    };
 
     #[test]
     fn parse_sympols() {
@@ -178,20 +500,12 @@ mod tests {
 
         let expected_tokens = [Token::Ident("foo".to_string())];
 
-        let mut found_tokens: LexedTokens = LexedTokens::from(source_code);
+        let found_tokens: Vec<Token> = Tokenizer::new(source_code, TriviaMode::Discard).collect();
 
         assert_eq!(
-            found_tokens.token_iter.len(),
-            expected_tokens.len(),
+            found_tokens, expected_tokens,
             "List of expected tokens should be the same as found tokens"
         );
-        expected_tokens.iter().enumerate().for_each(|(idx, token)| {
-            assert_eq!(
-                token,
-                &found_tokens.token_iter.nth(idx).expect("Should have token"),
-                "Token in position {idx} was not parsed"
-            )
-        });
     }
 
     #[test]
@@ -210,16 +524,11 @@ mod tests {
 
         let mut found_tokens: LexedTokens = LexedTokens::from(source_code);
 
-        assert_eq!(
-            found_tokens.token_iter.len(),
-            expected_tokens.len(),
-            "List of expected tokens should be the same as found tokens"
-        );
-
         expected_tokens.iter().for_each(|token| {
             let found = &found_tokens.consume().unwrap();
             assert_eq!(token, found, "Expected {token:?}, but got {found:?}")
         });
+        assert_eq!(found_tokens.consume(), None);
     }
 
     #[test]
@@ -242,6 +551,7 @@ mod tests {
             ~
             ==
             !=
+            <= >= < >
         ";
 
         let expected_tokens = vec![
@@ -294,6 +604,109 @@ mod tests {
             Token::Lasagna,
             Token::Equal,
             Token::NotEqual,
+            Token::LessThanEqual,
+            Token::GreaterThanEqual,
+            Token::LessThan,
+            Token::GreaterThan,
+        ];
+
+        let mut found_tokens: LexedTokens = LexedTokens::from(source_code);
+        let mut expected_iter = expected_tokens.into_iter();
+        while let Some(token) = found_tokens.consume() {
+            let expected_token = expected_iter.next().unwrap();
+            assert_eq!(token, expected_token);
+        }
+    }
+
+    #[test]
+    fn parse_string() {
+        let source_code = "import \"math.lasagna\". \"unterminated";
+
+        let expected_tokens = vec![
+            Token::Import,
+            Token::Str("math.lasagna".to_string()),
+            Token::Period,
+            Token::Illegal,
+        ];
+
+        let mut found_tokens: LexedTokens = LexedTokens::from(source_code);
+        let mut expected_iter = expected_tokens.into_iter();
+        while let Some(token) = found_tokens.consume() {
+            let expected_token = expected_iter.next().unwrap();
+            assert_eq!(token, expected_token);
+        }
+        assert!(expected_iter.next().is_none(), "Should consume all tokens");
+    }
+
+    #[test]
+    fn parse_float() {
+        let source_code = "3.14 + 4. 5.5.";
+
+        let expected_tokens = vec![
+            Token::Float("3.14".to_string()),
+            Token::Add,
+            Token::Int("4".to_string()),
+            Token::Period,
+            Token::Float("5.5".to_string()),
+            Token::Period,
+        ];
+
+        let mut found_tokens: LexedTokens = LexedTokens::from(source_code);
+        let mut expected_iter = expected_tokens.into_iter();
+        while let Some(token) = found_tokens.consume() {
+            let expected_token = expected_iter.next().unwrap();
+            assert_eq!(token, expected_token);
+        }
+        assert!(expected_iter.next().is_none(), "Should consume all tokens");
+    }
+
+    #[test]
+    fn tokens_track_their_span() {
+        let source_code = "let foo: 10.";
+
+        let mut found_tokens: LexedTokens = LexedTokens::from(source_code);
+
+        let mut spans = Vec::new();
+        while found_tokens.consume().is_some() {
+            let span = found_tokens.previous_span();
+            spans.push(&source_code[span.start..span.end]);
+        }
+
+        assert_eq!(spans, vec!["let", "foo", ":", "10", "."]);
+    }
+
+    #[test]
+    fn parse_digit_separators() {
+        let source_code = "1_000_000. 1_0.5 1__0 10_ _10";
+
+        let expected_tokens = vec![
+            Token::Int("1000000".to_string()),
+            Token::Period,
+            Token::Float("10.5".to_string()),
+            Token::InvalidDigitSeparator("1__0".to_string()),
+            Token::InvalidDigitSeparator("10_".to_string()),
+            Token::Illegal,
+            Token::Int("10".to_string()),
+        ];
+
+        let mut found_tokens: LexedTokens = LexedTokens::from(source_code);
+        let mut expected_iter = expected_tokens.into_iter();
+        while let Some(token) = found_tokens.consume() {
+            let expected_token = expected_iter.next().unwrap();
+            assert_eq!(token, expected_token);
+        }
+        assert!(expected_iter.next().is_none(), "Should consume all tokens");
+    }
+
+    #[test]
+    fn parse_radix_prefixed_integers() {
+        let source_code = "0xFF 0b1010 0xG 0";
+
+        let expected_tokens = vec![
+            Token::Int("0xFF".to_string()),
+            Token::Int("0b1010".to_string()),
+            Token::Int("0xG".to_string()),
+            Token::Int("0".to_string()),
         ];
 
         let mut found_tokens: LexedTokens = LexedTokens::from(source_code);
@@ -302,5 +715,268 @@ mod tests {
             let expected_token = expected_iter.next().unwrap();
             assert_eq!(token, expected_token);
         }
+        assert!(expected_iter.next().is_none(), "Should consume all tokens");
+    }
+
+    #[test]
+    fn relex_reuses_buffer() {
+        let mut found_tokens: LexedTokens = LexedTokens::from("let foo: 10.");
+        while found_tokens.consume().is_some() {}
+        let capacity = found_tokens.tokenizer.source.capacity();
+
+        found_tokens.relex("1 + 2");
+
+        assert_eq!(found_tokens.tokenizer.source.capacity(), capacity);
+        assert_eq!(found_tokens.consume(), Some(Token::Int("1".to_string())));
+        assert_eq!(found_tokens.consume(), Some(Token::Add));
+        assert_eq!(found_tokens.consume(), Some(Token::Int("2".to_string())));
+        assert_eq!(found_tokens.consume(), None);
+    }
+
+    #[test]
+    fn parse_very_long_tokens() {
+        let identifier = "a".repeat(10_000);
+        let number = "1".repeat(10_000);
+        let source_code = format!("{identifier} {number}");
+
+        let mut found_tokens: LexedTokens = LexedTokens::from(source_code.as_str());
+
+        assert_eq!(found_tokens.consume(), Some(Token::Ident(identifier)));
+        assert_eq!(found_tokens.consume(), Some(Token::Int(number)));
+        assert_eq!(found_tokens.consume(), None);
+    }
+
+    #[test]
+    fn unicode_escapes_test() {
+        let input_expected = [
+            (r#""caf\u{E9}""#, Token::Str(String::from("café"))),
+            (r#""\u{1F600}!""#, Token::Str(String::from("😀!"))),
+            (r#""a\nb""#, Token::Str(String::from(r"a\nb"))),
+            (
+                r#""\u{110000}""#,
+                Token::InvalidEscape(String::from(r"\u{110000}")),
+            ),
+            (r#""\u{zz}""#, Token::InvalidEscape(String::from(r"\u{zz}"))),
+            (r#""\u41""#, Token::InvalidEscape(String::from(r"\u"))),
+            (r#""\u{41""#, Token::InvalidEscape(String::from(r"\u{41"))),
+        ];
+
+        for (input, expected) in input_expected {
+            let mut found_tokens = LexedTokens::from(input);
+            assert_eq!(Some(expected), found_tokens.consume(), "Wrong for {input}");
+            assert_eq!(None, found_tokens.consume(), "Leftover tokens for {input}");
+        }
+    }
+
+    #[test]
+    fn method_access_test() {
+        let input_expected = [
+            (
+                "arr.len",
+                vec![
+                    Token::Ident(String::from("arr")),
+                    Token::MethodAccess,
+                    Token::Ident(String::from("len")),
+                ],
+            ),
+            (
+                "arr. len",
+                vec![
+                    Token::Ident(String::from("arr")),
+                    Token::Period,
+                    Token::Ident(String::from("len")),
+                ],
+            ),
+            (
+                "arr .len",
+                vec![
+                    Token::Ident(String::from("arr")),
+                    Token::Period,
+                    Token::Ident(String::from("len")),
+                ],
+            ),
+            (
+                "f().len + 1.",
+                vec![
+                    Token::Ident(String::from("f")),
+                    Token::LParen,
+                    Token::RParen,
+                    Token::MethodAccess,
+                    Token::Ident(String::from("len")),
+                    Token::Add,
+                    Token::Int(String::from("1")),
+                    Token::Period,
+                ],
+            ),
+            ("true.false", vec![Token::True, Token::Period, Token::False]),
+            (
+                "a.let",
+                vec![Token::Ident(String::from("a")), Token::Period, Token::Let],
+            ),
+            (
+                "x.y.~",
+                vec![
+                    Token::Ident(String::from("x")),
+                    Token::Period,
+                    Token::Ident(String::from("y")),
+                    Token::Period,
+                    Token::Lasagna,
+                ],
+            ),
+            (
+                "x.y.z",
+                vec![
+                    Token::Ident(String::from("x")),
+                    Token::MethodAccess,
+                    Token::Ident(String::from("y")),
+                    Token::MethodAccess,
+                    Token::Ident(String::from("z")),
+                ],
+            ),
+        ];
+
+        for (input, expected) in input_expected {
+            let mut found_tokens = LexedTokens::from(input);
+            for expected_token in expected {
+                assert_eq!(
+                    Some(expected_token),
+                    found_tokens.consume(),
+                    "Wrong for {input}"
+                );
+            }
+            assert_eq!(None, found_tokens.consume(), "Leftover tokens for {input}");
+        }
+    }
+
+    #[test]
+    fn block_comments_test() {
+        let input_expected = [
+            (
+                "let /* the answer */ x: 42.",
+                vec![
+                    Token::Let,
+                    Token::Ident(String::from("x")),
+                    Token::Assign,
+                    Token::Int(String::from("42")),
+                    Token::Period,
+                ],
+            ),
+            (
+                "1 /* outer /* inner */ still outer */ / 2",
+                vec![
+                    Token::Int(String::from("1")),
+                    Token::Slash,
+                    Token::Int(String::from("2")),
+                ],
+            ),
+            ("/**/", vec![]),
+            (
+                "1. /* never /* closed */",
+                vec![
+                    Token::Int(String::from("1")),
+                    Token::Period,
+                    Token::UnterminatedComment,
+                ],
+            ),
+        ];
+
+        for (input, expected) in input_expected {
+            let mut found_tokens = LexedTokens::from(input);
+            for expected_token in expected {
+                assert_eq!(
+                    Some(expected_token),
+                    found_tokens.consume(),
+                    "Wrong for {input}"
+                );
+            }
+            assert_eq!(None, found_tokens.consume(), "Leftover tokens for {input}");
+        }
+    }
+
+    #[test]
+    fn tokenizer_is_lazy_test() {
+        let source_code = "
+            let add: fn(x, y): x + y~.
+            /* a /* nested */ comment */
+            add(\"a\".len, [1][0]) ?? -2.5.
+        ";
+
+        let mut tokenizer = Tokenizer::new(source_code, TriviaMode::Discard);
+        assert_eq!(tokenizer.next(), Some(Token::Let));
+        assert_eq!(
+            tokenizer.offset,
+            source_code.find("let").unwrap() + "let".len(),
+            "Only the first token should have been lexed"
+        );
+
+        let iterated: Vec<Token> = Tokenizer::new(source_code, TriviaMode::Discard).collect();
+        let mut lexed_tokens = LexedTokens::from(source_code);
+        let mut consumed = Vec::new();
+        while let Some(token) = lexed_tokens.consume() {
+            consumed.push(token);
+        }
+
+        assert_eq!(iterated, consumed);
+        assert!(iterated.contains(&Token::MethodAccess));
+        assert_eq!(iterated.len(), 32);
+    }
+
+    #[test]
+    fn preserved_trivia_test() {
+        let source_code = "/* answer */\nlet a: 42. /* the answer */\nlet b: a.\n";
+
+        let mut tokenizer = Tokenizer::new(source_code, TriviaMode::Preserve);
+        let trivia_tokens: Vec<_> = std::iter::from_fn(|| tokenizer.next_with_trivia()).collect();
+        let tokens: Vec<Token> = trivia_tokens
+            .iter()
+            .map(|trivia_token| trivia_token.token.clone())
+            .collect();
+        let discarded: Vec<Token> = Tokenizer::new(source_code, TriviaMode::Discard).collect();
+        assert_eq!(discarded, tokens);
+
+        assert_eq!(
+            trivia_tokens[0].leading,
+            vec![
+                Trivia::Comment(String::from("/* answer */")),
+                Trivia::Whitespace(String::from("\n")),
+            ]
+        );
+
+        let first_period = &trivia_tokens[4];
+        assert_eq!(first_period.token, Token::Period);
+        assert_eq!(
+            first_period.trailing,
+            vec![
+                Trivia::Whitespace(String::from(" ")),
+                Trivia::Comment(String::from("/* the answer */")),
+            ]
+        );
+
+        let second_let = &trivia_tokens[5];
+        assert_eq!(second_let.token, Token::Let);
+        assert_eq!(
+            second_let.leading,
+            vec![Trivia::Whitespace(String::from("\n"))]
+        );
+
+        let last_period = trivia_tokens.last().expect("Should have tokens");
+        assert_eq!(
+            last_period.trailing,
+            vec![Trivia::Whitespace(String::from("\n"))]
+        );
+
+        let reprinted: String = trivia_tokens
+            .iter()
+            .map(|trivia_token| {
+                let leading: String = trivia_token.leading.iter().map(Trivia::to_string).collect();
+                let trailing: String = trivia_token
+                    .trailing
+                    .iter()
+                    .map(Trivia::to_string)
+                    .collect();
+                format!("{leading}{}{trailing}", trivia_token.token)
+            })
+            .collect();
+        assert_eq!(source_code, reprinted);
     }
 }
@@ -1,2 +1,4 @@
 pub mod lexedtokens;
+pub mod span;
 pub mod token;
+pub mod trivia;
@@ -15,36 +15,81 @@ pub enum Token {
     Lasagna,
     Equal,
     NotEqual,
+    Arrow,
     Ident(String),
     Int(String),
+    Float(String),
+    Str(String),
+    InvalidEscape(String),
+    /// A numeric literal with an underscore that does not separate digits.
+    InvalidDigitSeparator(String),
+    UnterminatedComment,
     Comma,
     Return,
     If,
     Func,
     LessThan,
     GreaterThan,
+    LessThanEqual,
+    GreaterThanEqual,
     Slash,
     True,
     False,
     Else,
     Period,
+    MethodAccess,
     Asterix,
+    Percent,
+    Caret,
+    DoubleQuestion,
+    Import,
+    Const,
+    While,
+    Do,
+    Break,
+    Continue,
 }
 
-#[derive(Debug, PartialEq, PartialOrd)]
+/// Binding strength of operators, from loosest to tightest.
+#[derive(Debug, PartialEq, PartialOrd, Clone, Copy)]
 pub enum Precedence {
     Lowest,
+    Coalesce,
     Equals,
     LessGreater,
     Sum,
     Product,
+    Power,
     Prefix,
     Call,
+    Index,
+}
+
+impl Precedence {
+    const ORDER: [Precedence; 10] = [
+        Precedence::Lowest,
+        Precedence::Coalesce,
+        Precedence::Equals,
+        Precedence::LessGreater,
+        Precedence::Sum,
+        Precedence::Product,
+        Precedence::Power,
+        Precedence::Prefix,
+        Precedence::Call,
+        Precedence::Index,
+    ];
+
+    /// The precedence one level looser than this one.
+    pub fn looser(self) -> Precedence {
+        Self::ORDER[(self as usize).saturating_sub(1)]
+    }
 }
 
 pub enum HasInfix {
     Arithmic(Operator),
     Call(),
+    Method(),
+    Index(),
     No(Token),
 }
 
@@ -53,17 +98,23 @@ pub enum ParsedToken {
     PossibleMultipart(FirstPart),
     NumericStart,
     AlphabeticStart,
+    StringStart,
 }
 
 pub enum FirstPart {
     Bang,
+    Question,
     Equal,
+    LessThan,
+    GreaterThan,
 }
 pub enum ParsedMultipartToken {
     OnlyOnePart(Token),
     Multipart(Token),
 }
 
+use std::fmt::Display;
+
 use Token::*;
 
 use crate::parser::ast::Operator;
@@ -78,6 +129,12 @@ impl Token {
             "else" => Else,
             "if" => If,
             "fn" => Func,
+            "import" => Import,
+            "const" => Const,
+            "while" => While,
+            "do" => Do,
+            "break" => Break,
+            "continue" => Continue,
             identifier_literal => Ident(identifier_literal.to_string()),
         }
     }
@@ -96,27 +153,43 @@ impl Token {
             '(' => CompleteToken(Token::LParen),
             ']' => CompleteToken(Token::RBracket),
             '[' => CompleteToken(Token::LBracket),
-            '<' => CompleteToken(Token::LessThan),
-            '>' => CompleteToken(Token::GreaterThan),
+            '<' => PossibleMultipart(FirstPart::LessThan),
+            '>' => PossibleMultipart(FirstPart::GreaterThan),
             ',' => CompleteToken(Token::Comma),
             '.' => CompleteToken(Token::Period),
             '~' => CompleteToken(Token::Lasagna),
             '/' => CompleteToken(Token::Slash),
             '*' => CompleteToken(Token::Asterix),
+            '^' => CompleteToken(Token::Caret),
+            '%' => CompleteToken(Token::Percent),
+            '?' => PossibleMultipart(FirstPart::Question),
+            '"' => StringStart,
             numeric_char if numeric_char.is_numeric() => NumericStart,
             alphabetic_char if alphabetic_char.is_alphabetic() => AlphabeticStart,
             _ => CompleteToken(Token::Illegal),
         }
     }
 
+    /// Operators take their precedence from [`Operator::precedence`], so the
+    /// binding strength of every operator is defined in one place.
     pub fn get_precedence(&self) -> Precedence {
-        match self {
-            LParen => Precedence::Call,
-            Equal | NotEqual => Precedence::Equals,
-            LessThan | GreaterThan => Precedence::LessGreater,
-            Add | Minus => Precedence::Sum,
-            Slash | Asterix => Precedence::Product,
-            _ => Precedence::Lowest,
+        match self.has_infix() {
+            HasInfix::Call() | HasInfix::Method() => Precedence::Call,
+            HasInfix::Index() => Precedence::Index,
+            HasInfix::Arithmic(operator) => operator.precedence(),
+            HasInfix::No(_) => Precedence::Lowest,
+        }
+    }
+
+    /// The precedence used when parsing the right hand side of an infix
+    /// operator. Right associative operators bind their right side one level
+    /// looser, so `2 ^ 2 ^ 3` parses as `2 ^ (2 ^ 3)`.
+    pub fn get_right_precedence(&self) -> Precedence {
+        match self.has_infix() {
+            HasInfix::Arithmic(operator) if operator.is_right_associative() => {
+                operator.precedence().looser()
+            }
+            _ => self.get_precedence(),
         }
     }
 
@@ -131,25 +204,89 @@ impl Token {
                 Some('=') => Multipart(Token::NotEqual),
                 _ => OnlyOnePart(Token::Bang),
             },
+            Question => match second_char {
+                Some('?') => Multipart(Token::DoubleQuestion),
+                _ => OnlyOnePart(Token::Illegal),
+            },
             Equal => match second_char {
                 Some('=') => Multipart(Token::Equal),
+                Some('>') => Multipart(Token::Arrow),
                 _ => OnlyOnePart(Token::Illegal),
             },
+            LessThan => match second_char {
+                Some('=') => Multipart(Token::LessThanEqual),
+                _ => OnlyOnePart(Token::LessThan),
+            },
+            GreaterThan => match second_char {
+                Some('=') => Multipart(Token::GreaterThanEqual),
+                _ => OnlyOnePart(Token::GreaterThan),
+            },
         }
     }
 
     pub fn has_infix(&self) -> HasInfix {
         match self {
-            Token::NotEqual => HasInfix::Arithmic(Operator::NotEquals),
-            Token::Add => HasInfix::Arithmic(Operator::Plus),
-            Token::Minus => HasInfix::Arithmic(Operator::Minus),
-            Token::Equal => HasInfix::Arithmic(Operator::Equals),
-            Token::LessThan => HasInfix::Arithmic(Operator::LessThan),
-            Token::GreaterThan => HasInfix::Arithmic(Operator::GreaterThan),
-            Token::Slash => HasInfix::Arithmic(Operator::DividedBy),
-            Token::Asterix => HasInfix::Arithmic(Operator::Multiply),
             Token::LParen => HasInfix::Call(),
-            unexpected_token => HasInfix::No(unexpected_token.clone()),
+            Token::MethodAccess => HasInfix::Method(),
+            Token::LBracket => HasInfix::Index(),
+            Token::Bang => HasInfix::No(Token::Bang),
+            token => match Operator::from_token(token) {
+                Some(operator) => HasInfix::Arithmic(operator),
+                None => HasInfix::No(token.clone()),
+            },
+        }
+    }
+}
+
+impl Display for Token {
+    /// Writes the token the way it is spelled in source code.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Ident(literal) | Int(literal) | Float(literal) | InvalidDigitSeparator(literal) => {
+                write!(f, "{literal}")
+            }
+            Str(literal) => write!(f, "\"{literal}\""),
+            InvalidEscape(escape) => write!(f, "{escape}"),
+            UnterminatedComment => write!(f, "/*"),
+            Illegal => write!(f, "illegal token"),
+            Let => write!(f, "let"),
+            Return => write!(f, "return"),
+            If => write!(f, "if"),
+            Else => write!(f, "else"),
+            Func => write!(f, "fn"),
+            True => write!(f, "true"),
+            False => write!(f, "false"),
+            Import => write!(f, "import"),
+            Const => write!(f, "const"),
+            While => write!(f, "while"),
+            Do => write!(f, "do"),
+            Break => write!(f, "break"),
+            Continue => write!(f, "continue"),
+            Bang => write!(f, "!"),
+            Add => write!(f, "+"),
+            Minus => write!(f, "-"),
+            Assign => write!(f, ":"),
+            RBrace => write!(f, "}}"),
+            LBrace => write!(f, "{{"),
+            RParen => write!(f, ")"),
+            LParen => write!(f, "("),
+            RBracket => write!(f, "]"),
+            LBracket => write!(f, "["),
+            Lasagna => write!(f, "~"),
+            Equal => write!(f, "=="),
+            NotEqual => write!(f, "!="),
+            Arrow => write!(f, "=>"),
+            Comma => write!(f, ","),
+            LessThan => write!(f, "<"),
+            GreaterThan => write!(f, ">"),
+            LessThanEqual => write!(f, "<="),
+            GreaterThanEqual => write!(f, ">="),
+            Slash => write!(f, "/"),
+            Period | MethodAccess => write!(f, "."),
+            Asterix => write!(f, "*"),
+            Caret => write!(f, "^"),
+            Percent => write!(f, "%"),
+            DoubleQuestion => write!(f, "??"),
         }
     }
 }
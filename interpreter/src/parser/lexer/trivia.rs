@@ -0,0 +1,38 @@
+use std::fmt::Display;
+
+use super::{span::Span, token::Token};
+
+/// Whether the tokenizer keeps the whitespace and comments between tokens.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum TriviaMode {
+    /// Whitespace and comments are skipped, as the parser has no use for them.
+    #[default]
+    Discard,
+    /// Whitespace and comments are kept, so the source can be printed again
+    /// without losing them.
+    Preserve,
+}
+
+/// Source code between tokens that does not change the meaning of a program.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Trivia {
+    Whitespace(String),
+    Comment(String),
+}
+
+impl Display for Trivia {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Trivia::Whitespace(text) | Trivia::Comment(text) => write!(f, "{text}"),
+        }
+    }
+}
+
+/// A token together with the trivia before and after it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TriviaToken {
+    pub token: Token,
+    pub span: Span,
+    pub leading: Vec<Trivia>,
+    pub trailing: Vec<Trivia>,
+}
@@ -1,8 +1,12 @@
-use std::{fmt::Display, num::ParseIntError};
+use std::{
+    error::Error,
+    fmt::Display,
+    num::{ParseFloatError, ParseIntError},
+};
 
 use lexer::token::Token;
 
-use crate::parser::lexer;
+use crate::parser::{ast::Identifier, lexer, lexer::span::Span};
 
 #[derive(Debug)]
 pub enum ParseError {
@@ -16,10 +20,60 @@ pub enum ParseError {
     NoPrefixExpression(Token),
     NoInfixExpression(Token),
     ParseIntegerError(Token, ParseIntError),
+    ParseFloatError(Token, ParseFloatError),
     NoPrefixPartner,
+    ChainedComparison(Token, Token),
+    RequiredParameterAfterDefault(Identifier),
+    InvalidEscape(String),
+    NestingLimitExceeded(usize),
+    UnterminatedComment,
+    AssignmentArityMismatch {
+        targets: usize,
+        values: usize,
+    },
+    TrailingTokens(Token),
+    UnknownType(Identifier),
+    InvalidDigitSeparator(String),
+}
+
+/// A parse error together with the top-level statement it was found in, so
+/// every error of a program can be listed with its location.
+#[derive(Debug)]
+pub struct StatementError {
+    /// The position of the statement in the program, counting from zero and
+    /// including the statements that parsed.
+    pub statement: usize,
+    /// From the start of the statement to the last token read before the error.
+    pub span: Span,
+    pub error: ParseError,
 }
 
 impl ParseError {
+    /// A code that identifies the kind of error, which stays the same when
+    /// the message changes. Parse errors have codes starting at `E0001`.
+    pub fn code(&self) -> &'static str {
+        match self {
+            ParseError::UnexpectedToken { .. } => "E0001",
+            ParseError::ExpectedToken => "E0002",
+            ParseError::UnknownToken(_) => "E0003",
+            ParseError::ExpressionError(_) => "E0004",
+            ParseError::NoPrefixExpression(_) => "E0005",
+            ParseError::NoInfixExpression(_) => "E0006",
+            ParseError::ParseIntegerError(..) => "E0007",
+            ParseError::ParseFloatError(..) => "E0008",
+            ParseError::NoPrefixPartner => "E0009",
+            ParseError::ChainedComparison(..) => "E0010",
+            ParseError::RequiredParameterAfterDefault(_) => "E0011",
+            ParseError::InvalidEscape(_) => "E0012",
+            ParseError::NestingLimitExceeded(_) => "E0013",
+            ParseError::UnterminatedComment => "E0014",
+            ParseError::AssignmentArityMismatch { .. } => "E0015",
+            ParseError::TrailingTokens(_) => "E0016",
+            ParseError::UnknownType(_) => "E0017",
+            ParseError::InvalidDigitSeparator(_) => "E0018",
+        }
+    }
+
     pub fn single_unexpected(expected_token: &Token, found_token: Option<&Token>) -> ParseError {
         ParseError::UnexpectedToken {
             expected_token: TokenExpectation::SingleExpectation(expected_token.clone()),
@@ -51,36 +105,154 @@ impl Display for ParseError {
             ParseError::UnexpectedToken {
                 expected_token,
                 found_token,
-            } => write!(
-                f,
-                "Expected token {expected_token:?}, but received token of type {found_token:?}"
-            ),
+            } => {
+                write!(f, "expected {expected_token} but found ")?;
+                match found_token {
+                    Some(token) => write!(f, "'{}'", abbreviate(&token.to_string())),
+                    None => write!(f, "end of input"),
+                }
+            }
             ParseError::ExpectedToken => {
                 write!(f, "Expected to receive a token, but no token was received")
             }
             ParseError::UnknownToken(token) => write!(
                 f,
-                "Received unknown token of type {token:?}, don't know how to handle it"
+                "Received unknown token '{token}', don't know how to handle it"
             ),
             ParseError::ExpressionError(error) => write!(f, "{error}"),
             ParseError::ParseIntegerError(token, error) => write!(
                 f,
-                "Tried to parse token {token:?} as an integer, but got error {error}"
+                "Tried to parse '{}' as an integer, but got error {error}",
+                abbreviate(&token.to_string())
+            ),
+            ParseError::ParseFloatError(token, error) => write!(
+                f,
+                "Tried to parse '{}' as a float, but got error {error}",
+                abbreviate(&token.to_string())
             ),
             ParseError::NoPrefixExpression(token) => {
-                write!(f, "No prefix parse function for {token:?} found")
+                write!(f, "No prefix parse function for '{token}' found")
             }
             ParseError::NoPrefixPartner => write!(f, "Expected expression to follow prefix"),
+            ParseError::ChainedComparison(first, second) => write!(
+                f,
+                "Comparisons can not be chained, found '{first}' followed by '{second}'. Use parentheses to group them"
+            ),
+            ParseError::RequiredParameterAfterDefault(parameter) => write!(
+                f,
+                "Parameter {parameter} needs a default value, since it follows a parameter with one"
+            ),
+            ParseError::InvalidEscape(escape) => write!(
+                f,
+                "Invalid escape {escape} in string, expected \\u{{...}} with the hex digits of a unicode codepoint"
+            ),
+            ParseError::NestingLimitExceeded(nesting_limit) => write!(
+                f,
+                "Program is nested more than {nesting_limit} levels deep"
+            ),
+            ParseError::AssignmentArityMismatch { targets, values } => write!(
+                f,
+                "Can not assign {values} values to {targets} identifiers"
+            ),
+            ParseError::UnterminatedComment => {
+                write!(f, "Block comment is not closed with a matching */")
+            }
+            ParseError::TrailingTokens(token) => write!(
+                f,
+                "Expected the expression to end, but found '{}'",
+                abbreviate(&token.to_string())
+            ),
+            ParseError::InvalidDigitSeparator(literal) => match literal.ends_with('_') {
+                true => write!(f, "Numeric literal {literal} can not end with '_'"),
+                false => write!(
+                    f,
+                    "Numeric literal {literal} can not have '_' after another '_'"
+                ),
+            },
+            ParseError::UnknownType(type_annotation) => write!(
+                f,
+                "Unknown type {type_annotation} in type annotation"
+            ),
             ParseError::NoInfixExpression(token) => {
-                write!(f, "No infix parse function for {token:?} found")
+                write!(f, "No infix parse function for '{token}' found")
             }
         }
     }
 }
 
+impl StatementError {
+    pub fn code(&self) -> &'static str {
+        self.error.code()
+    }
+}
+
+impl Display for StatementError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.error.fmt(f)
+    }
+}
+
+const MAX_DISPLAYED_TOKEN_LENGTH: usize = 32;
+
+/// Literals can be arbitrarily long, so they are cut short to keep error
+/// messages readable.
+fn abbreviate(displayed: &str) -> String {
+    match displayed.char_indices().nth(MAX_DISPLAYED_TOKEN_LENGTH) {
+        Some((cutoff, _)) => format!("{}...", &displayed[..cutoff]),
+        None => displayed.to_string(),
+    }
+}
+
+impl Display for TokenExpectation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TokenExpectation::SingleExpectation(token) => write!(f, "{}", describe(token)),
+            TokenExpectation::MultipleExpectation(tokens) => {
+                let expectations: Vec<String> = tokens.iter().map(describe).collect();
+                write!(f, "one of {}", expectations.join(", "))
+            }
+        }
+    }
+}
+
+/// Tokens carrying a literal are expected by kind, since the expectation is
+/// built with a placeholder value.
+fn describe(token: &Token) -> String {
+    match token {
+        Token::Ident(_) => String::from("an identifier"),
+        Token::Str(_) => String::from("a string"),
+        Token::Int(_) | Token::Float(_) => String::from("a number"),
+        token => format!("'{token}'"),
+    }
+}
+
+impl Error for ParseError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ParseError::ParseIntegerError(_, error) => Some(error),
+            ParseError::ParseFloatError(_, error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl Error for StatementError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        self.error.source()
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::parser::{parse_errors::ParseError, test_util::parse_program, ParsedProgram};
+    use std::{collections::HashSet, error::Error, num::IntErrorKind};
+
+    use crate::parser::{
+        ast::Identifier,
+        lexer::token::Token,
+        parse_errors::{ParseError, StatementError},
+        test_util::{self, parse_program},
+        ParsedProgram,
+    };
 
     #[test]
     fn test_parse_errors() {
@@ -94,9 +266,206 @@ mod tests {
             ParsedProgram::ValidProgram(_) => panic!("Program did not fail"),
             ParsedProgram::InvalidProgram(parse_errors) => {
                 parse_errors.iter().for_each(|parse_error| {
-                    assert!(matches!(parse_error, ParseError::NoPrefixExpression(_)))
+                    assert!(matches!(
+                        parse_error.error,
+                        ParseError::NoPrefixExpression(_)
+                    ))
                 });
             }
         }
     }
+
+    #[test]
+    fn parse_error_exposes_source_test() {
+        let program = parse_program("99999999999.");
+
+        match program {
+            ParsedProgram::ValidProgram(_) => panic!("Program did not fail"),
+            ParsedProgram::InvalidProgram(parse_errors) => {
+                let parse_error = parse_errors.first().expect("Should have an error");
+                assert!(matches!(
+                    parse_error.error,
+                    ParseError::ParseIntegerError(..)
+                ));
+                assert!(parse_error.source().is_some());
+            }
+        }
+    }
+
+    #[test]
+    fn very_long_integer_should_fail_test() {
+        let source_code = format!("{}.", "9".repeat(5000));
+
+        match parse_program(&source_code) {
+            ParsedProgram::ValidProgram(_) => panic!("Program did not fail"),
+            ParsedProgram::InvalidProgram(parse_errors) => {
+                let parse_error = parse_errors.first().expect("Should have an error");
+                assert!(matches!(
+                    &parse_error.error,
+                    ParseError::ParseIntegerError(_, error)
+                        if error.kind() == &IntErrorKind::PosOverflow
+                ));
+                assert!(parse_error.to_string().len() < 200);
+            }
+        }
+    }
+
+    #[test]
+    fn unexpected_token_message_test() {
+        let input_expected = vec![
+            (
+                "let f: fn(a b): a~.",
+                "expected one of ',', ')' but found 'b'",
+            ),
+            (
+                "let f: fn(a, 5): a~.",
+                "expected an identifier but found '5'",
+            ),
+            ("import 5.", "expected a string but found '5'"),
+            ("(1, 2", "expected ')' but found end of input"),
+            ("let x +: 1.", "expected ':' but found '+'"),
+            ("let x: * 2.", "No prefix parse function for '*' found"),
+            (
+                "1 < 2 < 3.",
+                "Comparisons can not be chained, found '<' followed by '<'. Use parentheses to group them",
+            ),
+        ];
+
+        test_util::assert_list(
+            input_expected,
+            |expected: &&str, input: &&str| match parse_program(input) {
+                ParsedProgram::InvalidProgram(parse_errors) => {
+                    let parse_error = parse_errors.first().expect("Should have an error");
+                    assert_eq!(expected, &parse_error.to_string(), "Wrong for '{input}'")
+                }
+                ParsedProgram::ValidProgram(_) => panic!("'{input}' should not parse"),
+            },
+        );
+    }
+
+    #[test]
+    fn invalid_escape_test() {
+        match parse_program("let a: \"\\u{D800}\". a") {
+            ParsedProgram::InvalidProgram(parse_errors) => {
+                let parse_error = parse_errors.first().expect("Should have an error");
+                assert!(
+                    matches!(&parse_error.error, ParseError::InvalidEscape(escape) if escape == "\\u{D800}")
+                );
+            }
+            ParsedProgram::ValidProgram(_) => panic!("Invalid escape should not parse"),
+        }
+    }
+
+    #[test]
+    fn invalid_digit_separator_test() {
+        let input_expected = vec![
+            ("let a: 1_.", "Numeric literal 1_ can not end with '_'"),
+            (
+                "let a: 1__000.",
+                "Numeric literal 1__000 can not have '_' after another '_'",
+            ),
+        ];
+
+        test_util::assert_list(input_expected, |expected, input| {
+            match parse_program(input) {
+                ParsedProgram::InvalidProgram(parse_errors) => {
+                    let parse_error = parse_errors.first().expect("Should have an error");
+                    assert_eq!("E0018", parse_error.code());
+                    assert_eq!(expected, &parse_error.to_string(), "Wrong for '{input}'");
+                }
+                ParsedProgram::ValidProgram(_) => panic!("'{input}' should not parse"),
+            }
+        });
+    }
+
+    #[test]
+    fn unterminated_comment_test() {
+        match parse_program("let a: 1. /* a /* b */ a") {
+            ParsedProgram::InvalidProgram(parse_errors) => assert!(matches!(
+                parse_errors.as_slice(),
+                [StatementError {
+                    error: ParseError::UnterminatedComment,
+                    ..
+                }]
+            )),
+            ParsedProgram::ValidProgram(_) => panic!("Unterminated comment should not parse"),
+        }
+    }
+
+    #[test]
+    fn errors_know_their_statement_test() {
+        let source_code = "let a: 1. let: 2. let b: * 3. 4. let c 5. (1, 2";
+
+        match parse_program(source_code) {
+            ParsedProgram::InvalidProgram(parse_errors) => {
+                let statements: Vec<usize> = parse_errors
+                    .iter()
+                    .map(|parse_error| parse_error.statement)
+                    .collect();
+                assert_eq!(vec![1, 2, 4, 5], statements);
+
+                let located: Vec<&str> = parse_errors
+                    .iter()
+                    .map(|parse_error| &source_code[parse_error.span.start..parse_error.span.end])
+                    .collect();
+                assert_eq!(vec!["let", "let b: *", "let c", "(1, 2"], located);
+            }
+            ParsedProgram::ValidProgram(_) => panic!("Program did not fail"),
+        }
+
+        match parse_program("let: 1. let x 2. * 3.") {
+            ParsedProgram::InvalidProgram(parse_errors) => {
+                let statements: Vec<usize> = parse_errors
+                    .iter()
+                    .map(|parse_error| parse_error.statement)
+                    .collect();
+                assert_eq!(vec![0, 1, 2], statements);
+            }
+            ParsedProgram::ValidProgram(_) => panic!("Program did not fail"),
+        }
+    }
+
+    #[test]
+    fn error_codes_test() {
+        match parse_program("let x +: 1.") {
+            ParsedProgram::InvalidProgram(parse_errors) => {
+                let parse_error = parse_errors.first().expect("Should have an error");
+                assert_eq!("E0001", parse_error.code());
+            }
+            ParsedProgram::ValidProgram(_) => panic!("Program did not fail"),
+        }
+        assert_eq!("E0013", ParseError::NestingLimitExceeded(1).code());
+
+        let parse_errors = [
+            ParseError::single_unexpected(&Token::Comma, None),
+            ParseError::ExpectedToken,
+            ParseError::UnknownToken(Token::Comma),
+            ParseError::ExpressionError(String::new()),
+            ParseError::NoPrefixExpression(Token::Comma),
+            ParseError::NoInfixExpression(Token::Comma),
+            ParseError::ParseIntegerError(
+                Token::Int(String::new()),
+                "".parse::<i32>().expect_err("Empty string is no integer"),
+            ),
+            ParseError::ParseFloatError(
+                Token::Float(String::new()),
+                "".parse::<f64>().expect_err("Empty string is no float"),
+            ),
+            ParseError::NoPrefixPartner,
+            ParseError::ChainedComparison(Token::LessThan, Token::LessThan),
+            ParseError::RequiredParameterAfterDefault(Identifier(String::from("a"))),
+            ParseError::InvalidEscape(String::new()),
+            ParseError::NestingLimitExceeded(1),
+            ParseError::UnterminatedComment,
+            ParseError::AssignmentArityMismatch {
+                targets: 1,
+                values: 2,
+            },
+            ParseError::TrailingTokens(Token::Comma),
+            ParseError::UnknownType(Identifier(String::from("a"))),
+            ParseError::InvalidDigitSeparator(String::new()),
+        ];
+        let codes: HashSet<&str> = parse_errors.iter().map(ParseError::code).collect();
+        assert_eq!(parse_errors.len(), codes.len(), "Codes should be unique");
+    }
 }
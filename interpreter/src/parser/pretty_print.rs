@@ -0,0 +1,166 @@
+use super::{
+    ast::{BlockStatement, Statement},
+    expressions::{
+        expression::Expression, functions::format_parameters, if_expression::IfExpression,
+    },
+    ParsedProgram,
+};
+
+const INDENTATION: &str = "    ";
+
+impl ParsedProgram {
+    /// Renders the program with every statement on its own line, and the bodies
+    /// of functions and if expressions indented one level deeper than their
+    /// header. Meant for inspecting the parsed tree, not for re-parsing it.
+    pub fn pretty_print(&self) -> String {
+        match self {
+            ParsedProgram::ValidProgram(statements) => statements
+                .iter()
+                .map(|statement| format!("{}\n", statement.pretty_print(0)))
+                .collect(),
+            ParsedProgram::InvalidProgram(_) => self.to_string(),
+        }
+    }
+}
+
+impl Statement {
+    fn pretty_print(&self, depth: usize) -> String {
+        match self {
+            Statement::Assign(assign_statement) => format!(
+                "let {}: {}.",
+                assign_statement.target(),
+                assign_statement.assignment.pretty_print(depth)
+            ),
+            Statement::ConstAssign(assign_statement) => format!(
+                "const {}: {}.",
+                assign_statement.target(),
+                assign_statement.assignment.pretty_print(depth)
+            ),
+            Statement::ParallelAssign(parallel_assign_statement) => {
+                let assignments: Vec<String> = parallel_assign_statement
+                    .assignments
+                    .iter()
+                    .map(|assignment| assignment.pretty_print(depth))
+                    .collect();
+                let identifiers: Vec<String> = parallel_assign_statement
+                    .identifiers
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect();
+                format!(
+                    "let {}: {}.",
+                    identifiers.join(", "),
+                    assignments.join(", ")
+                )
+            }
+            Statement::Return(return_statement) => match &return_statement.return_value {
+                Some(return_value) => format!("return {}", return_value.pretty_print(depth)),
+                None => return_statement.to_string(),
+            },
+            Statement::Expression(expression_statement) => {
+                expression_statement.expression.pretty_print(depth)
+            }
+            Statement::Import(import_statement) => import_statement.to_string(),
+            Statement::While(while_statement) => format!(
+                "while {}:\n{}{}~",
+                while_statement.condition,
+                while_statement.body.pretty_print(depth + 1),
+                INDENTATION.repeat(depth)
+            ),
+            Statement::DoWhile(while_statement) => format!(
+                "do:\n{}{}~ while {}.",
+                while_statement.body.pretty_print(depth + 1),
+                INDENTATION.repeat(depth),
+                while_statement.condition
+            ),
+            Statement::Break | Statement::Continue => self.to_string(),
+        }
+    }
+}
+
+impl BlockStatement {
+    fn pretty_print(&self, depth: usize) -> String {
+        self.statements
+            .iter()
+            .map(|statement| {
+                format!(
+                    "{}{}\n",
+                    INDENTATION.repeat(depth),
+                    statement.pretty_print(depth)
+                )
+            })
+            .collect()
+    }
+}
+
+impl Expression {
+    fn pretty_print(&self, depth: usize) -> String {
+        let closing_indentation = INDENTATION.repeat(depth);
+
+        match self {
+            Expression::If(IfExpression {
+                condition,
+                consequence,
+                alternative,
+            }) => {
+                let mut printed =
+                    format!("if {condition}:\n{}", consequence.pretty_print(depth + 1));
+                if let Some(alternative) = alternative {
+                    printed.push_str(&format!(
+                        "{closing_indentation}else:\n{}",
+                        alternative.pretty_print(depth + 1)
+                    ));
+                }
+                printed.push_str(&format!("{closing_indentation}~"));
+                printed
+            }
+            Expression::Function(function_literal) => format!(
+                "fn({}):\n{}{closing_indentation}~",
+                format_parameters(
+                    &function_literal.parameters,
+                    &function_literal.defaults,
+                    &function_literal.rest_parameter
+                ),
+                function_literal.body.pretty_print(depth + 1)
+            ),
+            Expression::Block(block) => {
+                format!(
+                    "{{\n{}{closing_indentation}}}",
+                    block.pretty_print(depth + 1)
+                )
+            }
+            expression => expression.to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::test_util;
+
+    #[test]
+    fn pretty_print_test() {
+        let input = "
+            let add: fn(x, y):
+                let z: x + y.
+                if z > 2: return z else: return 2~
+            ~
+            add(1, 2)
+        ";
+
+        let expected = "\
+let add: fn(x, y):
+    let z: x + y.
+    if z > 2:
+        return z
+    else:
+        return 2
+    ~
+~.
+add(1, 2)
+";
+
+        let program = test_util::parse_program(input);
+        assert_eq!(expected, program.pretty_print());
+    }
+}
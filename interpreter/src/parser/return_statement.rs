@@ -10,27 +10,37 @@ use crate::{
 use super::Parser;
 
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct ReturnStatement {
-    pub return_value: Expression,
+    pub return_value: Option<Expression>,
 }
 
 impl ReturnStatement {
+    /// A bare `return.` has no value, and returns null.
     pub fn parse_return_statement(parser: &mut Parser) -> Result<Statement, ParseError> {
         parser.tokens.expect_token(Token::Return)?;
+        if parser.tokens.next_token_is(&Token::Period) {
+            parser.tokens.consume();
+            return Ok(Statement::Return(ReturnStatement { return_value: None }));
+        }
+
         let next_token = parser.tokens.expect()?;
         let expression = Expression::parse(parser, next_token, Precedence::Lowest)?;
 
         parser.tokens.expect_optional_token(Token::Period);
 
         Ok(Statement::Return(ReturnStatement {
-            return_value: expression,
+            return_value: Some(expression),
         }))
     }
 }
 
 impl Display for ReturnStatement {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "return {}", self.return_value)
+        match &self.return_value {
+            Some(return_value) => write!(f, "return {return_value}"),
+            None => write!(f, "return."),
+        }
     }
 }
 
@@ -46,13 +56,14 @@ mod tests {
         let source_code = "
             return 5.
             return foobar.
+            return.
         ";
 
         let statements = test_util::expect_parsed_program(source_code);
 
         assert_eq!(
             statements.len(),
-            2,
+            3,
             "Program should be parsed to 3 statements"
         );
 
@@ -60,15 +71,20 @@ mod tests {
         assert_eq!(
             first_statement,
             &Statement::Return(ReturnStatement {
-                return_value: Expression::IntegerLiteral(5)
+                return_value: Some(Expression::IntegerLiteral(5))
             })
         );
         let second_statement = statements.get(1).expect("Should get statement");
         assert_eq!(
             second_statement,
             &Statement::Return(ReturnStatement {
-                return_value: test_util::create_identifierliteral("foobar")
+                return_value: Some(test_util::create_identifierliteral("foobar"))
             })
         );
+        let third_statement = statements.get(2).expect("Should get statement");
+        assert_eq!(
+            third_statement,
+            &Statement::Return(ReturnStatement { return_value: None })
+        );
     }
 }
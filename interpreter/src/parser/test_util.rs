@@ -15,7 +15,7 @@ use crate::{
             expression::Expression, expression_statement::ExpressionStatement,
             functions::FunctionLiteral, if_expression::IfExpression,
         },
-        lexer::lexedtokens::LexedTokens,
+        lexer::{lexedtokens::LexedTokens, span::Span},
     },
 };
 
@@ -87,6 +87,7 @@ pub fn create_prefix_test_case(
         expression: Expression::Prefix {
             right: Box::new(right_expression),
             operator,
+            span: Span::default(),
         },
     })
 }
@@ -101,6 +102,7 @@ pub fn create_infix_test_case(
             left: Box::new(left_expression),
             right: Box::new(right_expression),
             operator,
+            span: Span::default(),
         },
     })
 }
@@ -112,6 +114,8 @@ pub fn create_function_expression(parameters: Vec<&str>, body: BlockStatement) -
                 .iter()
                 .map(|param| Identifier(param.to_string()))
                 .collect(),
+            defaults: Vec::new(),
+            rest_parameter: None,
             body,
         }),
     })
@@ -140,6 +144,7 @@ pub fn create_infix_expression(
         right: Box::from(right_expression),
         left: Box::from(left),
         operator,
+        span: Span::default(),
     }
 }
 
@@ -0,0 +1,139 @@
+use std::fmt::Display;
+
+use super::{
+    ast::{BlockStatement, Statement},
+    expressions::expression::Expression,
+    lexer::token::{Precedence, Token},
+    parse_errors::ParseError,
+    Parser,
+};
+
+#[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct WhileStatement {
+    pub condition: Expression,
+    pub body: BlockStatement,
+}
+
+impl WhileStatement {
+    pub fn parse(parser: &mut Parser) -> Result<Statement, ParseError> {
+        parser.tokens.expect_token(Token::While)?;
+
+        let next_token = parser.tokens.expect()?;
+        let condition = Expression::parse(parser, next_token, Precedence::Lowest)?;
+        parser.tokens.expect_token(Token::Assign)?;
+
+        let body = Expression::parse_blockstatement(parser)?;
+        parser.tokens.expect_token(Token::Lasagna)?;
+
+        Ok(Statement::While(WhileStatement { condition, body }))
+    }
+
+    /// `do: body~ while condition.` runs the body once before the condition is
+    /// checked for the first time.
+    pub fn parse_do_while(parser: &mut Parser) -> Result<Statement, ParseError> {
+        parser.tokens.expect_token(Token::Do)?;
+        parser.tokens.expect_token(Token::Assign)?;
+
+        let body = Expression::parse_blockstatement(parser)?;
+        parser.tokens.expect_token(Token::Lasagna)?;
+        parser.tokens.expect_token(Token::While)?;
+
+        let next_token = parser.tokens.expect()?;
+        let condition = Expression::parse(parser, next_token, Precedence::Lowest)?;
+        parser.tokens.expect_optional_token(Token::Period);
+
+        Ok(Statement::DoWhile(WhileStatement { condition, body }))
+    }
+
+    /// `break` and `continue` are single keywords, optionally ending with a period.
+    pub fn parse_loop_control(parser: &mut Parser) -> Result<Statement, ParseError> {
+        let statement = match parser.tokens.expect()? {
+            Token::Break => Statement::Break,
+            Token::Continue => Statement::Continue,
+            unexpected_token => {
+                return Err(ParseError::multiple_unexpected(
+                    Vec::from([Token::Break, Token::Continue]),
+                    Some(&unexpected_token),
+                ))
+            }
+        };
+
+        parser.tokens.expect_optional_token(Token::Period);
+        Ok(statement)
+    }
+}
+
+impl Display for WhileStatement {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "while ")?;
+        self.condition.fmt(f)?;
+        write!(f, ": ")?;
+        self.body.fmt(f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::{
+        ast::{BlockStatement, Operator, Statement},
+        expressions::{expression::Expression, expression_statement::ExpressionStatement},
+        test_util,
+        while_statement::WhileStatement,
+    };
+
+    #[test]
+    fn parse_while_statement_test() {
+        let statements = test_util::expect_parsed_program("while x < 10: break. continue~");
+
+        assert_eq!(
+            statements,
+            vec![Statement::While(WhileStatement {
+                condition: test_util::create_infix_expression(
+                    test_util::create_identifierliteral("x"),
+                    Expression::IntegerLiteral(10),
+                    Operator::LessThan,
+                ),
+                body: BlockStatement {
+                    statements: vec![Statement::Break, Statement::Continue],
+                },
+            })]
+        );
+    }
+
+    #[test]
+    fn parse_do_while_statement_test() {
+        let statements = test_util::expect_parsed_program("do: x. break~ while x < 10. x");
+
+        assert_eq!(
+            statements[0],
+            Statement::DoWhile(WhileStatement {
+                condition: test_util::create_infix_expression(
+                    test_util::create_identifierliteral("x"),
+                    Expression::IntegerLiteral(10),
+                    Operator::LessThan,
+                ),
+                body: BlockStatement {
+                    statements: vec![
+                        Statement::Expression(ExpressionStatement {
+                            expression: test_util::create_identifierliteral("x"),
+                        }),
+                        Statement::Break
+                    ],
+                },
+            })
+        );
+        assert_eq!(2, statements.len());
+
+        assert!(test_util::has_parser_errors(&test_util::parse_program(
+            "do: x~ x < 10."
+        )));
+    }
+
+    #[test]
+    fn parse_while_without_lasagna_should_fail_test() {
+        let program = test_util::parse_program("while true: x");
+
+        assert!(test_util::has_parser_errors(&program));
+    }
+}
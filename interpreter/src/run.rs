@@ -0,0 +1,183 @@
+use std::{error::Error, fmt::Display};
+
+use crate::{
+    eval::{
+        self,
+        eval_error::EvalError,
+        objects::{Environment, Object},
+        EvaledProgram,
+    },
+    parser::{
+        lexer::{
+            lexedtokens::{LexedTokens, Tokenizer},
+            span::Span,
+            token::Token,
+            trivia::TriviaMode,
+        },
+        parse_errors::StatementError,
+        ParsedProgram, Parser,
+    },
+};
+
+/// The stage of the pipeline that `run` stops after.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RunMode {
+    Tokens,
+    Ast,
+    Eval,
+}
+
+#[derive(Debug)]
+pub enum RunOutput {
+    Tokens(Vec<Token>),
+    Ast(String),
+    Eval(Object),
+}
+
+#[derive(Debug)]
+pub enum RunError {
+    /// Tokens the lexer could not make sense of, with where they were found.
+    LexError(Vec<(Token, Span)>),
+    ParseError(Vec<StatementError>),
+    EvalError(EvalError),
+}
+
+/// Runs the source through the pipeline up to the stage of the mode, and
+/// returns what that stage produced: the lexed tokens, the parsed program as
+/// displayed, or the object the program evaluates to in a new environment.
+pub fn run(source: &str, mode: RunMode) -> Result<RunOutput, RunError> {
+    match mode {
+        RunMode::Tokens => lex(source).map(RunOutput::Tokens),
+        RunMode::Ast => match Parser::parse_tokens(LexedTokens::from(source)) {
+            ParsedProgram::InvalidProgram(parse_errors) => Err(RunError::ParseError(parse_errors)),
+            valid_program => Ok(RunOutput::Ast(valid_program.to_string())),
+        },
+        RunMode::Eval => match eval::eval(source, &mut Environment::new_env_reference()) {
+            EvaledProgram::Valid(object) => Ok(RunOutput::Eval(object)),
+            EvaledProgram::ParseError(parse_errors) => Err(RunError::ParseError(parse_errors)),
+            EvaledProgram::EvalError(eval_error) => Err(RunError::EvalError(eval_error)),
+        },
+    }
+}
+
+fn lex(source: &str) -> Result<Vec<Token>, RunError> {
+    let mut tokens = Vec::new();
+    let mut lex_errors = Vec::new();
+
+    let mut tokenizer = Tokenizer::new(source, TriviaMode::Discard);
+    while let Some((token, span)) = tokenizer.next_spanned() {
+        match token {
+            Token::Illegal
+            | Token::InvalidEscape(_)
+            | Token::InvalidDigitSeparator(_)
+            | Token::UnterminatedComment => lex_errors.push((token, span)),
+            token => tokens.push(token),
+        }
+    }
+
+    match lex_errors.is_empty() {
+        true => Ok(tokens),
+        false => Err(RunError::LexError(lex_errors)),
+    }
+}
+
+impl Display for RunOutput {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RunOutput::Tokens(tokens) => tokens.iter().try_for_each(|token| writeln!(f, "{token}")),
+            RunOutput::Ast(program) => write!(f, "{program}"),
+            RunOutput::Eval(object) => writeln!(f, "{object}"),
+        }
+    }
+}
+
+impl Display for RunError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RunError::LexError(lex_errors) => {
+                writeln!(f, "Found lex errors:")?;
+                lex_errors
+                    .iter()
+                    .try_for_each(|(token, span)| writeln!(f, "  {token} at {span}"))
+            }
+            RunError::ParseError(parse_errors) => {
+                writeln!(f, "Found parse errors:")?;
+                parse_errors
+                    .iter()
+                    .try_for_each(|parse_error| writeln!(f, "  {parse_error}"))
+            }
+            RunError::EvalError(eval_error) => writeln!(f, "Runtime error: {eval_error}"),
+        }
+    }
+}
+
+impl Error for RunError {}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        eval::{eval_error::EvalError, objects::Object},
+        parser::lexer::token::Token,
+        run::{run, RunError, RunMode, RunOutput},
+    };
+
+    const PROGRAM: &str = "let x: 2. x * 3";
+
+    #[test]
+    fn run_tokens_test() {
+        match run(PROGRAM, RunMode::Tokens) {
+            Ok(RunOutput::Tokens(tokens)) => assert_eq!(
+                vec![
+                    Token::Let,
+                    Token::Ident(String::from("x")),
+                    Token::Assign,
+                    Token::Int(String::from("2")),
+                    Token::Period,
+                    Token::Ident(String::from("x")),
+                    Token::Asterix,
+                    Token::Int(String::from("3")),
+                ],
+                tokens
+            ),
+            other => panic!("Expected tokens, got {other:?}"),
+        }
+
+        match run("x @ 1 /* open", RunMode::Tokens) {
+            Err(RunError::LexError(lex_errors)) => {
+                let tokens: Vec<Token> = lex_errors.into_iter().map(|(token, _)| token).collect();
+                assert_eq!(vec![Token::Illegal, Token::UnterminatedComment], tokens);
+            }
+            other => panic!("Expected lex errors, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn run_ast_test() {
+        match run(PROGRAM, RunMode::Ast) {
+            Ok(RunOutput::Ast(program)) => assert_eq!("let x: 2.\nx * 3\n", program),
+            other => panic!("Expected program, got {other:?}"),
+        }
+
+        assert!(matches!(
+            run("let x 2.", RunMode::Ast),
+            Err(RunError::ParseError(parse_errors)) if parse_errors.len() == 1
+        ));
+    }
+
+    #[test]
+    fn run_eval_test() {
+        assert!(matches!(
+            run(PROGRAM, RunMode::Eval),
+            Ok(RunOutput::Eval(Object::Integer(6)))
+        ));
+
+        assert!(matches!(
+            run("1 / 0", RunMode::Eval),
+            Err(RunError::EvalError(EvalError::DivisionByZero(_)))
+        ));
+        assert!(matches!(
+            run("let x 2.", RunMode::Eval),
+            Err(RunError::ParseError(_))
+        ));
+    }
+}
@@ -1,17 +1,106 @@
-use std::io::{stdin, stdout, Write};
+use std::{
+    env,
+    io::{self, stderr, stdin, stdout, Read, Write},
+    process::ExitCode,
+    thread,
+};
 
-use interpreter::eval::{self, objects::Environment, EvaledProgram};
+use interpreter::{
+    eval::{
+        self,
+        objects::{Environment, Object},
+        EvaledProgram,
+    },
+    run::{self, RunMode},
+};
 use tracing_subscriber::FmtSubscriber;
 
-fn main() -> Result<(), std::io::Error> {
+/// Evaluation is recursive, so the REPL runs on a thread with a stack large
+/// enough to reach its recursion limit before overflowing.
+const REPL_STACK_SIZE: usize = 64 * 1024 * 1024;
+
+/// The REPL has a larger stack than the interpreter's default recursion limit
+/// is made for, so programs typed into it can recurse deeper.
+const REPL_MAX_CALL_DEPTH: usize = 1000;
+
+fn main() -> Result<ExitCode, std::io::Error> {
     let subscriber = FmtSubscriber::builder()
         .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
         .finish();
 
     tracing::subscriber::set_global_default(subscriber).expect("setting default subscriber failed");
 
+    let dump_mode = match parse_dump_mode(env::args().skip(1)) {
+        Ok(dump_mode) => dump_mode,
+        Err(usage) => {
+            eprintln!("{usage}");
+            return Ok(ExitCode::FAILURE);
+        }
+    };
+
+    let repl =
+        thread::Builder::new()
+            .stack_size(REPL_STACK_SIZE)
+            .spawn(move || match dump_mode {
+                Some(mode) => run_dump(mode),
+                None => run_repl().map(|_| ExitCode::SUCCESS),
+            })?;
+
+    match repl.join() {
+        Ok(result) => result,
+        Err(_) => panic!("REPL thread panicked"),
+    }
+}
+
+/// `--dump <tokens|ast|eval>` runs the program read from stdin up to the
+/// given stage instead of starting the REPL.
+fn parse_dump_mode(mut args: impl Iterator<Item = String>) -> Result<Option<RunMode>, String> {
+    const USAGE: &str = "usage: repl [--dump <tokens|ast|eval>]";
+
+    let Some(flag) = args.next() else {
+        return Ok(None);
+    };
+    if flag != "--dump" {
+        return Err(format!("unknown argument '{flag}'\n{USAGE}"));
+    }
+
+    let mode = match args.next().as_deref() {
+        Some("tokens") => RunMode::Tokens,
+        Some("ast") => RunMode::Ast,
+        Some("eval") => RunMode::Eval,
+        _ => return Err(String::from(USAGE)),
+    };
+
+    match args.next() {
+        Some(extra) => Err(format!("unknown argument '{extra}'\n{USAGE}")),
+        None => Ok(Some(mode)),
+    }
+}
+
+fn run_dump(mode: RunMode) -> Result<ExitCode, std::io::Error> {
+    let mut source = String::new();
+    stdin().read_to_string(&mut source)?;
+
+    match run::run(&source, mode) {
+        Ok(output) => {
+            print!("{output}");
+            Ok(ExitCode::SUCCESS)
+        }
+        Err(run_error) => {
+            eprint!("{run_error}");
+            Ok(ExitCode::FAILURE)
+        }
+    }
+}
+
+fn run_repl() -> Result<(), std::io::Error> {
     println!("Welcome to lasagnalang, try and write some code:");
     let repl_scope = &mut Environment::new_env_reference();
+    repl_scope
+        .borrow()
+        .context()
+        .borrow_mut()
+        .set_max_call_depth(REPL_MAX_CALL_DEPTH);
 
     loop {
         let mut buffer = String::new();
@@ -22,22 +111,95 @@ fn main() -> Result<(), std::io::Error> {
         match stdin().read_line(&mut buffer) {
             Ok(_) => {
                 let input = buffer.trim_end();
-                let evaluated_output = eval::eval(input, repl_scope);
-
-                match evaluated_output {
-                    EvaledProgram::Valid(object) => println!("{object}"),
-                    EvaledProgram::ParseError(parse_errors) => {
-                        eprintln!("Found parse errors:");
-                        parse_errors.into_iter().for_each(|error| {
-                            eprintln!("{error}");
-                        });
-                    }
-                    EvaledProgram::EvalError(runtime_error) => {
-                        eprintln!("Runtime error: {runtime_error}")
+                if input == ":env" {
+                    for (identifier, object) in repl_scope.borrow().dump() {
+                        println!("{identifier}: {object}");
                     }
+                    continue;
                 }
+
+                let evaluated_output = eval::eval_printable(input, repl_scope);
+
+                print_evaluation(evaluated_output, &mut stdout(), &mut stderr())?;
             }
             Err(_) => panic!(),
         }
     }
 }
+
+/// Only the value of a trailing expression statement is printed, so
+/// assignments and other statements print nothing.
+fn print_evaluation(
+    evaluated_output: EvaledProgram,
+    output: &mut impl Write,
+    errors: &mut impl Write,
+) -> Result<(), io::Error> {
+    match evaluated_output {
+        EvaledProgram::Valid(Object::Void) => Ok(()),
+        EvaledProgram::Valid(object) => writeln!(output, "{object}"),
+        EvaledProgram::ParseError(parse_errors) => {
+            writeln!(errors, "Found parse errors:")?;
+            parse_errors
+                .into_iter()
+                .try_for_each(|error| writeln!(errors, "{error}"))
+        }
+        EvaledProgram::EvalError(runtime_error) => {
+            writeln!(errors, "Runtime error: {runtime_error}")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use interpreter::{
+        eval::{self, objects::Environment},
+        run::RunMode,
+    };
+
+    use crate::{parse_dump_mode, print_evaluation};
+
+    fn args(args: &[&str]) -> impl Iterator<Item = String> {
+        args.iter()
+            .map(|arg| arg.to_string())
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+
+    #[test]
+    fn prints_only_expression_statements_test() {
+        let repl_scope = &mut Environment::new_env_reference();
+        let mut output = Vec::new();
+        let mut errors = Vec::new();
+
+        for input in ["5 + 5", "let x: 5.", "x"] {
+            let evaluated_output = eval::eval_printable(input, repl_scope);
+            print_evaluation(evaluated_output, &mut output, &mut errors)
+                .expect("Should write output");
+        }
+
+        assert_eq!("10\n5\n", String::from_utf8_lossy(&output));
+        assert!(errors.is_empty());
+    }
+
+    #[test]
+    fn parse_dump_mode_test() {
+        assert_eq!(Ok(None), parse_dump_mode(args(&[])));
+        assert_eq!(
+            Ok(Some(RunMode::Tokens)),
+            parse_dump_mode(args(&["--dump", "tokens"]))
+        );
+        assert_eq!(
+            Ok(Some(RunMode::Ast)),
+            parse_dump_mode(args(&["--dump", "ast"]))
+        );
+        assert_eq!(
+            Ok(Some(RunMode::Eval)),
+            parse_dump_mode(args(&["--dump", "eval"]))
+        );
+
+        assert!(parse_dump_mode(args(&["--dump"])).is_err());
+        assert!(parse_dump_mode(args(&["--dump", "bytes"])).is_err());
+        assert!(parse_dump_mode(args(&["--verbose"])).is_err());
+        assert!(parse_dump_mode(args(&["--dump", "ast", "extra"])).is_err());
+    }
+}